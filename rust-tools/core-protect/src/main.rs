@@ -1,8 +1,9 @@
 use std::env;
 use std::fs;
 use std::io::{self, Write};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::{self, Command, Stdio};
+use faelight_zone::LockState;
 
 const VERSION: &str = "1.0.1";
 
@@ -25,7 +26,10 @@ fn main() {
     }
     
     match args[1].as_str() {
-        "lock" => cmd_lock(&core_dir),
+        "lock" => {
+            let reason = args[2..].join(" ");
+            cmd_lock(&core_dir, Some(&reason));
+        }
         "unlock" => cmd_unlock(&core_dir),
         "status" => cmd_status(&core_dir),
         "edit" => {
@@ -45,7 +49,7 @@ fn main() {
     }
 }
 
-fn cmd_health(core_dir: &PathBuf) {
+fn cmd_health(core_dir: &Path) {
     println!();
     println!("{}🏥 core-protect v{} - Health Check{}", CYAN, VERSION, NC);
     println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
@@ -92,20 +96,10 @@ fn cmd_health(core_dir: &PathBuf) {
     
     // Check current protection status
     print!("  Checking protection status... ");
-    let output = Command::new("lsattr")
-        .arg("-d")
-        .arg(core_dir)
-        .output();
-    
-    if let Ok(o) = output {
-        let stdout = String::from_utf8_lossy(&o.stdout);
-        if stdout.contains('i') {
-            println!("{}🔒 LOCKED{}", GREEN, NC);
-        } else {
-            println!("{}🔓 UNLOCKED{}", YELLOW, NC);
-        }
+    if LockState::read(&home_of(core_dir)).locked {
+        println!("{}🔒 LOCKED{}", GREEN, NC);
     } else {
-        println!("{}❓ Unknown{}", YELLOW, NC);
+        println!("{}🔓 UNLOCKED{}", YELLOW, NC);
     }
     
     println!();
@@ -118,7 +112,11 @@ fn cmd_health(core_dir: &PathBuf) {
     }
 }
 
-fn cmd_lock(core_dir: &PathBuf) {
+fn home_of(core_dir: &Path) -> PathBuf {
+    core_dir.parent().unwrap_or(core_dir).to_path_buf()
+}
+
+fn cmd_lock(core_dir: &Path, reason: Option<&str>) {
     println!("🔒 Locking 0-core (immutable protection)...");
     
     // Lock all items in core_dir (silently skip unsupported files)
@@ -143,10 +141,20 @@ fn cmd_lock(core_dir: &PathBuf) {
         .status()
         .ok();
     
+    // Every tool reads the attribute, so only describe a lock that took
+    let home = home_of(core_dir);
+    if !LockState::read(&home).locked {
+        eprintln!("{}❌ Could not set the immutable attribute on {}{}", RED, core_dir.display(), NC);
+        process::exit(1);
+    }
+    if let Err(e) = LockState::record(&home, reason) {
+        eprintln!("{}⚠️  Could not record lock details: {}{}", YELLOW, e, NC);
+    }
+    
     println!("{}✅ Core protected! Cannot modify without unlocking.{}", GREEN, NC);
 }
 
-fn cmd_unlock(core_dir: &PathBuf) {
+fn cmd_unlock(core_dir: &Path) {
     println!("🔓 Unlocking 0-core for editing...");
     
     // Unlock all items first (silently skip unsupported files)
@@ -171,30 +179,35 @@ fn cmd_unlock(core_dir: &PathBuf) {
         .status()
         .ok();
     
+    let home = home_of(core_dir);
+    if LockState::read(&home).locked {
+        eprintln!("{}❌ Could not clear the immutable attribute on {}{}", RED, core_dir.display(), NC);
+        process::exit(1);
+    }
+    if let Err(e) = LockState::clear(&home) {
+        eprintln!("{}⚠️  Could not remove lock details: {}{}", YELLOW, e, NC);
+    }
+    
     println!("{}✅ Core unlocked! You can now edit.{}", GREEN, NC);
 }
 
-fn cmd_status(core_dir: &PathBuf) {
+fn cmd_status(core_dir: &Path) {
     println!("📊 Checking 0-core protection status...");
     
-    let output = Command::new("lsattr")
-        .arg("-d")
-        .arg(core_dir)
-        .output();
+    let state = LockState::read(&home_of(core_dir));
+    if !state.locked {
+        println!("🔓 Core is UNLOCKED (editable)");
+        return;
+    }
     
-    if let Ok(o) = output {
-        let stdout = String::from_utf8_lossy(&o.stdout);
-        if stdout.contains('i') {
-            println!("🔒 Core is LOCKED (immutable)");
-        } else {
-            println!("🔓 Core is UNLOCKED (editable)");
-        }
-    } else {
-        println!("❓ Could not determine status");
+    println!("🔒 Core is LOCKED (immutable)");
+    match state.info {
+        Some(info) => println!("   Locked {}", info),
+        None => println!("   Locked outside core-protect, no details recorded"),
     }
 }
 
-fn cmd_edit(core_dir: &PathBuf, package: &str) {
+fn cmd_edit(core_dir: &Path, package: &str) {
     let pkg_dir = core_dir.join(package);
     
     if !pkg_dir.exists() {
@@ -222,12 +235,12 @@ fn cmd_edit(core_dir: &PathBuf, package: &str) {
         .ok();
     
     println!("🔒 Re-locking core...");
-    cmd_lock(core_dir);
+    cmd_lock(core_dir, Some(&format!("after editing {}", package)));
     
     println!("{}✅ Edits complete, core re-locked!{}", GREEN, NC);
 }
 
-fn get_blast_radius(core_dir: &PathBuf, package: &str) -> String {
+fn get_blast_radius(core_dir: &Path, package: &str) -> String {
     let dotmeta = core_dir.join(package).join(".dotmeta");
     
     if let Ok(content) = fs::read_to_string(&dotmeta) {
@@ -246,7 +259,7 @@ fn get_blast_radius(core_dir: &PathBuf, package: &str) -> String {
     "unknown".to_string()
 }
 
-fn get_failure_modes(core_dir: &PathBuf, package: &str) -> Vec<String> {
+fn get_failure_modes(core_dir: &Path, package: &str) -> Vec<String> {
     let dotmeta = core_dir.join(package).join(".dotmeta");
     let mut modes = vec![];
     
@@ -285,7 +298,7 @@ fn prompt(msg: &str) -> String {
     input.trim().to_string()
 }
 
-fn show_blast_warning(core_dir: &PathBuf, package: &str, blast_radius: &str) -> bool {
+fn show_blast_warning(core_dir: &Path, package: &str, blast_radius: &str) -> bool {
     let failure_modes = get_failure_modes(core_dir, package);
     
     match blast_radius {
@@ -354,7 +367,7 @@ fn show_blast_warning(core_dir: &PathBuf, package: &str, blast_radius: &str) ->
     true
 }
 
-fn create_backup(core_dir: &PathBuf, package: &str, blast_radius: &str) {
+fn create_backup(core_dir: &Path, package: &str, blast_radius: &str) {
     if blast_radius == "critical" || blast_radius == "high" {
         println!("💾 Creating backup...");
         
@@ -387,7 +400,7 @@ fn show_help() {
     println!("  core-protect <command>");
     println!();
     println!("COMMANDS:");
-    println!("  lock              Lock 0-core (prevent changes)");
+    println!("  lock [reason]     Lock 0-core (prevent changes)");
     println!("  unlock            Unlock 0-core (allow changes)");
     println!("  status            Check protection status");
     println!("  edit <package>    Unlock, edit, re-lock (with blast radius check)");
@@ -396,7 +409,7 @@ fn show_help() {
     println!("  --help, -h        Show this help");
     println!();
     println!("EXAMPLES:");
-    println!("  core-protect lock");
+    println!("  core-protect lock \"release freeze\"");
     println!("  core-protect edit shell-zsh");
     println!("  core-protect status");
     println!("  core-protect --health");
@@ -87,8 +87,8 @@ pub fn render(canvas: &mut [u8], width: u32, _height: u32) -> Vec<(i32, i32, Str
     draw_text(&mut cache, canvas, width, &health_text, x_pos, 8, health_color);
     x_pos += 60;
     
-    // Lock status
-    let locked = is_core_locked();
+    // Lock status (shared with fm and faelight-git)
+    let locked = faelight_zone::is_core_locked();
     let lock_color = if locked { ACCENT_COLOR } else { AMBER_COLOR };
     let lock_icon = if locked { ICON_LOCKED } else { ICON_UNLOCKED };
     draw_text(&mut cache, canvas, width, lock_icon, x_pos, 8, lock_color);
@@ -342,23 +342,6 @@ fn get_health() -> u8 {
     ((passed * 100) / total) as u8
 }
 
-fn is_core_locked() -> bool {
-    let home = env::var("HOME").unwrap_or_default();
-    let core_path = format!("{}/0-core", home);
-    
-    match Command::new("lsattr").args(["-d", &core_path]).output() {
-        Ok(out) => {
-            let result = String::from_utf8_lossy(&out.stdout);
-            result
-                .split_whitespace()
-                .next()
-                .map(|attrs| attrs.contains('i'))
-                .unwrap_or(false)
-        }
-        Err(_) => false,
-    }
-}
-
 fn sway_query(cmd: &str) -> Option<String> {
     Command::new("swaymsg")
         .args(["-t", cmd, "-r"])
@@ -1,12 +1,12 @@
 //! Test client for faelight-daemon
 use tokio::net::UnixStream;
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
-use serde_json;
 
 #[path = "../protocol/mod.rs"]
+#[allow(dead_code)]
 mod protocol;
 
-use protocol::{Message, MessagePayload, Command, Response};
+use protocol::{Message, MessagePayload, Command, Response, Event, PROTOCOL_VERSION};
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -18,6 +18,15 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     
     println!("✅ Connected!");
     
+    // Test 0: Handshake
+    println!("\n🤝 Test 0: Hello (protocol v{})", PROTOCOL_VERSION);
+    send_command(&mut writer, 100, Command::Hello {
+        version: PROTOCOL_VERSION,
+        capabilities: Vec::new(),
+    }).await?;
+    let response = read_response(&mut reader).await?;
+    println!("Response: {:?}", response);
+    
     // Test 1: Ping
     println!("\n📡 Test 1: Ping");
     send_command(&mut writer, 1, Command::Ping).await?;
@@ -43,13 +52,71 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Test 3: Preview
     println!("\n📖 Test 3: Preview /etc/hostname");
     send_command(&mut writer, 3, Command::Preview {
-        path: "/etc/hostname".to_string()
+        path: "/etc/hostname".to_string(),
+        max_lines: Some(10),
     }).await?;
     let response = read_response(&mut reader).await?;
-    if let Response::Preview { content } = response {
+    if let Response::Preview { content, preview } = response {
         println!("Content: {}", content.trim());
+        if let Some(preview) = preview {
+            println!("Kind: {:?}, {} bytes, {} highlights",
+                preview.kind, preview.size, preview.highlights.len());
+        }
+    }
+    
+    // Test 4: Search
+    println!("\n🔍 Test 4: Search for 'daemon'");
+    send_command(&mut writer, 4, Command::Search {
+        query: "daemon".to_string(),
+        limit: Some(5),
+    }).await?;
+    let response = read_response(&mut reader).await?;
+    if let Response::SearchResults { results } = response {
+        println!("Found {} matches:", results.len());
+        for hit in &results {
+            println!("  {:>4} {}", hit.score, hit.entry.path);
+        }
+    }
+    
+    // Test 5: Git status
+    println!("\n🌿 Test 5: Git status for current directory");
+    let cwd = std::env::current_dir()?.to_string_lossy().to_string();
+    send_command(&mut writer, 5, Command::GitStatus { path: cwd }).await?;
+    let response = read_response(&mut reader).await?;
+    match response {
+        Response::GitStatus { repo_root, files } => {
+            println!("Repo: {} ({} changed files)", repo_root, files.len());
+            for (path, status) in files.iter().take(5) {
+                println!("  {:?}/{:?} {}", status.index, status.worktree, path);
+            }
+        }
+        Response::Error { code, message } => println!("Error ({:?}): {}", code, message),
+        _ => {}
     }
     
+    // Test 6: Stats
+    println!("\n📊 Test 6: Stats");
+    send_command(&mut writer, 6, Command::Stats).await?;
+    let response = read_response(&mut reader).await?;
+    if let Response::Stats { stats } = response {
+        println!("Uptime: {}s, clients: {}, requests: {}, indexed: {}",
+            stats.uptime_secs, stats.clients, stats.requests, stats.index_entries);
+    }
+    
+    // Test 7: Watch
+    println!("\n👀 Test 7: Watch /tmp for changes");
+    send_command(&mut writer, 7, Command::Watch {
+        path: "/tmp".to_string()
+    }).await?;
+    let response = read_response(&mut reader).await?;
+    println!("Response: {:?}", response);
+    
+    let probe = std::path::Path::new("/tmp/faelight-daemon-watch-test");
+    std::fs::write(probe, "probe")?;
+    let event = read_event(&mut reader).await?;
+    println!("Event: {:?} {}", event.kind, event.path);
+    std::fs::remove_file(probe)?;
+    
     println!("\n✨ All tests passed!");
     Ok(())
 }
@@ -82,3 +149,16 @@ async fn read_response(
         _ => Err("Expected response".into()),
     }
 }
+
+async fn read_event(
+    reader: &mut BufReader<tokio::net::unix::OwnedReadHalf>
+) -> Result<Event, Box<dyn std::error::Error>> {
+    let mut line = String::new();
+    reader.read_line(&mut line).await?;
+    let msg: Message = serde_json::from_str(&line)?;
+    
+    match msg.payload {
+        MessagePayload::Event(event) => Ok(event),
+        _ => Err("Expected event".into()),
+    }
+}
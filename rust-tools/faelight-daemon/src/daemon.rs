@@ -1,128 +1,456 @@
 //! The faelight daemon server
 use tokio::net::{UnixListener, UnixStream};
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
-use std::path::Path;
-use crate::protocol::{Message, MessagePayload, Command, Response, Entry};
+use tokio::sync::{mpsc, Semaphore};
+use std::path::{Path, PathBuf};
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+use crate::protocol::{
+    Message, MessagePayload, Command, Response, Entry, ErrorCode, DaemonStats, CAPABILITIES,
+    PROTOCOL_VERSION,
+};
+use crate::index::{FileIndex, DEFAULT_SEARCH_LIMIT};
+use crate::watch::{self, ClientId, Outbox, WatchHub};
+use crate::git;
+use crate::ops;
+use crate::preview;
+
+/// How often the index checks for changed directories
+const INDEX_REFRESH_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Requests a single connection may have in flight at once
+const MAX_IN_FLIGHT: usize = 16;
+
+/// How often an idle daemon checks whether it may exit
+const IDLE_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// State shared by every client connection
+struct State {
+    index: RwLock<FileIndex>,
+    watches: WatchHub,
+    next_client: AtomicU64,
+    started: Instant,
+    socket_activated: bool,
+    /// Currently connected clients
+    clients: AtomicUsize,
+    /// Last connect, disconnect or request
+    last_active: Mutex<Instant>,
+    requests: Mutex<BTreeMap<String, u64>>,
+}
+
+impl State {
+    fn new(index: FileIndex, watches: WatchHub, socket_activated: bool) -> Self {
+        Self {
+            index: RwLock::new(index),
+            watches,
+            next_client: AtomicU64::new(1),
+            started: Instant::now(),
+            socket_activated,
+            clients: AtomicUsize::new(0),
+            last_active: Mutex::new(Instant::now()),
+            requests: Mutex::new(BTreeMap::new()),
+        }
+    }
+    
+    fn touch(&self) {
+        if let Ok(mut last) = self.last_active.lock() {
+            *last = Instant::now();
+        }
+    }
+    
+    fn count_request(&self, cmd: &Command) {
+        if let Ok(mut requests) = self.requests.lock() {
+            *requests.entry(cmd.name().to_string()).or_default() += 1;
+        }
+        self.touch();
+    }
+    
+    /// No clients connected and nothing happened for `timeout`
+    fn is_idle(&self, timeout: Duration) -> bool {
+        self.clients.load(Ordering::Relaxed) == 0
+            && self.last_active.lock().map(|last| last.elapsed() >= timeout).unwrap_or(false)
+    }
+    
+    fn stats(&self) -> DaemonStats {
+        let requests_by_command = self.requests.lock().map(|r| r.clone()).unwrap_or_default();
+        
+        DaemonStats {
+            uptime_secs: self.started.elapsed().as_secs(),
+            clients: self.clients.load(Ordering::Relaxed),
+            requests: requests_by_command.values().sum(),
+            requests_by_command,
+            index_entries: self.index.read().map(|i| i.len()).unwrap_or(0),
+            watched_dirs: self.watches.len(),
+            socket_activated: self.socket_activated,
+        }
+    }
+}
+
+/// Per-connection context handed to command processing
+#[derive(Clone)]
+struct Client {
+    id: ClientId,
+    outbox: Outbox,
+}
 
 pub struct Daemon {
     socket_path: String,
+    idle_timeout: Option<Duration>,
+    /// Socket handed over by systemd
+    inherited: Option<std::os::unix::net::UnixListener>,
 }
 
 impl Daemon {
     pub fn new(socket_path: String) -> Self {
-        Self { socket_path }
+        Self {
+            socket_path,
+            idle_timeout: None,
+            inherited: None,
+        }
     }
     
-    pub async fn run(&self) -> Result<(), Box<dyn std::error::Error>> {
-        // Remove old socket if exists
-        let path = Path::new(&self.socket_path);
-        if path.exists() {
-            std::fs::remove_file(path)?;
-        }
+    /// Serve on a socket systemd passed in instead of binding our own
+    pub fn with_listener(mut self, listener: Option<std::os::unix::net::UnixListener>) -> Self {
+        self.inherited = listener;
+        self
+    }
+    
+    /// Exit once no client has been connected for `timeout`
+    pub fn with_idle_timeout(mut self, timeout: Duration) -> Self {
+        self.idle_timeout = Some(timeout);
+        self
+    }
+    
+    pub async fn run(self) -> Result<(), Box<dyn std::error::Error>> {
+        let home = std::env::var("HOME")
+            .map(PathBuf::from)
+            .unwrap_or_else(|_| PathBuf::from("/home"));
         
-        // Bind to Unix socket
-        let listener = UnixListener::bind(&self.socket_path)?;
-        println!("🌲 faelight-daemon listening on {}", self.socket_path);
+        // Under systemd the socket already exists and outlives us
+        let (listener, socket_activated) = match self.inherited {
+            Some(std_listener) => {
+                std_listener.set_nonblocking(true)?;
+                println!("🌲 faelight-daemon socket-activated");
+                (UnixListener::from_std(std_listener)?, true)
+            }
+            None => {
+                // Remove old socket if exists
+                let path = Path::new(&self.socket_path);
+                if path.exists() {
+                    std::fs::remove_file(path)?;
+                }
+                
+                // Bind to Unix socket
+                let listener = UnixListener::bind(&self.socket_path)?;
+                println!("🌲 faelight-daemon listening on {}", self.socket_path);
+                (listener, false)
+            }
+        };
+        
+        let (watches, fs_events) = WatchHub::new()?;
+        let index = FileIndex::new(FileIndex::zone_roots(&home));
+        let state = Arc::new(State::new(index, watches, socket_activated));
+        
+        spawn_indexer(state.clone());
+        spawn_watch_dispatcher(state.clone(), fs_events);
+        
+        let mut idle_check = tokio::time::interval(IDLE_CHECK_INTERVAL);
         
         loop {
-            match listener.accept().await {
-                Ok((stream, _)) => {
-                    tokio::spawn(async move {
-                        if let Err(e) = handle_client(stream).await {
-                            eprintln!("Error handling client: {}", e);
-                        }
-                    });
-                }
-                Err(e) => {
-                    eprintln!("Accept error: {}", e);
+            tokio::select! {
+                accepted = listener.accept() => match accepted {
+                    Ok((stream, _)) => {
+                        let state = state.clone();
+                        tokio::spawn(async move {
+                            if let Err(e) = handle_client(stream, state).await {
+                                eprintln!("Error handling client: {}", e);
+                            }
+                        });
+                    }
+                    Err(e) => {
+                        eprintln!("Accept error: {}", e);
+                    }
+                },
+                _ = idle_check.tick(), if self.idle_timeout.is_some() => {
+                    if self.idle_timeout.is_some_and(|timeout| state.is_idle(timeout)) {
+                        println!("🌲 Idle, shutting down");
+                        break;
+                    }
                 }
             }
         }
+        
+        // systemd keeps listening and starts us again on the next connection
+        if !socket_activated {
+            let _ = std::fs::remove_file(&self.socket_path);
+        }
+        
+        Ok(())
+    }
+}
+
+/// Build the index in the background, then keep it fresh incrementally
+fn spawn_indexer(state: Arc<State>) {
+    tokio::spawn(async move {
+        let build_state = state.clone();
+        let _ = tokio::task::spawn_blocking(move || {
+            if let Some(entries) = rebuild_index(&build_state, true) {
+                println!("🌲 Indexed {} entries", entries);
+            }
+        }).await;
+        
+        let mut interval = tokio::time::interval(INDEX_REFRESH_INTERVAL);
+        interval.tick().await;
+        
+        loop {
+            interval.tick().await;
+            let refresh_state = state.clone();
+            let _ = tokio::task::spawn_blocking(move || {
+                rebuild_index(&refresh_state, false);
+            }).await;
+        }
+    });
+}
+
+/// Build (`full`) or refresh a copy of the index outside the lock, so
+/// searches and watch events go on meanwhile, then swap it in with those
+/// events replayed. Returns the number of entries.
+fn rebuild_index(state: &State, full: bool) -> Option<usize> {
+    let mut fresh = {
+        let mut index = state.index.write().ok()?;
+        index.start_replay();
+        if full { FileIndex::new(index.roots().to_vec()) } else { index.clone() }
+    };
+    
+    if full {
+        fresh.build();
+    } else {
+        fresh.refresh();
     }
+    
+    let mut index = state.index.write().ok()?;
+    index.replace(fresh);
+    Some(index.len())
 }
 
-async fn handle_client(stream: UnixStream) -> Result<(), Box<dyn std::error::Error>> {
+/// Fan filesystem events out to subscribers and into the index
+fn spawn_watch_dispatcher(
+    state: Arc<State>,
+    fs_events: mpsc::UnboundedReceiver<notify::Event>,
+) {
+    tokio::spawn(async move {
+        watch::dispatch(&state.watches, fs_events, |path| {
+            if let Ok(mut index) = state.index.write() {
+                index.update_path(path);
+            }
+        }).await;
+    });
+}
+
+async fn handle_client(stream: UnixStream, state: Arc<State>) -> Result<(), Box<dyn std::error::Error>> {
     let (reader, mut writer) = stream.into_split();
     let mut reader = BufReader::new(reader);
     let mut line = String::new();
     
-    loop {
+    // Responses and pushed events share one ordered outgoing queue
+    let (outbox, mut outgoing) = mpsc::unbounded_channel::<Message>();
+    let writer_task = tokio::spawn(async move {
+        while let Some(msg) = outgoing.recv().await {
+            let Ok(json) = serde_json::to_string(&msg) else {
+                continue;
+            };
+            if writer.write_all(json.as_bytes()).await.is_err()
+                || writer.write_all(b"\n").await.is_err()
+                || writer.flush().await.is_err()
+            {
+                break;
+            }
+        }
+    });
+    
+    let client = Client {
+        id: state.next_client.fetch_add(1, Ordering::Relaxed),
+        outbox,
+    };
+    state.clients.fetch_add(1, Ordering::Relaxed);
+    state.touch();
+    
+    // Requests run concurrently; clients match responses by id
+    let in_flight = Arc::new(Semaphore::new(MAX_IN_FLIGHT));
+    
+    let result: std::io::Result<()> = loop {
         line.clear();
-        let n = reader.read_line(&mut line).await?;
+        let n = match reader.read_line(&mut line).await {
+            Ok(n) => n,
+            Err(e) => break Err(e),
+        };
         
         if n == 0 {
             // Connection closed
-            break;
+            break Ok(());
         }
         
-        // Parse message
-        let message: Message = match serde_json::from_str(&line) {
-            Ok(msg) => msg,
-            Err(e) => {
-                eprintln!("Failed to parse message: {}", e);
+        let (id, payload) = match parse_message(&line) {
+            Ok(parsed) => parsed,
+            Err((id, response)) => {
+                let _ = client.outbox.send(Message {
+                    id,
+                    payload: MessagePayload::Response(response),
+                });
                 continue;
             }
         };
         
-        // Process command
-        let response = match message.payload {
-            MessagePayload::Command(cmd) => process_command(cmd).await,
-            _ => Response::Error { 
-                message: "Expected command".to_string() 
-            },
+        let cmd = match payload {
+            MessagePayload::Command(cmd) => cmd,
+            _ => {
+                let _ = client.outbox.send(Message {
+                    id,
+                    payload: MessagePayload::Response(Response::error(
+                        ErrorCode::InvalidRequest,
+                        "Expected command",
+                    )),
+                });
+                continue;
+            }
         };
         
-        // Send response
-        let response_msg = Message {
-            id: message.id,
-            payload: MessagePayload::Response(response),
-        };
+        state.count_request(&cmd);
         
-        let json = serde_json::to_string(&response_msg)?;
-        writer.write_all(json.as_bytes()).await?;
-        writer.write_all(b"\n").await?;
-        writer.flush().await?;
-    }
+        let Ok(permit) = in_flight.clone().acquire_owned().await else {
+            break Ok(());
+        };
+        let state = state.clone();
+        let client = client.clone();
+        tokio::spawn(async move {
+            let response = process_command(id, cmd, &state, &client).await;
+            let _ = client.outbox.send(Message {
+                id,
+                payload: MessagePayload::Response(response),
+            });
+            drop(permit);
+        });
+    };
+    
+    state.watches.drop_client(client.id);
+    state.clients.fetch_sub(1, Ordering::Relaxed);
+    state.touch();
+    drop(client);
+    let _ = writer_task.await;
+    
+    result.map_err(Into::into)
+}
+
+/// Parse one request line, keeping the id even when the payload is unknown
+fn parse_message(line: &str) -> Result<(u64, MessagePayload), (u64, Response)> {
+    let value: serde_json::Value = serde_json::from_str(line).map_err(|e| {
+        (0, Response::error(ErrorCode::InvalidRequest, format!("Malformed message: {}", e)))
+    })?;
+    let id = value.get("id").and_then(|id| id.as_u64()).unwrap_or(0);
     
-    Ok(())
+    // Well-formed but unknown commands come from newer clients
+    match serde_json::from_value::<Message>(value) {
+        Ok(msg) => Ok((id, msg.payload)),
+        Err(e) => Err((id, Response::error(
+            ErrorCode::Unsupported,
+            format!("Unsupported request (protocol v{}): {}", PROTOCOL_VERSION, e),
+        ))),
+    }
 }
 
-async fn process_command(cmd: Command) -> Response {
+async fn process_command(id: u64, cmd: Command, state: &State, client: &Client) -> Response {
     match cmd {
+        Command::Hello { version, .. } => {
+            if version != PROTOCOL_VERSION {
+                println!("Client {} speaks protocol v{} (daemon v{})", client.id, version, PROTOCOL_VERSION);
+            }
+            Response::Hello {
+                version: PROTOCOL_VERSION,
+                capabilities: CAPABILITIES.iter().map(|c| c.to_string()).collect(),
+            }
+        }
+        
         Command::Ping => Response::Pong,
         
+        Command::Stats => Response::Stats { stats: state.stats() },
+        
         Command::GetEntries { path } => {
             match read_directory(&path).await {
                 Ok(entries) => Response::Entries { entries },
-                Err(e) => Response::Error { 
-                    message: format!("Failed to read directory: {}", e) 
-                },
+                Err(e) => Response::io_error("Failed to read directory", &e),
             }
         }
         
-        Command::Preview { path } => {
-            match tokio::fs::read_to_string(&path).await {
-                Ok(content) => Response::Preview { content },
-                Err(e) => Response::Error {
-                    message: format!("Failed to read file: {}", e)
+        Command::Preview { path, max_lines } => {
+            let max_lines = max_lines.unwrap_or(preview::DEFAULT_PREVIEW_LINES);
+            match preview::preview(&path, max_lines).await {
+                Ok(preview) => Response::Preview {
+                    content: preview.lines.join("\n"),
+                    preview: Some(Box::new(preview)),
                 },
+                Err((code, message)) => Response::error(code, message),
             }
         }
         
-        Command::Search { query } => {
-            // TODO: Implement search
-            Response::Error { 
-                message: "Search not implemented yet".to_string() 
+        Command::Search { query, limit } => {
+            let limit = limit.unwrap_or(DEFAULT_SEARCH_LIMIT);
+            match state.index.read() {
+                Ok(index) => Response::SearchResults {
+                    results: index.search(&query, limit),
+                },
+                Err(_) => Response::error(ErrorCode::Internal, "Search index unavailable"),
             }
         }
         
         Command::GitStatus { path } => {
-            // TODO: Implement git status
-            Response::GitStatus { 
-                status: "Git status not implemented yet".to_string() 
+            match git::status(&path).await {
+                Ok(status) => Response::GitStatus {
+                    repo_root: status.repo_root,
+                    files: status.files,
+                },
+                Err((code, message)) => Response::error(code, message),
+            }
+        }
+        
+        Command::Watch { path } => {
+            match state.watches.subscribe(Path::new(&path), client.id, client.outbox.clone()) {
+                Ok(()) => Response::Ok,
+                Err(e) => Response::io_error("Cannot watch directory", &e),
             }
         }
         
+        Command::Unwatch { path } => {
+            if state.watches.unsubscribe(Path::new(&path), client.id) {
+                Response::Ok
+            } else {
+                Response::error(ErrorCode::NotFound, format!("Not watching {}", path))
+            }
+        }
+        
+        Command::Copy { src, dst } => {
+            let outbox = client.outbox.clone();
+            run_blocking(move || {
+                ops::copy(Path::new(&src), Path::new(&dst), &mut |p| send_progress(&outbox, id, p))
+            }).await
+        }
+        
+        Command::Move { src, dst } => {
+            let outbox = client.outbox.clone();
+            run_blocking(move || {
+                ops::move_path(Path::new(&src), Path::new(&dst), &mut |p| send_progress(&outbox, id, p))
+            }).await
+        }
+        
+        Command::Delete { path } => run_blocking(move || ops::delete(Path::new(&path))).await,
+        
+        Command::Rename { path, new_name } => {
+            run_blocking(move || ops::rename(Path::new(&path), &new_name)).await
+        }
+        
+        Command::Mkdir { path } => run_blocking(move || ops::mkdir(Path::new(&path))).await,
+        
         Command::Shutdown => {
             println!("Shutdown requested");
             std::process::exit(0);
@@ -130,6 +458,25 @@ async fn process_command(cmd: Command) -> Response {
     }
 }
 
+/// Run a blocking file operation off the async workers
+async fn run_blocking<F>(op: F) -> Response
+where
+    F: FnOnce() -> Result<(), ops::OpError> + Send + 'static,
+{
+    match tokio::task::spawn_blocking(op).await {
+        Ok(Ok(())) => Response::Ok,
+        Ok(Err(e)) => e.into(),
+        Err(e) => Response::error(ErrorCode::Internal, format!("Operation aborted: {}", e)),
+    }
+}
+
+fn send_progress(outbox: &Outbox, id: u64, progress: crate::protocol::Progress) {
+    let _ = outbox.send(Message {
+        id,
+        payload: MessagePayload::Progress(progress),
+    });
+}
+
 async fn read_directory(path: &str) -> Result<Vec<Entry>, std::io::Error> {
     let mut entries = Vec::new();
     let mut dir = tokio::fs::read_dir(path).await?;
@@ -149,3 +496,49 @@ async fn read_directory(path: &str) -> Result<Vec<Entry>, std::io::Error> {
     entries.sort_by(|a, b| a.name.cmp(&b.name));
     Ok(entries)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn state() -> State {
+        let (watches, _) = WatchHub::new().unwrap();
+        State::new(FileIndex::new(Vec::new()), watches, false)
+    }
+    
+    #[test]
+    fn test_idle_timer() {
+        let state = state();
+        let timeout = Duration::from_millis(50);
+        assert!(!state.is_idle(timeout));
+        
+        std::thread::sleep(timeout);
+        assert!(state.is_idle(timeout));
+        
+        // A connected client keeps it alive however quiet it is
+        state.clients.fetch_add(1, Ordering::Relaxed);
+        assert!(!state.is_idle(timeout));
+        state.clients.fetch_sub(1, Ordering::Relaxed);
+        
+        // A request restarts the timer
+        state.count_request(&Command::Ping);
+        assert!(!state.is_idle(timeout));
+    }
+    
+    #[test]
+    fn test_stats_counters() {
+        let state = state();
+        state.count_request(&Command::Ping);
+        state.count_request(&Command::Ping);
+        state.count_request(&Command::Stats);
+        state.clients.fetch_add(2, Ordering::Relaxed);
+        
+        let stats = state.stats();
+        assert_eq!(stats.requests, 3);
+        assert_eq!(stats.requests_by_command.get("Ping"), Some(&2));
+        assert_eq!(stats.requests_by_command.get("Stats"), Some(&1));
+        assert_eq!(stats.clients, 2);
+        assert_eq!(stats.watched_dirs, 0);
+        assert!(!stats.socket_activated);
+    }
+}
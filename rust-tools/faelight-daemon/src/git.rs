@@ -0,0 +1,121 @@
+//! Structured git status for a directory
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use tokio::process::Command;
+use crate::protocol::{ErrorCode, GitFileStatus, GitState};
+
+pub struct RepoStatus {
+    pub repo_root: String,
+    pub files: BTreeMap<String, GitFileStatus>,
+}
+
+/// Get per-file git status for everything below `path`
+pub async fn status(path: &str) -> Result<RepoStatus, (ErrorCode, String)> {
+    let output = Command::new("git")
+        .args(["-C", path, "rev-parse", "--show-toplevel"])
+        .output()
+        .await
+        .map_err(|e| (ErrorCode::Internal, format!("Failed to run git: {}", e)))?;
+
+    if !output.status.success() {
+        return Err((ErrorCode::NotFound, format!("Not a git repository: {}", path)));
+    }
+
+    let repo_root = PathBuf::from(String::from_utf8_lossy(&output.stdout).trim());
+
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(&repo_root)
+        .args(["status", "--porcelain=v1", "-z"])
+        .output()
+        .await
+        .map_err(|e| (ErrorCode::Internal, format!("Failed to run git: {}", e)))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        return Err((ErrorCode::Internal, stderr));
+    }
+
+    // Porcelain paths are relative to the repo root, not to `path`
+    let scope = Path::new(path).canonicalize().unwrap_or_else(|_| PathBuf::from(path));
+    let files = parse_porcelain(&output.stdout)
+        .into_iter()
+        .map(|(rel, status)| (repo_root.join(rel), status))
+        .filter(|(abs, _)| abs.starts_with(&scope))
+        .map(|(abs, status)| (abs.to_string_lossy().to_string(), status))
+        .collect();
+
+    Ok(RepoStatus {
+        repo_root: repo_root.to_string_lossy().to_string(),
+        files,
+    })
+}
+
+/// Parse `git status --porcelain=v1 -z` output into repo-relative paths
+fn parse_porcelain(raw: &[u8]) -> Vec<(String, GitFileStatus)> {
+    let mut result = Vec::new();
+    let mut fields = raw.split(|b| *b == 0).filter(|f| !f.is_empty());
+
+    while let Some(field) = fields.next() {
+        if field.len() < 4 {
+            continue;
+        }
+
+        let (x, y) = (field[0] as char, field[1] as char);
+        let path = String::from_utf8_lossy(&field[3..])
+            .trim_end_matches('/')
+            .to_string();
+
+        // Renames and copies carry the original path as the next field
+        let orig_path = if matches!(x, 'R' | 'C') || matches!(y, 'R' | 'C') {
+            fields.next().map(|f| String::from_utf8_lossy(f).to_string())
+        } else {
+            None
+        };
+
+        let (index, worktree) = match (x, y) {
+            ('?', '?') => (GitState::Unmodified, GitState::Untracked),
+            ('!', '!') => (GitState::Unmodified, GitState::Ignored),
+            ('D', 'D') | ('A', 'A') | ('U', _) | (_, 'U') => {
+                (GitState::Conflicted, GitState::Conflicted)
+            }
+            (x, y) => (state_from_code(x), state_from_code(y)),
+        };
+
+        result.push((path, GitFileStatus { index, worktree, orig_path }));
+    }
+
+    result
+}
+
+fn state_from_code(code: char) -> GitState {
+    match code {
+        'M' => GitState::Modified,
+        'A' => GitState::Added,
+        'D' => GitState::Deleted,
+        'R' => GitState::Renamed,
+        'C' => GitState::Copied,
+        'T' => GitState::TypeChanged,
+        _ => GitState::Unmodified,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_porcelain() {
+        let raw = b" M src/main.rs\0R  new.rs\0old.rs\0?? notes/\0UU both.rs\0";
+        let parsed = parse_porcelain(raw);
+
+        assert_eq!(parsed.len(), 4);
+        assert_eq!(parsed[0].0, "src/main.rs");
+        assert_eq!(parsed[0].1.worktree, GitState::Modified);
+        assert_eq!(parsed[1].1.index, GitState::Renamed);
+        assert_eq!(parsed[1].1.orig_path.as_deref(), Some("old.rs"));
+        assert_eq!(parsed[2].0, "notes");
+        assert_eq!(parsed[2].1.worktree, GitState::Untracked);
+        assert_eq!(parsed[3].1.index, GitState::Conflicted);
+    }
+}
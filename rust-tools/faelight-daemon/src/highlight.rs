@@ -0,0 +1,308 @@
+//! Lightweight syntax highlighting for previews
+use std::path::Path;
+use crate::protocol::{HighlightKind, HighlightSpan};
+
+const RUST_KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum",
+    "extern", "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move",
+    "mut", "pub", "ref", "return", "self", "Self", "static", "struct", "super", "trait", "true",
+    "type", "unsafe", "use", "where", "while",
+];
+
+const SHELL_KEYWORDS: &[&str] = &[
+    "case", "do", "done", "elif", "else", "esac", "export", "fi", "for", "function", "if", "in",
+    "local", "return", "select", "then", "until", "while",
+];
+
+const TOML_KEYWORDS: &[&str] = &["true", "false"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Language {
+    Rust,
+    Toml,
+    Markdown,
+    Shell,
+}
+
+impl Language {
+    pub fn name(self) -> &'static str {
+        match self {
+            Language::Rust => "rust",
+            Language::Toml => "toml",
+            Language::Markdown => "markdown",
+            Language::Shell => "shell",
+        }
+    }
+}
+
+/// Pick a language from the extension, falling back to the shebang
+pub fn language_for(path: &Path, first_line: Option<&str>) -> Option<Language> {
+    let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+
+    match ext {
+        "rs" => Some(Language::Rust),
+        "toml" => Some(Language::Toml),
+        "md" | "markdown" => Some(Language::Markdown),
+        "sh" | "bash" | "zsh" => Some(Language::Shell),
+        _ => first_line
+            .filter(|l| l.starts_with("#!") && (l.ends_with("sh") || l.contains("sh ")))
+            .map(|_| Language::Shell),
+    }
+}
+
+/// Highlight spans for the given lines (columns are char offsets)
+pub fn highlight(language: Language, lines: &[String]) -> Vec<HighlightSpan> {
+    let mut spans = Vec::new();
+    let mut in_block = false;
+
+    for (line_no, line) in lines.iter().enumerate() {
+        let chars: Vec<char> = line.chars().collect();
+        let mut push = |start: usize, end: usize, kind: HighlightKind| {
+            if end > start {
+                spans.push(HighlightSpan { line: line_no, start, end, kind });
+            }
+        };
+
+        match language {
+            Language::Markdown => markdown_line(&chars, &mut in_block, &mut push),
+            Language::Rust => code_line(&chars, &CodeSyntax::RUST, &mut in_block, &mut push),
+            Language::Shell => code_line(&chars, &CodeSyntax::SHELL, &mut in_block, &mut push),
+            Language::Toml => {
+                let trimmed = line.trim_start();
+                if trimmed.starts_with('[') {
+                    let start = chars.len() - trimmed.chars().count();
+                    let end = chars[start..]
+                        .iter()
+                        .position(|&c| c == ']')
+                        .map(|i| start + i + 1)
+                        .unwrap_or(chars.len());
+                    push(start, end, HighlightKind::Heading);
+                } else if let Some(eq) = chars.iter().position(|&c| c == '=') {
+                    let key_start = chars.iter().position(|c| !c.is_whitespace()).unwrap_or(0);
+                    if key_start < eq && chars[key_start] != '#' {
+                        let key_end = eq - chars[..eq].iter().rev().take_while(|c| c.is_whitespace()).count();
+                        push(key_start, key_end, HighlightKind::Key);
+                        let mut rest = |s: usize, e: usize, k: HighlightKind| push(s + eq + 1, e + eq + 1, k);
+                        code_line(&chars[eq + 1..], &CodeSyntax::TOML, &mut in_block, &mut rest);
+                        continue;
+                    }
+                }
+                code_line(&chars, &CodeSyntax::TOML, &mut in_block, &mut push);
+            }
+        }
+    }
+
+    spans
+}
+
+/// Token rules for the C-like and shell-like languages
+struct CodeSyntax {
+    line_comment: &'static str,
+    block_comment: Option<(&'static str, &'static str)>,
+    keywords: &'static [&'static str],
+    /// Single quotes delimit strings (not char literals or lifetimes)
+    single_quoted: bool,
+    /// `$NAME` / `${NAME}` expansions
+    variables: bool,
+    /// Capitalised identifiers are types
+    types: bool,
+}
+
+impl CodeSyntax {
+    const RUST: CodeSyntax = CodeSyntax {
+        line_comment: "//",
+        block_comment: Some(("/*", "*/")),
+        keywords: RUST_KEYWORDS,
+        single_quoted: false,
+        variables: false,
+        types: true,
+    };
+
+    const SHELL: CodeSyntax = CodeSyntax {
+        line_comment: "#",
+        block_comment: None,
+        keywords: SHELL_KEYWORDS,
+        single_quoted: true,
+        variables: true,
+        types: false,
+    };
+
+    const TOML: CodeSyntax = CodeSyntax {
+        line_comment: "#",
+        block_comment: None,
+        keywords: TOML_KEYWORDS,
+        single_quoted: true,
+        variables: false,
+        types: false,
+    };
+}
+
+fn starts_with_at(chars: &[char], at: usize, pat: &str) -> bool {
+    pat.chars().enumerate().all(|(k, p)| chars.get(at + k) == Some(&p))
+}
+
+fn find_from(chars: &[char], from: usize, pat: &str) -> Option<usize> {
+    (from..chars.len()).find(|&i| starts_with_at(chars, i, pat))
+}
+
+fn code_line(
+    chars: &[char],
+    syntax: &CodeSyntax,
+    in_block: &mut bool,
+    push: &mut dyn FnMut(usize, usize, HighlightKind),
+) {
+    let mut i = 0;
+
+    // Continue a block comment from a previous line
+    if *in_block {
+        let close = syntax.block_comment.map(|(_, close)| close).unwrap_or("");
+        match find_from(chars, 0, close) {
+            Some(end) => {
+                let end = end + close.chars().count();
+                push(0, end, HighlightKind::Comment);
+                *in_block = false;
+                i = end;
+            }
+            None => {
+                push(0, chars.len(), HighlightKind::Comment);
+                return;
+            }
+        }
+    }
+
+    while i < chars.len() {
+        let c = chars[i];
+
+        if starts_with_at(chars, i, syntax.line_comment) {
+            push(i, chars.len(), HighlightKind::Comment);
+            return;
+        }
+
+        if let Some((open, close)) = syntax.block_comment {
+            if starts_with_at(chars, i, open) {
+                let body = i + open.chars().count();
+                match find_from(chars, body, close) {
+                    Some(end) => {
+                        let end = end + close.chars().count();
+                        push(i, end, HighlightKind::Comment);
+                        i = end;
+                    }
+                    None => {
+                        push(i, chars.len(), HighlightKind::Comment);
+                        *in_block = true;
+                        return;
+                    }
+                }
+                continue;
+            }
+        }
+
+        if c == '"' || (c == '\'' && syntax.single_quoted) {
+            let mut end = i + 1;
+            while end < chars.len() && chars[end] != c {
+                if chars[end] == '\\' && c == '"' {
+                    end += 1;
+                }
+                end += 1;
+            }
+            let end = (end + 1).min(chars.len());
+            push(i, end, HighlightKind::String);
+            i = end;
+            continue;
+        }
+
+        if syntax.variables && c == '$' {
+            let end = if chars.get(i + 1) == Some(&'{') {
+                find_from(chars, i, "}").map(|e| e + 1).unwrap_or(chars.len())
+            } else {
+                i + 1 + chars[i + 1..].iter().take_while(|c| c.is_alphanumeric() || **c == '_').count()
+            };
+            push(i, end, HighlightKind::Variable);
+            i = end.max(i + 1);
+            continue;
+        }
+
+        if c.is_ascii_digit() {
+            let end = i + chars[i..].iter().take_while(|c| c.is_alphanumeric() || **c == '_' || **c == '.').count();
+            push(i, end, HighlightKind::Number);
+            i = end;
+            continue;
+        }
+
+        if c.is_alphabetic() || c == '_' {
+            let end = i + chars[i..].iter().take_while(|c| c.is_alphanumeric() || **c == '_').count();
+            let word: String = chars[i..end].iter().collect();
+
+            if syntax.keywords.contains(&word.as_str()) {
+                push(i, end, HighlightKind::Keyword);
+            } else if syntax.types && c.is_uppercase() {
+                push(i, end, HighlightKind::Type);
+            }
+            i = end;
+            continue;
+        }
+
+        i += 1;
+    }
+}
+
+fn markdown_line(chars: &[char], in_fence: &mut bool, push: &mut dyn FnMut(usize, usize, HighlightKind)) {
+    if starts_with_at(chars, 0, "```") {
+        *in_fence = !*in_fence;
+        push(0, chars.len(), HighlightKind::String);
+        return;
+    }
+    if *in_fence {
+        push(0, chars.len(), HighlightKind::String);
+        return;
+    }
+    if chars.first() == Some(&'#') {
+        push(0, chars.len(), HighlightKind::Heading);
+        return;
+    }
+
+    // Inline `code`
+    let mut i = 0;
+    while let Some(open) = find_from(chars, i, "`") {
+        let Some(close) = find_from(chars, open + 1, "`") else {
+            break;
+        };
+        push(open, close + 1, HighlightKind::String);
+        i = close + 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(language: Language, line: &str) -> Vec<(String, HighlightKind)> {
+        let chars: Vec<char> = line.chars().collect();
+        highlight(language, &[line.to_string()])
+            .into_iter()
+            .map(|s| (chars[s.start..s.end].iter().collect(), s.kind))
+            .collect()
+    }
+
+    #[test]
+    fn test_highlight_rust() {
+        let spans = kinds(Language::Rust, r#"let x: Entry = "hi"; // note"#);
+        assert_eq!(spans, vec![
+            ("let".to_string(), HighlightKind::Keyword),
+            ("Entry".to_string(), HighlightKind::Type),
+            ("\"hi\"".to_string(), HighlightKind::String),
+            ("// note".to_string(), HighlightKind::Comment),
+        ]);
+    }
+
+    #[test]
+    fn test_highlight_toml_and_shell() {
+        let spans = kinds(Language::Toml, "name = \"faelight\"");
+        assert_eq!(spans[0], ("name".to_string(), HighlightKind::Key));
+        assert_eq!(spans[1], ("\"faelight\"".to_string(), HighlightKind::String));
+
+        let spans = kinds(Language::Shell, "echo $HOME # home");
+        assert_eq!(spans[0], ("$HOME".to_string(), HighlightKind::Variable));
+        assert_eq!(spans[1], ("# home".to_string(), HighlightKind::Comment));
+    }
+}
@@ -0,0 +1,362 @@
+//! In-memory file index over the zone roots
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use faelight_zone::Zones;
+use crate::protocol::{Entry, SearchHit};
+
+/// Directory names never descended into
+const SKIP_DIRS: &[&str] = &[".git", "target", "node_modules", ".cache"];
+
+/// Default number of search results
+pub const DEFAULT_SEARCH_LIMIT: usize = 50;
+
+#[derive(Clone)]
+pub struct FileIndex {
+    roots: Vec<PathBuf>,
+    entries: BTreeMap<PathBuf, Entry>,
+    /// Last seen mtime for every indexed directory
+    dirs: HashMap<PathBuf, Option<SystemTime>>,
+    /// Paths updated while a copy is rebuilt outside the lock
+    replay: Option<Vec<PathBuf>>,
+}
+
+impl FileIndex {
+    pub fn new(roots: Vec<PathBuf>) -> Self {
+        Self {
+            roots,
+            entries: BTreeMap::new(),
+            dirs: HashMap::new(),
+            replay: None,
+        }
+    }
+
+    /// Remember updated paths from now on, for a copy that will `replace` this one
+    pub fn start_replay(&mut self) {
+        self.replay = Some(Vec::new());
+    }
+
+    /// Swap in a rebuilt copy, applying what changed here since `start_replay`
+    pub fn replace(&mut self, mut fresh: FileIndex) {
+        fresh.replay = None;
+        for path in self.replay.take().unwrap_or_default() {
+            fresh.update_path(&path);
+        }
+        *self = fresh;
+    }
+
+    /// Directories of every zone that exist on disk. Nested ones are
+    /// covered by their parent and left out.
+    pub fn zone_roots(home: &Path) -> Vec<PathBuf> {
+        Zones::global().roots(home)
+    }
+
+    /// Full scan of every root
+    pub fn build(&mut self) {
+        self.entries.clear();
+        self.dirs.clear();
+
+        for root in self.roots.clone() {
+            self.scan_tree(&root);
+        }
+    }
+
+    pub fn roots(&self) -> &[PathBuf] {
+        &self.roots
+    }
+
+    /// Number of indexed entries
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Rescan only directories whose mtime changed since the last scan.
+    /// Returns the number of directories that were rescanned.
+    pub fn refresh(&mut self) -> usize {
+        let changed: Vec<PathBuf> = self.dirs
+            .iter()
+            .filter(|(dir, mtime)| dir_mtime(dir) != **mtime)
+            .map(|(dir, _)| dir.clone())
+            .collect();
+
+        for dir in &changed {
+            // A parent rescan may already have dropped this directory
+            if self.dirs.contains_key(dir) {
+                self.rescan_dir(dir);
+            }
+        }
+
+        changed.len()
+    }
+
+    /// Re-index a single path after it was created, modified or removed
+    pub fn update_path(&mut self, path: &Path) {
+        if let Some(replay) = self.replay.as_mut() {
+            replay.push(path.to_path_buf());
+        }
+        if !self.is_indexed_path(path) {
+            return;
+        }
+
+        match std::fs::symlink_metadata(path) {
+            Ok(metadata) => {
+                self.entries.insert(path.to_path_buf(), make_entry(path, &metadata));
+                if metadata.is_dir() && !self.dirs.contains_key(path) {
+                    self.scan_tree(path);
+                }
+            }
+            Err(_) => self.remove_subtree(path),
+        }
+    }
+
+    /// Fuzzy search over indexed names and root-relative paths
+    pub fn search(&self, query: &str, limit: usize) -> Vec<SearchHit> {
+        let query = query.trim();
+        if query.is_empty() {
+            return Vec::new();
+        }
+
+        let mut hits: Vec<SearchHit> = self.entries
+            .iter()
+            .filter_map(|(path, entry)| {
+                // Name matches rank above matches that span directories
+                let score = fuzzy_score(query, &entry.name).map(|s| s + NAME_BONUS).or_else(|| {
+                    let rel = self.relative_to_root(path);
+                    fuzzy_score(query, &rel.to_string_lossy())
+                })?;
+
+                Some(SearchHit { entry: entry.clone(), score })
+            })
+            .collect();
+
+        hits.sort_by(|a, b| {
+            b.score
+                .cmp(&a.score)
+                .then_with(|| a.entry.path.len().cmp(&b.entry.path.len()))
+                .then_with(|| a.entry.path.cmp(&b.entry.path))
+        });
+        hits.truncate(limit);
+        hits
+    }
+
+    fn is_indexed_path(&self, path: &Path) -> bool {
+        self.roots.iter().any(|root| path.starts_with(root))
+            && !path.components().any(|c| SKIP_DIRS.iter().any(|s| c.as_os_str() == *s))
+    }
+
+    fn relative_to_root<'a>(&self, path: &'a Path) -> &'a Path {
+        self.roots
+            .iter()
+            .find_map(|root| path.strip_prefix(root).ok())
+            .unwrap_or(path)
+    }
+
+    /// Recursively index a directory and everything below it
+    fn scan_tree(&mut self, dir: &Path) {
+        let mut stack = vec![dir.to_path_buf()];
+
+        while let Some(dir) = stack.pop() {
+            for child in self.scan_children(&dir) {
+                stack.push(child);
+            }
+        }
+    }
+
+    /// Index the direct children of a directory, returning child directories
+    fn scan_children(&mut self, dir: &Path) -> Vec<PathBuf> {
+        let mut subdirs = Vec::new();
+        self.dirs.insert(dir.to_path_buf(), dir_mtime(dir));
+
+        let Ok(read_dir) = std::fs::read_dir(dir) else {
+            return subdirs;
+        };
+
+        for child in read_dir.flatten() {
+            let path = child.path();
+            let Ok(metadata) = child.metadata() else {
+                continue;
+            };
+
+            if metadata.is_dir() && is_skipped(&path) {
+                continue;
+            }
+
+            self.entries.insert(path.clone(), make_entry(&path, &metadata));
+            if metadata.is_dir() {
+                subdirs.push(path);
+            }
+        }
+
+        subdirs
+    }
+
+    /// Reconcile a changed directory's direct children with the disk
+    fn rescan_dir(&mut self, dir: &Path) {
+        if !dir.is_dir() {
+            self.remove_subtree(dir);
+            // Replaced by a file or link rather than deleted
+            if let Ok(metadata) = std::fs::symlink_metadata(dir) {
+                self.entries.insert(dir.to_path_buf(), make_entry(dir, &metadata));
+            }
+            return;
+        }
+
+        let old_children: Vec<PathBuf> = self.entries
+            .range(dir.to_path_buf()..)
+            .skip_while(|(path, _)| path.as_path() == dir)
+            .take_while(|(path, _)| path.starts_with(dir))
+            .filter(|(path, _)| path.parent() == Some(dir))
+            .map(|(path, _)| path.clone())
+            .collect();
+
+        let subdirs = self.scan_children(dir);
+
+        for old in old_children {
+            if !old.exists() && !old.is_symlink() {
+                self.remove_subtree(&old);
+            }
+        }
+
+        for subdir in subdirs {
+            if !self.dirs.contains_key(&subdir) {
+                self.scan_tree(&subdir);
+            }
+        }
+    }
+
+    fn remove_subtree(&mut self, path: &Path) {
+        let doomed: Vec<PathBuf> = self.entries
+            .range(path.to_path_buf()..)
+            .take_while(|(p, _)| p.starts_with(path))
+            .map(|(p, _)| p.clone())
+            .collect();
+
+        for p in doomed {
+            self.entries.remove(&p);
+        }
+        self.dirs.retain(|dir, _| !dir.starts_with(path));
+    }
+}
+
+fn is_skipped(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|n| n.to_str())
+        .map(|n| SKIP_DIRS.contains(&n))
+        .unwrap_or(false)
+}
+
+fn dir_mtime(dir: &Path) -> Option<SystemTime> {
+    std::fs::metadata(dir).and_then(|m| m.modified()).ok()
+}
+
+fn make_entry(path: &Path, metadata: &std::fs::Metadata) -> Entry {
+    Entry {
+        name: path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        path: path.to_string_lossy().to_string(),
+        is_dir: metadata.is_dir(),
+        size: metadata.len(),
+    }
+}
+
+/// Score added when the query matches the file name itself
+const NAME_BONUS: i64 = 100;
+
+/// Calculate fuzzy match score (higher is better)
+pub fn fuzzy_score(query: &str, text: &str) -> Option<i64> {
+    let query: Vec<char> = query.to_lowercase().chars().collect();
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+
+    let mut score = 0;
+    let mut text_idx = 0;
+    let mut prev_match: Option<usize> = None;
+
+    for ch in &query {
+        let offset = text[text_idx..].iter().position(|c| c == ch)?;
+        let idx = text_idx + offset;
+
+        score += 1;
+
+        // Bonus for consecutive matches
+        if prev_match == Some(idx.wrapping_sub(1)) {
+            score += 5;
+        }
+
+        // Bonus for matching at the start of a word
+        if idx == 0 || matches!(text[idx - 1], '/' | '-' | '_' | '.' | ' ') {
+            score += 3;
+        }
+
+        prev_match = Some(idx);
+        text_idx = idx + 1;
+    }
+
+    // Prefer shorter candidates
+    Some(score * 10 - text.len() as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fuzzy_score() {
+        assert!(fuzzy_score("fm", "faelight-fm").is_some());
+        assert!(fuzzy_score("xyz", "faelight-fm").is_none());
+
+        // Consecutive and word-start matches rank higher
+        let tight = fuzzy_score("fm", "faelight-fm").unwrap();
+        let loose = fuzzy_score("fm", "faelight-dmenu").unwrap();
+        assert!(tight > loose);
+    }
+
+    #[test]
+    fn test_refresh_dir_became_file() {
+        let root = std::env::temp_dir().join(format!("faelight-daemon-index-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(root.join("notes/old")).unwrap();
+        std::fs::write(root.join("notes/old/a.md"), "a").unwrap();
+
+        let mut index = FileIndex::new(vec![root.clone()]);
+        index.build();
+        assert_eq!(index.len(), 3);
+
+        std::fs::remove_dir_all(root.join("notes")).unwrap();
+        std::fs::write(root.join("notes"), "now a file").unwrap();
+        // Count both as changed even within the timestamp granularity
+        index.dirs.insert(root.join("notes"), None);
+        index.dirs.insert(root.clone(), None);
+        index.refresh();
+
+        assert_eq!(index.len(), 1);
+        let hits = index.search("notes", 10);
+        assert_eq!(hits.len(), 1);
+        assert!(!hits[0].entry.is_dir);
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_replay_updates_into_copy() {
+        let root = std::env::temp_dir().join(format!("faelight-daemon-replay-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(&root).unwrap();
+
+        let mut live = FileIndex::new(vec![root.clone()]);
+        live.build();
+        live.start_replay();
+        let mut fresh = live.clone();
+        fresh.build();
+
+        // Seen by the watcher while the copy was being rebuilt
+        std::fs::write(root.join("late.txt"), "x").unwrap();
+        live.update_path(&root.join("late.txt"));
+        live.replace(fresh);
+        assert_eq!(live.search("late", 10).len(), 1);
+        assert!(live.replay.is_none());
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -1,16 +1,45 @@
 mod protocol;
 mod daemon;
+mod index;
+mod git;
+mod watch;
+mod ops;
+mod systemd;
+mod preview;
+mod highlight;
 
+use std::time::Duration;
 use daemon::Daemon;
 
-#[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
+fn main() -> Result<(), Box<dyn std::error::Error>> {
     let socket_path = "/tmp/faelight-daemon.sock".to_string();
     
     println!("🌲 Starting faelight-daemon v0.1.0");
     
-    let daemon = Daemon::new(socket_path);
-    daemon.run().await?;
+    // Clears LISTEN_* from the environment, so before any thread exists
+    let inherited = systemd::inherited_listener();
     
-    Ok(())
+    let mut daemon = Daemon::new(socket_path).with_listener(inherited);
+    if let Some(secs) = idle_timeout_arg()? {
+        daemon = daemon.with_idle_timeout(Duration::from_secs(secs));
+    }
+    
+    tokio::runtime::Runtime::new()?.block_on(daemon.run())
+}
+
+/// Parse `--idle-timeout <secs>`; 0 keeps the daemon running forever
+fn idle_timeout_arg() -> Result<Option<u64>, Box<dyn std::error::Error>> {
+    let mut args = std::env::args().skip(1);
+    
+    while let Some(arg) = args.next() {
+        if arg == "--idle-timeout" {
+            let secs: u64 = args
+                .next()
+                .ok_or("--idle-timeout needs a value in seconds")?
+                .parse()?;
+            return Ok((secs > 0).then_some(secs));
+        }
+    }
+    
+    Ok(None)
 }
@@ -0,0 +1,376 @@
+//! Zone-aware file operations
+use std::fs;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use faelight_zone::{is_core_locked, resolve_path, Decision, Op, Zones};
+use crate::protocol::{ErrorCode, Progress, Response};
+
+/// Minimum time between two progress reports
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+const COPY_BUFFER_SIZE: usize = 256 * 1024;
+
+/// Operation failure carrying its protocol error code
+#[derive(Debug)]
+pub struct OpError {
+    pub code: ErrorCode,
+    pub message: String,
+}
+
+impl OpError {
+    fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self { code, message: message.into() }
+    }
+
+    fn io(context: &str, path: &Path, err: std::io::Error) -> Self {
+        Self::new(ErrorCode::from_io(&err), format!("{} {}: {}", context, path.display(), err))
+    }
+}
+
+impl From<OpError> for Response {
+    fn from(err: OpError) -> Self {
+        Response::error(err.code, err.message)
+    }
+}
+
+type OpResult<T> = Result<T, OpError>;
+
+fn home_dir() -> PathBuf {
+    std::env::var("HOME")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from("/home"))
+}
+
+/// Refuse what the zone policy denies. Confirmations are up to the client,
+/// which asked before sending the request.
+fn ensure_allowed(op: Op, path: &Path) -> OpResult<()> {
+    check_zone(Zones::global(), op, path, &home_dir(), is_core_locked())
+}
+
+fn check_zone(zones: &Zones, op: Op, path: &Path, home: &Path, locked: bool) -> OpResult<()> {
+    if let Decision::Deny(reason) = zones.can(op, &resolve_path(path), home, locked) {
+        return Err(OpError::new(ErrorCode::ZoneLocked, format!("{}: {}", path.display(), reason)));
+    }
+    Ok(())
+}
+
+fn ensure_absent(path: &Path) -> OpResult<()> {
+    if path.exists() || path.is_symlink() {
+        return Err(OpError::new(
+            ErrorCode::AlreadyExists,
+            format!("{} already exists", path.display()),
+        ));
+    }
+    Ok(())
+}
+
+/// Refuse a destination inside the source tree. Both are compared with
+/// their directories resolved, so `..`, `.` and symlinked parents can't
+/// hide it; the last component stays as is, since a link is copied as a link.
+fn ensure_not_nested(src: &Path, dst: &Path) -> OpResult<()> {
+    let src = resolve_parent(src).unwrap_or_else(|| src.to_path_buf());
+    let dst = resolve_parent(dst).unwrap_or_else(|| dst.to_path_buf());
+    if dst.starts_with(&src) {
+        return Err(OpError::new(
+            ErrorCode::InvalidRequest,
+            format!("Cannot copy {} into itself", src.display()),
+        ));
+    }
+    Ok(())
+}
+
+fn resolve_parent(path: &Path) -> Option<PathBuf> {
+    let (Some(parent), Some(name)) = (path.parent(), path.file_name()) else {
+        return path.canonicalize().ok();
+    };
+    let parent = if parent.as_os_str().is_empty() { Path::new(".") } else { parent };
+    Some(parent.canonicalize().ok()?.join(name))
+}
+
+/// Copy a file or directory tree to `dst`, reporting progress
+pub fn copy(src: &Path, dst: &Path, on_progress: &mut dyn FnMut(Progress)) -> OpResult<()> {
+    ensure_allowed(Op::Write, dst)?;
+    ensure_absent(dst)?;
+    ensure_not_nested(src, dst)?;
+
+    let mut reporter = ProgressReporter::new(tree_size(src), on_progress);
+    if let Err(e) = copy_tree(src, dst, &mut reporter) {
+        // Don't leave a half-copied tree behind
+        let _ = remove_tree(dst);
+        return Err(e);
+    }
+    reporter.finish();
+    Ok(())
+}
+
+/// Move a file or directory, falling back to copy + delete across filesystems
+pub fn move_path(src: &Path, dst: &Path, on_progress: &mut dyn FnMut(Progress)) -> OpResult<()> {
+    ensure_allowed(Op::Move, src)?;
+    ensure_allowed(Op::Write, dst)?;
+    ensure_absent(dst)?;
+    ensure_not_nested(src, dst)?;
+
+    match fs::rename(src, dst) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::CrossesDevices => {
+            let mut reporter = ProgressReporter::new(tree_size(src), on_progress);
+            if let Err(e) = copy_tree(src, dst, &mut reporter) {
+                let _ = remove_tree(dst);
+                return Err(e);
+            }
+            reporter.finish();
+            remove_tree(src)
+        }
+        Err(e) => Err(OpError::io("Cannot move", src, e)),
+    }
+}
+
+/// Delete a file or directory tree
+pub fn delete(path: &Path) -> OpResult<()> {
+    ensure_allowed(Op::Delete, path)?;
+
+    if !path.exists() && !path.is_symlink() {
+        return Err(OpError::new(ErrorCode::NotFound, format!("{} not found", path.display())));
+    }
+    remove_tree(path)
+}
+
+/// Rename a file in place
+pub fn rename(path: &Path, new_name: &str) -> OpResult<()> {
+    if new_name.is_empty() || new_name.contains('/') || new_name == "." || new_name == ".." {
+        return Err(OpError::new(
+            ErrorCode::InvalidRequest,
+            format!("Invalid file name: {:?}", new_name),
+        ));
+    }
+
+    let parent = path.parent().ok_or_else(|| {
+        OpError::new(ErrorCode::InvalidRequest, format!("{} has no parent", path.display()))
+    })?;
+    let new_path = parent.join(new_name);
+
+    ensure_allowed(Op::Move, path)?;
+    ensure_absent(&new_path)?;
+    fs::rename(path, &new_path).map_err(|e| OpError::io("Cannot rename", path, e))
+}
+
+/// Create a directory (and missing parents)
+pub fn mkdir(path: &Path) -> OpResult<()> {
+    ensure_allowed(Op::Write, path)?;
+    ensure_absent(path)?;
+    fs::create_dir_all(path).map_err(|e| OpError::io("Cannot create", path, e))
+}
+
+fn remove_tree(path: &Path) -> OpResult<()> {
+    let result = if path.is_dir() && !path.is_symlink() {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
+    };
+    result.map_err(|e| OpError::io("Cannot delete", path, e))
+}
+
+/// Total size in bytes of regular files below `path`
+fn tree_size(path: &Path) -> u64 {
+    let Ok(metadata) = fs::symlink_metadata(path) else {
+        return 0;
+    };
+
+    if !metadata.is_dir() {
+        return if metadata.is_file() { metadata.len() } else { 0 };
+    }
+
+    fs::read_dir(path)
+        .map(|entries| entries.flatten().map(|e| tree_size(&e.path())).sum())
+        .unwrap_or(0)
+}
+
+fn copy_tree(src: &Path, dst: &Path, reporter: &mut ProgressReporter) -> OpResult<()> {
+    let metadata = fs::symlink_metadata(src).map_err(|e| OpError::io("Cannot read", src, e))?;
+
+    if metadata.is_symlink() {
+        let target = fs::read_link(src).map_err(|e| OpError::io("Cannot read link", src, e))?;
+        std::os::unix::fs::symlink(target, dst).map_err(|e| OpError::io("Cannot create", dst, e))
+    } else if metadata.is_dir() {
+        fs::create_dir(dst).map_err(|e| OpError::io("Cannot create", dst, e))?;
+        let entries = fs::read_dir(src).map_err(|e| OpError::io("Cannot read", src, e))?;
+
+        for entry in entries {
+            let entry = entry.map_err(|e| OpError::io("Cannot read", src, e))?;
+            copy_tree(&entry.path(), &dst.join(entry.file_name()), reporter)?;
+        }
+
+        fs::set_permissions(dst, metadata.permissions())
+            .map_err(|e| OpError::io("Cannot set permissions on", dst, e))
+    } else if let Some(kind) = special_kind(metadata.file_type()) {
+        // Reading a FIFO blocks the worker and a device never ends
+        Err(OpError::new(
+            ErrorCode::InvalidRequest,
+            format!("{} is a {}, it can't be copied", src.display(), kind),
+        ))
+    } else {
+        copy_file(src, dst, &metadata, reporter)
+    }
+}
+
+fn special_kind(file_type: fs::FileType) -> Option<&'static str> {
+    use std::os::unix::fs::FileTypeExt;
+    if file_type.is_fifo() {
+        Some("FIFO")
+    } else if file_type.is_socket() {
+        Some("socket")
+    } else if file_type.is_char_device() || file_type.is_block_device() {
+        Some("device")
+    } else {
+        None
+    }
+}
+
+fn copy_file(
+    src: &Path,
+    dst: &Path,
+    metadata: &fs::Metadata,
+    reporter: &mut ProgressReporter,
+) -> OpResult<()> {
+    let mut input = fs::File::open(src).map_err(|e| OpError::io("Cannot open", src, e))?;
+    let mut output = fs::File::create(dst).map_err(|e| OpError::io("Cannot create", dst, e))?;
+    let mut buffer = vec![0u8; COPY_BUFFER_SIZE];
+
+    loop {
+        let n = input.read(&mut buffer).map_err(|e| OpError::io("Cannot read", src, e))?;
+        if n == 0 {
+            break;
+        }
+        output.write_all(&buffer[..n]).map_err(|e| OpError::io("Cannot write", dst, e))?;
+        reporter.advance(n as u64, src);
+    }
+
+    fs::set_permissions(dst, metadata.permissions())
+        .map_err(|e| OpError::io("Cannot set permissions on", dst, e))
+}
+
+/// Rate-limited progress reporting
+struct ProgressReporter<'a> {
+    total: u64,
+    done: u64,
+    last_report: Instant,
+    on_progress: &'a mut dyn FnMut(Progress),
+}
+
+impl<'a> ProgressReporter<'a> {
+    fn new(total: u64, on_progress: &'a mut dyn FnMut(Progress)) -> Self {
+        Self {
+            total,
+            done: 0,
+            last_report: Instant::now(),
+            on_progress,
+        }
+    }
+
+    fn advance(&mut self, bytes: u64, current: &Path) {
+        self.done += bytes;
+
+        if self.last_report.elapsed() >= PROGRESS_INTERVAL {
+            self.last_report = Instant::now();
+            (self.on_progress)(Progress {
+                bytes_done: self.done,
+                bytes_total: self.total,
+                current: current.to_string_lossy().to_string(),
+            });
+        }
+    }
+
+    fn finish(&mut self) {
+        (self.on_progress)(Progress {
+            bytes_done: self.done,
+            bytes_total: self.total,
+            current: String::new(),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_root(name: &str) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("faelight-daemon-ops-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        root.canonicalize().unwrap()
+    }
+
+    #[test]
+    fn test_core_lock_refuses_writes() {
+        let home = temp_root("lock");
+        let target = home.join("0-core/stow/shell-zsh/.zshrc");
+        fs::create_dir_all(target.parent().unwrap()).unwrap();
+        let zones = Zones::default();
+
+        let err = check_zone(&zones, Op::Write, &target, &home, true).unwrap_err();
+        assert_eq!(err.code, ErrorCode::ZoneLocked);
+        assert!(err.message.contains("core-protect unlock"), "{}", err.message);
+        assert!(check_zone(&zones, Op::Write, &target, &home, false).is_ok());
+        // Reading a locked Core is fine
+        assert!(check_zone(&zones, Op::Read, &target, &home, true).is_ok());
+
+        fs::remove_dir_all(&home).unwrap();
+    }
+
+    #[test]
+    fn test_copy_reports_progress() {
+        let root = temp_root("progress");
+        fs::create_dir_all(root.join("src/deep")).unwrap();
+        fs::write(root.join("src/a"), vec![1u8; 3000]).unwrap();
+        fs::write(root.join("src/deep/b"), vec![2u8; 500]).unwrap();
+
+        let mut reports = Vec::new();
+        copy(&root.join("src"), &root.join("dst"), &mut |p| reports.push(p)).unwrap();
+        let last = reports.last().expect("final report");
+        assert_eq!((last.bytes_done, last.bytes_total), (3500, 3500));
+        assert!(last.current.is_empty());
+        assert_eq!(fs::read(root.join("dst/deep/b")).unwrap(), vec![2u8; 500]);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_copy_into_itself_is_refused() {
+        let root = temp_root("nested");
+        let src = root.join("src");
+        fs::create_dir_all(src.join("sub")).unwrap();
+        fs::create_dir_all(root.join("other")).unwrap();
+        std::os::unix::fs::symlink(&src, root.join("alias")).unwrap();
+        let mut ignore = |_| {};
+
+        for dst in [src.join("copy"), root.join("other/../src/copy"), src.join("sub/./copy"), root.join("alias/copy")] {
+            let err = copy(&src, &dst, &mut ignore).unwrap_err();
+            assert_eq!(err.code, ErrorCode::InvalidRequest, "{}", dst.display());
+        }
+        // Not an error just because the names share a prefix
+        assert!(copy(&src, &root.join("src-copy"), &mut ignore).is_ok());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_copy_refuses_special_files() {
+        let root = temp_root("special");
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::write(root.join("src/a"), "a").unwrap();
+        let fifo = root.join("src/pipe");
+        assert!(std::process::Command::new("mkfifo").arg(&fifo).status().unwrap().success());
+        let mut ignore = |_| {};
+
+        // Returns instead of blocking on the FIFO, and cleans up
+        let err = copy(&root.join("src"), &root.join("dst"), &mut ignore).unwrap_err();
+        assert_eq!(err.code, ErrorCode::InvalidRequest);
+        assert!(err.message.contains("FIFO"), "{}", err.message);
+        assert!(!root.join("dst").exists());
+        let err = copy(Path::new("/dev/zero"), &root.join("zero"), &mut ignore).unwrap_err();
+        assert_eq!(err.code, ErrorCode::InvalidRequest);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,362 @@
+//! File previews with type detection and size limits
+use std::io::Read;
+use std::os::unix::fs::FileTypeExt;
+use std::path::Path;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::process::Command;
+use crate::highlight;
+use crate::protocol::{ErrorCode, FilePreview, PreviewKind};
+
+/// Default number of lines returned for text, listings and archives
+pub const DEFAULT_PREVIEW_LINES: usize = 200;
+
+/// Never read more than this much of a file
+const MAX_PREVIEW_BYTES: u64 = 256 * 1024;
+
+/// Bytes shown in a binary hex dump
+const HEX_DUMP_BYTES: usize = 512;
+
+/// How long unzip/tar get to list an archive
+const ARCHIVE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Magic bytes of recognised image formats
+const IMAGE_MAGIC: &[(&[u8], &str)] = &[
+    (b"\x89PNG\r\n\x1a\n", "PNG"),
+    (b"\xff\xd8\xff", "JPEG"),
+    (b"GIF87a", "GIF"),
+    (b"GIF89a", "GIF"),
+    (b"\x00\x00\x01\x00", "ICO"),
+];
+
+/// Magic bytes of recognised archive formats
+const ARCHIVE_MAGIC: &[(&[u8], &str)] = &[
+    (b"PK\x03\x04", "zip"),
+    (b"PK\x05\x06", "zip"),
+    (b"\x1f\x8b", "gzip"),
+    (b"\xfd7zXZ\x00", "xz"),
+    (b"\x28\xb5\x2f\xfd", "zstd"),
+    (b"BZh", "bzip2"),
+    (b"7z\xbc\xaf\x27\x1c", "7z"),
+];
+
+/// Build a preview of `path`, returning at most `max_lines` lines
+pub async fn preview(path: &str, max_lines: usize) -> Result<FilePreview, (ErrorCode, String)> {
+    let io_err = |e: std::io::Error| (ErrorCode::from_io(&e), format!("Cannot preview {}: {}", path, e));
+    let metadata = tokio::fs::metadata(path).await.map_err(io_err)?;
+
+    if metadata.is_dir() {
+        return preview_dir(path, max_lines).await.map_err(io_err);
+    }
+    if !metadata.is_file() {
+        // Reading a FIFO blocks and /dev/zero never ends
+        return Ok(FilePreview::new(PreviewKind::Special, vec![special_kind(&metadata.file_type()).to_string()]));
+    }
+
+    let owned = path.to_string();
+    let head = tokio::task::spawn_blocking(move || read_head(&owned))
+        .await
+        .map_err(|e| (ErrorCode::Internal, e.to_string()))?
+        .map_err(io_err)?;
+
+    let size = metadata.len();
+    let mut preview = match detect(&head) {
+        Detected::Image(format) => {
+            let dimensions = image_dimensions(format, &head);
+            let line = match dimensions {
+                Some((w, h)) => format!("{} image, {}×{}", format, w, h),
+                None => format!("{} image", format),
+            };
+
+            let mut preview = FilePreview::new(PreviewKind::Image, vec![line]);
+            preview.format = Some(format.to_string());
+            preview.dimensions = dimensions;
+            preview
+        }
+        Detected::Archive(format) => {
+            let (lines, truncated) = list_archive(path, format, max_lines).await;
+            let mut preview = FilePreview::new(PreviewKind::Archive, lines);
+            preview.format = Some(format.to_string());
+            preview.truncated = truncated;
+            preview
+        }
+        Detected::Text(text) => {
+            let mut lines: Vec<String> = text.lines().take(max_lines + 1).map(String::from).collect();
+            let truncated = lines.len() > max_lines || size > head.len() as u64;
+            lines.truncate(max_lines);
+
+            let language = highlight::language_for(Path::new(path), lines.first().map(String::as_str));
+            let highlights = language.map(|lang| highlight::highlight(lang, &lines)).unwrap_or_default();
+
+            let mut preview = FilePreview::new(PreviewKind::Text, lines);
+            preview.truncated = truncated;
+            preview.language = language.map(|l| l.name().to_string());
+            preview.highlights = highlights;
+            preview
+        }
+        Detected::Binary => {
+            let shown = head.len().min(HEX_DUMP_BYTES).min(max_lines * 16);
+            let mut preview = FilePreview::new(PreviewKind::Binary, hex_dump(&head[..shown]));
+            preview.truncated = size > shown as u64;
+            preview
+        }
+    };
+
+    preview.size = size;
+    Ok(preview)
+}
+
+async fn preview_dir(path: &str, max_lines: usize) -> std::io::Result<FilePreview> {
+    let mut names = Vec::new();
+    let mut dir = tokio::fs::read_dir(path).await?;
+
+    while let Some(entry) = dir.next_entry().await? {
+        let mut name = entry.file_name().to_string_lossy().to_string();
+        if entry.file_type().await.map(|t| t.is_dir()).unwrap_or(false) {
+            name.push('/');
+        }
+        names.push(name);
+    }
+
+    names.sort();
+    let truncated = names.len() > max_lines;
+    names.truncate(max_lines);
+
+    let mut preview = FilePreview::new(PreviewKind::Directory, names);
+    preview.truncated = truncated;
+    Ok(preview)
+}
+
+fn special_kind(file_type: &std::fs::FileType) -> &'static str {
+    if file_type.is_fifo() {
+        "FIFO (named pipe)"
+    } else if file_type.is_socket() {
+        "Socket"
+    } else if file_type.is_char_device() {
+        "Character device"
+    } else if file_type.is_block_device() {
+        "Block device"
+    } else {
+        "Special file"
+    }
+}
+
+fn read_head(path: &str) -> std::io::Result<Vec<u8>> {
+    let mut head = Vec::new();
+    std::fs::File::open(path)?
+        .take(MAX_PREVIEW_BYTES)
+        .read_to_end(&mut head)?;
+    Ok(head)
+}
+
+enum Detected {
+    Image(&'static str),
+    Archive(&'static str),
+    Text(String),
+    Binary,
+}
+
+fn detect(head: &[u8]) -> Detected {
+    if let Some((_, format)) = IMAGE_MAGIC.iter().find(|(magic, _)| head.starts_with(magic)) {
+        return Detected::Image(format);
+    }
+    if head.len() > 12 && head.starts_with(b"RIFF") && &head[8..12] == b"WEBP" {
+        return Detected::Image("WebP");
+    }
+    // "BM" alone is too common at the start of text; also require the reserved zero bytes
+    if head.len() > 26 && head.starts_with(b"BM") && head[6..10] == [0; 4] {
+        return Detected::Image("BMP");
+    }
+    if let Some((_, format)) = ARCHIVE_MAGIC.iter().find(|(magic, _)| head.starts_with(magic)) {
+        return Detected::Archive(format);
+    }
+    if head.len() > 262 && &head[257..262] == b"ustar" {
+        return Detected::Archive("tar");
+    }
+
+    if head.contains(&0) {
+        return Detected::Binary;
+    }
+
+    // The read limit may cut a multi-byte character in half
+    match std::str::from_utf8(head) {
+        Ok(text) => Detected::Text(text.to_string()),
+        Err(e) if e.error_len().is_none() => {
+            Detected::Text(String::from_utf8_lossy(&head[..e.valid_up_to()]).to_string())
+        }
+        Err(_) => Detected::Binary,
+    }
+}
+
+fn image_dimensions(format: &str, head: &[u8]) -> Option<(u32, u32)> {
+    let be32 = |at: usize| Some(u32::from_be_bytes(head.get(at..at + 4)?.try_into().ok()?));
+    let le16 = |at: usize| Some(u16::from_le_bytes(head.get(at..at + 2)?.try_into().ok()?) as u32);
+    let le32 = |at: usize| Some(i32::from_le_bytes(head.get(at..at + 4)?.try_into().ok()?).unsigned_abs());
+
+    match format {
+        "PNG" => Some((be32(16)?, be32(20)?)),
+        "GIF" => Some((le16(6)?, le16(8)?)),
+        "BMP" => Some((le32(18)?, le32(22)?)),
+        "JPEG" => jpeg_dimensions(head),
+        _ => None,
+    }
+}
+
+/// Walk JPEG segments up to the first start-of-frame marker
+fn jpeg_dimensions(head: &[u8]) -> Option<(u32, u32)> {
+    let mut pos = 2;
+
+    while pos + 9 < head.len() {
+        if head[pos] != 0xff {
+            return None;
+        }
+        let marker = head[pos + 1];
+        let len = u16::from_be_bytes([head[pos + 2], head[pos + 3]]) as usize;
+
+        // SOF0..SOF15, except DHT (C4), JPG (C8) and DAC (CC)
+        if (0xc0..=0xcf).contains(&marker) && !matches!(marker, 0xc4 | 0xc8 | 0xcc) {
+            let h = u16::from_be_bytes([head[pos + 5], head[pos + 6]]) as u32;
+            let w = u16::from_be_bytes([head[pos + 7], head[pos + 8]]) as u32;
+            return Some((w, h));
+        }
+        pos += 2 + len;
+    }
+
+    None
+}
+
+/// List archive members with the system unzip/tar. Only the first lines
+/// are read; the lister is killed then, or when it runs out of time, so a
+/// huge compressed tarball is never decompressed in full.
+async fn list_archive(path: &str, format: &str, max_lines: usize) -> (Vec<String>, bool) {
+    let mut command = match format {
+        "zip" => Command::new("unzip"),
+        "tar" | "gzip" | "xz" | "zstd" | "bzip2" => Command::new("tar"),
+        _ => return (vec![format!("{} archive", format)], false),
+    };
+    let args = if format == "zip" { ["-Z1", path] } else { ["-tf", path] };
+    let child = command
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .spawn();
+    let compressed = || (vec![format!("{} compressed data", format)], false);
+    let Ok(mut child) = child else {
+        return compressed();
+    };
+    let Some(stdout) = child.stdout.take() else {
+        return compressed();
+    };
+
+    let mut lines = Vec::new();
+    let mut reader = BufReader::new(stdout).lines();
+    let listed = tokio::time::timeout(ARCHIVE_TIMEOUT, async {
+        while lines.len() <= max_lines {
+            match reader.next_line().await {
+                Ok(Some(line)) => lines.push(line),
+                _ => break,
+            }
+        }
+        // Nothing listed: a failed listing is a compressed single file
+        lines.is_empty() && !child.wait().await.is_ok_and(|s| s.success())
+    })
+    .await;
+
+    match listed {
+        Ok(true) => compressed(),
+        Err(_) if lines.is_empty() => (vec![format!("{} archive (listing timed out)", format)], false),
+        // Cut short by the line limit or the timeout
+        timed_out => {
+            let truncated = lines.len() > max_lines || timed_out.is_err();
+            lines.truncate(max_lines);
+            (lines, truncated)
+        }
+    }
+}
+
+/// Classic `offset  hex  |ascii|` dump, 16 bytes per line
+fn hex_dump(bytes: &[u8]) -> Vec<String> {
+    bytes
+        .chunks(16)
+        .enumerate()
+        .map(|(i, chunk)| {
+            let hex: Vec<String> = chunk.iter().map(|b| format!("{:02x}", b)).collect();
+            let ascii: String = chunk
+                .iter()
+                .map(|&b| if b.is_ascii_graphic() || b == b' ' { b as char } else { '.' })
+                .collect();
+            format!("{:08x}  {:<47}  |{}|", i * 16, hex.join(" "), ascii)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect() {
+        assert!(matches!(detect(b"\x89PNG\r\n\x1a\n...."), Detected::Image("PNG")));
+        assert!(matches!(detect(b"PK\x03\x04rest"), Detected::Archive("zip")));
+        assert!(matches!(detect(b"fn main() {}\n"), Detected::Text(_)));
+        assert!(matches!(detect(b"\x7fELF\x02\x01\x01\x00"), Detected::Binary));
+
+        // Multi-byte character cut off by the read limit
+        assert!(matches!(detect("caf\u{e9}".as_bytes().split_last().unwrap().1), Detected::Text(_)));
+    }
+
+    #[tokio::test]
+    async fn test_special_files_are_not_read() {
+        let fifo = std::env::temp_dir().join(format!("faelight-daemon-fifo-{}", std::process::id()));
+        let _ = std::fs::remove_file(&fifo);
+        assert!(std::process::Command::new("mkfifo").arg(&fifo).status().unwrap().success());
+
+        let fifo_preview = preview(&fifo.to_string_lossy(), 10).await.unwrap();
+        assert_eq!(fifo_preview.kind, PreviewKind::Special);
+        assert_eq!(fifo_preview.lines, vec!["FIFO (named pipe)".to_string()]);
+        assert_eq!(preview("/dev/zero", 10).await.unwrap().lines, vec!["Character device".to_string()]);
+
+        std::fs::remove_file(&fifo).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_list_archive() {
+        let root = std::env::temp_dir().join(format!("faelight-daemon-archive-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(root.join("files")).unwrap();
+        for i in 0..50 {
+            std::fs::write(root.join(format!("files/{}", i)), "x").unwrap();
+        }
+        let tarball = root.join("files.tar.gz");
+        let status = std::process::Command::new("tar")
+            .arg("-czf").arg(&tarball).arg("-C").arg(&root).arg("files")
+            .status().unwrap();
+        assert!(status.success());
+
+        let (lines, truncated) = list_archive(&tarball.to_string_lossy(), "gzip", 5).await;
+        assert_eq!(lines.len(), 5);
+        assert!(truncated);
+        let (lines, truncated) = list_archive(&tarball.to_string_lossy(), "gzip", 100).await;
+        assert_eq!(lines.len(), 51);
+        assert!(!truncated);
+
+        // gzip of a single file isn't a tarball
+        let plain = root.join("notes.gz");
+        std::fs::write(&plain, b"\x1f\x8b\x08\x00garbage").unwrap();
+        let (lines, _) = list_archive(&plain.to_string_lossy(), "gzip", 5).await;
+        assert_eq!(lines, vec!["gzip compressed data".to_string()]);
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_hex_dump() {
+        let dump = hex_dump(b"ABC\x00");
+        assert_eq!(dump.len(), 1);
+        assert!(dump[0].starts_with("00000000  41 42 43 00"));
+        assert!(dump[0].ends_with("|ABC.|"));
+    }
+}
@@ -1,21 +1,68 @@
 //! RPC Protocol for faelight-daemon
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Protocol version spoken by this build
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// Optional features advertised in the handshake
+pub const CAPABILITIES: &[&str] = &["search", "git-status", "watch", "multiplex", "file-ops", "stats", "rich-preview"];
 
 /// Commands sent from client to daemon
 #[derive(Debug, Serialize, Deserialize)]
 pub enum Command {
+    /// Handshake: announce the client's protocol version and features
+    Hello {
+        version: u32,
+        #[serde(default)]
+        capabilities: Vec<String>,
+    },
+    
     /// List entries in a directory
     GetEntries { path: String },
     
-    /// Search for files
-    Search { query: String },
+    /// Fuzzy search the zone index
+    Search {
+        query: String,
+        #[serde(default)]
+        limit: Option<usize>,
+    },
     
     /// Get file preview
-    Preview { path: String },
+    Preview {
+        path: String,
+        /// Line limit for text, listings and archives
+        #[serde(default)]
+        max_lines: Option<usize>,
+    },
     
     /// Get git status for directory
     GitStatus { path: String },
     
+    /// Subscribe to change events for a directory
+    Watch { path: String },
+    
+    /// Cancel a directory subscription
+    Unwatch { path: String },
+    
+    /// Copy a file or directory tree to `dst` (progress is pushed while copying)
+    Copy { src: String, dst: String },
+    
+    /// Move a file or directory to `dst`
+    Move { src: String, dst: String },
+    
+    /// Delete a file or directory tree
+    Delete { path: String },
+    
+    /// Rename a file in place
+    Rename { path: String, new_name: String },
+    
+    /// Create a directory
+    Mkdir { path: String },
+    
+    /// Report uptime, load and index size
+    Stats,
+    
     /// Ping to check daemon is alive
     Ping,
     
@@ -26,14 +73,34 @@ pub enum Command {
 /// Responses sent from daemon to client
 #[derive(Debug, Serialize, Deserialize)]
 pub enum Response {
+    /// Handshake reply with the daemon's version and features
+    Hello {
+        version: u32,
+        capabilities: Vec<String>,
+    },
+    
     /// Success with entries
     Entries { entries: Vec<Entry> },
     
-    /// Success with preview
-    Preview { content: String },
+    /// Success with preview.
+    /// `content` is the plain-text rendering older clients display.
+    Preview {
+        content: String,
+        #[serde(default)]
+        preview: Option<Box<FilePreview>>,
+    },
     
-    /// Success with git status
-    GitStatus { status: String },
+    /// Ranked search results, best match first
+    SearchResults { results: Vec<SearchHit> },
+    
+    /// Success with git status, keyed by absolute path
+    GitStatus {
+        repo_root: String,
+        files: BTreeMap<String, GitFileStatus>,
+    },
+    
+    /// Daemon statistics
+    Stats { stats: DaemonStats },
     
     /// Pong response
     Pong,
@@ -42,7 +109,72 @@ pub enum Response {
     Ok,
     
     /// Error occurred
-    Error { message: String },
+    Error {
+        #[serde(default)]
+        code: ErrorCode,
+        message: String,
+    },
+}
+
+/// Machine-readable error category
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ErrorCode {
+    NotFound,
+    PermissionDenied,
+    ZoneLocked,
+    AlreadyExists,
+    Unsupported,
+    InvalidRequest,
+    #[default]
+    Internal,
+    /// A code added by a newer peer, so the message still gets through
+    #[serde(other)]
+    Unknown,
+}
+
+impl ErrorCode {
+    pub fn from_io(err: &std::io::Error) -> Self {
+        match err.kind() {
+            std::io::ErrorKind::NotFound => ErrorCode::NotFound,
+            std::io::ErrorKind::PermissionDenied => ErrorCode::PermissionDenied,
+            std::io::ErrorKind::AlreadyExists => ErrorCode::AlreadyExists,
+            _ => ErrorCode::Internal,
+        }
+    }
+}
+
+impl Command {
+    /// Variant name, used as the key for request counters
+    pub fn name(&self) -> &'static str {
+        match self {
+            Command::Hello { .. } => "Hello",
+            Command::GetEntries { .. } => "GetEntries",
+            Command::Search { .. } => "Search",
+            Command::Preview { .. } => "Preview",
+            Command::GitStatus { .. } => "GitStatus",
+            Command::Watch { .. } => "Watch",
+            Command::Unwatch { .. } => "Unwatch",
+            Command::Copy { .. } => "Copy",
+            Command::Move { .. } => "Move",
+            Command::Delete { .. } => "Delete",
+            Command::Rename { .. } => "Rename",
+            Command::Mkdir { .. } => "Mkdir",
+            Command::Stats => "Stats",
+            Command::Ping => "Ping",
+            Command::Shutdown => "Shutdown",
+        }
+    }
+}
+
+impl Response {
+    pub fn error(code: ErrorCode, message: impl Into<String>) -> Self {
+        Response::Error { code, message: message.into() }
+    }
+    
+    /// Map an I/O failure onto the matching error code
+    pub fn io_error(context: &str, err: &std::io::Error) -> Self {
+        Self::error(ErrorCode::from_io(err), format!("{}: {}", context, err))
+    }
 }
 
 /// File entry information
@@ -54,7 +186,153 @@ pub struct Entry {
     pub size: u64,
 }
 
-/// Message envelope for JSON-RPC
+/// A single search result
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchHit {
+    pub entry: Entry,
+    pub score: i64,
+}
+
+/// Git state of one side (index or worktree) of a file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GitState {
+    Unmodified,
+    Modified,
+    Added,
+    Deleted,
+    Renamed,
+    Copied,
+    TypeChanged,
+    Untracked,
+    Ignored,
+    Conflicted,
+}
+
+/// Git status of a single file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GitFileStatus {
+    pub index: GitState,
+    pub worktree: GitState,
+    /// Original path for renames and copies
+    pub orig_path: Option<String>,
+}
+
+/// Kind of filesystem change
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EventKind {
+    Create,
+    Modify,
+    Delete,
+    Rename,
+}
+
+/// Filesystem change pushed to watching clients
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Event {
+    pub kind: EventKind,
+    pub path: String,
+    /// Previous path for renames
+    pub from: Option<String>,
+}
+
+/// Progress of a long-running operation, tagged with the request id
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Progress {
+    pub bytes_done: u64,
+    pub bytes_total: u64,
+    /// File currently being processed
+    pub current: String,
+}
+
+/// What kind of file a preview describes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PreviewKind {
+    Text,
+    Binary,
+    Image,
+    Archive,
+    Directory,
+    /// FIFO, socket or device, described rather than read
+    Special,
+}
+
+/// Category of a highlighted token
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HighlightKind {
+    Keyword,
+    Type,
+    String,
+    Number,
+    Comment,
+    Heading,
+    Key,
+    Variable,
+}
+
+/// Highlighted range within one preview line, in chars
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HighlightSpan {
+    pub line: usize,
+    pub start: usize,
+    pub end: usize,
+    pub kind: HighlightKind,
+}
+
+/// Typed file preview
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FilePreview {
+    pub kind: PreviewKind,
+    /// File size in bytes
+    pub size: u64,
+    /// Text lines, hex dump, archive members or directory entries
+    pub lines: Vec<String>,
+    /// More content exists than was returned
+    pub truncated: bool,
+    /// Image or archive format
+    pub format: Option<String>,
+    /// Image width and height
+    pub dimensions: Option<(u32, u32)>,
+    /// Detected language for highlighted text
+    pub language: Option<String>,
+    #[serde(default)]
+    pub highlights: Vec<HighlightSpan>,
+}
+
+impl FilePreview {
+    pub fn new(kind: PreviewKind, lines: Vec<String>) -> Self {
+        Self {
+            kind,
+            size: 0,
+            lines,
+            truncated: false,
+            format: None,
+            dimensions: None,
+            language: None,
+            highlights: Vec::new(),
+        }
+    }
+}
+
+/// Runtime statistics reported by the Stats command
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DaemonStats {
+    pub uptime_secs: u64,
+    /// Currently connected clients
+    pub clients: usize,
+    /// Requests handled since startup
+    pub requests: u64,
+    /// Request counts per command
+    pub requests_by_command: BTreeMap<String, u64>,
+    /// Entries in the search index
+    pub index_entries: usize,
+    /// Directories with at least one watcher
+    pub watched_dirs: usize,
+    /// Whether the listening socket was inherited from systemd
+    pub socket_activated: bool,
+}
+
+/// Message envelope for JSON-RPC.
+/// Server-pushed events carry id 0.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Message {
     pub id: u64,
@@ -66,4 +344,23 @@ pub struct Message {
 pub enum MessagePayload {
     Command(Command),
     Response(Response),
+    Event(Event),
+    Progress(Progress),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_unknown_error_code() {
+        let json = r#"{"Error":{"code":"QuotaExceeded","message":"Disk full"}}"#;
+        match serde_json::from_str::<Response>(json).unwrap() {
+            Response::Error { code, message } => {
+                assert_eq!(code, ErrorCode::Unknown);
+                assert_eq!(message, "Disk full");
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
 }
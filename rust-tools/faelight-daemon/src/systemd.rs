@@ -0,0 +1,30 @@
+//! systemd socket activation
+use std::os::unix::io::FromRawFd;
+use std::os::unix::net::UnixListener;
+
+/// First inherited file descriptor (SD_LISTEN_FDS_START)
+const LISTEN_FDS_START: i32 = 3;
+
+/// Take over the listening socket passed by systemd, if any.
+/// The environment is cleared so child processes don't inherit it, which
+/// is only sound while the process is single-threaded: call this from
+/// `main` before the runtime starts.
+pub fn inherited_listener() -> Option<UnixListener> {
+    let pid = std::env::var("LISTEN_PID").ok()?;
+    let fds = std::env::var("LISTEN_FDS").ok()?;
+
+    std::env::remove_var("LISTEN_PID");
+    std::env::remove_var("LISTEN_FDS");
+    std::env::remove_var("LISTEN_FDNAMES");
+
+    // The variables may have leaked from a parent that was activated itself
+    if pid.parse::<u32>().ok()? != std::process::id() {
+        return None;
+    }
+    if fds.parse::<i32>().ok()? < 1 {
+        return None;
+    }
+
+    // SAFETY: systemd hands us ownership of fd 3 when LISTEN_PID is our pid
+    Some(unsafe { UnixListener::from_raw_fd(LISTEN_FDS_START) })
+}
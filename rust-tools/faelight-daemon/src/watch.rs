@@ -0,0 +1,302 @@
+//! Directory watch subscriptions backed by inotify
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+use notify::event::{EventKind as NotifyKind, ModifyKind, RenameMode};
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use tokio::time::Instant;
+use crate::protocol::{Event, EventKind, Message, MessagePayload};
+
+/// How long a MOVED_FROM waits for its MOVED_TO before it counts as a delete
+const RENAME_WINDOW: Duration = Duration::from_millis(50);
+
+pub type ClientId = u64;
+
+/// Outgoing message queue of a connected client
+pub type Outbox = UnboundedSender<Message>;
+
+pub struct WatchHub {
+    watcher: Mutex<RecommendedWatcher>,
+    subscribers: Mutex<HashMap<PathBuf, HashMap<ClientId, Outbox>>>,
+}
+
+impl WatchHub {
+    /// Create the hub and the stream of raw filesystem events feeding it
+    pub fn new() -> notify::Result<(Self, UnboundedReceiver<notify::Event>)> {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+            if let Ok(event) = res {
+                let _ = tx.send(event);
+            }
+        })?;
+
+        let hub = Self {
+            watcher: Mutex::new(watcher),
+            subscribers: Mutex::new(HashMap::new()),
+        };
+        Ok((hub, rx))
+    }
+
+    /// Subscribe a client to changes directly inside `path`
+    pub fn subscribe(&self, path: &Path, client: ClientId, outbox: Outbox) -> std::io::Result<()> {
+        let path = path.canonicalize()?;
+        let mut subscribers = self.subscribers
+            .lock()
+            .map_err(|_| std::io::Error::other("watch table poisoned"))?;
+
+        if !subscribers.contains_key(&path) {
+            let mut watcher = self.watcher
+                .lock()
+                .map_err(|_| std::io::Error::other("watcher poisoned"))?;
+            watcher
+                .watch(&path, RecursiveMode::NonRecursive)
+                .map_err(|e| match e.kind {
+                    notify::ErrorKind::Io(io) => io,
+                    _ => std::io::Error::other(e.to_string()),
+                })?;
+        }
+
+        subscribers.entry(path).or_default().insert(client, outbox);
+        Ok(())
+    }
+
+    /// Drop a client's subscription; the inotify watch goes when nobody is left
+    pub fn unsubscribe(&self, path: &Path, client: ClientId) -> bool {
+        let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        let Ok(mut subscribers) = self.subscribers.lock() else {
+            return false;
+        };
+
+        let Some(clients) = subscribers.get_mut(&path) else {
+            return false;
+        };
+        let removed = clients.remove(&client).is_some();
+
+        if clients.is_empty() {
+            subscribers.remove(&path);
+            if let Ok(mut watcher) = self.watcher.lock() {
+                let _ = watcher.unwatch(&path);
+            }
+        }
+
+        removed
+    }
+
+    /// Number of watched directories
+    pub fn len(&self) -> usize {
+        self.subscribers.lock().map(|s| s.len()).unwrap_or(0)
+    }
+
+    /// Drop every subscription held by a disconnected client
+    pub fn drop_client(&self, client: ClientId) {
+        let paths: Vec<PathBuf> = match self.subscribers.lock() {
+            Ok(subscribers) => subscribers
+                .iter()
+                .filter(|(_, clients)| clients.contains_key(&client))
+                .map(|(path, _)| path.clone())
+                .collect(),
+            Err(_) => return,
+        };
+
+        for path in paths {
+            self.unsubscribe(&path, client);
+        }
+    }
+
+    /// Push an event to every client watching the directories it touches
+    fn publish(&self, event: Event) {
+        let Ok(subscribers) = self.subscribers.lock() else {
+            return;
+        };
+
+        let mut dirs = vec![PathBuf::from(&event.path)];
+        dirs.extend(Path::new(&event.path).parent().map(Path::to_path_buf));
+        if let Some(ref from) = event.from {
+            dirs.extend(Path::new(from).parent().map(Path::to_path_buf));
+        }
+
+        let mut notified: HashMap<ClientId, &Outbox> = HashMap::new();
+        for dir in &dirs {
+            if let Some(clients) = subscribers.get(dir) {
+                for (id, outbox) in clients {
+                    notified.insert(*id, outbox);
+                }
+            }
+        }
+
+        for outbox in notified.values() {
+            let _ = outbox.send(Message {
+                id: 0,
+                payload: MessagePayload::Event(event.clone()),
+            });
+        }
+    }
+}
+
+/// Translate raw inotify events into protocol events and publish them.
+/// `on_change` is called with every touched path (used to keep the index fresh).
+pub async fn dispatch(
+    hub: &WatchHub,
+    mut rx: UnboundedReceiver<notify::Event>,
+    mut on_change: impl FnMut(&Path),
+) {
+    // MOVED_FROM halves waiting for their MOVED_TO, keyed by inotify cookie,
+    // with the time they give up and count as deletes
+    let mut pending: HashMap<usize, (PathBuf, Instant)> = HashMap::new();
+
+    loop {
+        // Flushed on every pass, so a busy directory can't hold them back
+        let now = Instant::now();
+        let expired: Vec<usize> = pending
+            .iter()
+            .filter(|(_, (_, deadline))| *deadline <= now)
+            .map(|(cookie, _)| *cookie)
+            .collect();
+        for cookie in expired {
+            if let Some((from, _)) = pending.remove(&cookie) {
+                // Moved out of every watched directory
+                on_change(&from);
+                hub.publish(make_event(EventKind::Delete, &from, None));
+            }
+        }
+
+        let next_deadline = pending.values().map(|(_, deadline)| *deadline).min();
+        let raw = match next_deadline {
+            None => rx.recv().await,
+            Some(deadline) => match tokio::time::timeout_at(deadline, rx.recv()).await {
+                Ok(raw) => raw,
+                Err(_) => continue,
+            },
+        };
+
+        let Some(raw) = raw else {
+            // No MOVED_TO can come any more
+            for (_, (from, _)) in pending.drain() {
+                on_change(&from);
+                hub.publish(make_event(EventKind::Delete, &from, None));
+            }
+            break;
+        };
+        let Some(path) = raw.paths.first().cloned() else {
+            continue;
+        };
+
+        let event = match raw.kind {
+            NotifyKind::Create(_) => make_event(EventKind::Create, &path, None),
+            NotifyKind::Remove(_) => make_event(EventKind::Delete, &path, None),
+            NotifyKind::Modify(ModifyKind::Name(RenameMode::From)) => {
+                match raw.tracker() {
+                    Some(cookie) => {
+                        pending.insert(cookie, (path, Instant::now() + RENAME_WINDOW));
+                    }
+                    None => {
+                        on_change(&path);
+                        hub.publish(make_event(EventKind::Delete, &path, None));
+                    }
+                }
+                continue;
+            }
+            NotifyKind::Modify(ModifyKind::Name(RenameMode::To)) => {
+                match raw.tracker().and_then(|cookie| pending.remove(&cookie)) {
+                    Some((from, _)) => {
+                        on_change(&from);
+                        make_event(EventKind::Rename, &path, Some(&from))
+                    }
+                    None => make_event(EventKind::Create, &path, None),
+                }
+            }
+            // Already reported through the From/To pair
+            NotifyKind::Modify(ModifyKind::Name(_)) => continue,
+            NotifyKind::Modify(_) => make_event(EventKind::Modify, &path, None),
+            _ => continue,
+        };
+
+        on_change(&path);
+        hub.publish(event);
+    }
+}
+
+fn make_event(kind: EventKind, path: &Path, from: Option<&Path>) -> Event {
+    Event {
+        kind,
+        path: path.to_string_lossy().to_string(),
+        from: from.map(|f| f.to_string_lossy().to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use notify::event::{CreateKind, DataChange};
+
+    fn renamed(mode: RenameMode, path: PathBuf, cookie: usize) -> notify::Event {
+        notify::Event::new(NotifyKind::Modify(ModifyKind::Name(mode))).add_path(path).set_tracker(cookie)
+    }
+
+    /// Feed `raw` through dispatch, `gap` apart, and collect what a
+    /// client watching `dir` receives
+    async fn run(dir: &Path, raw: Vec<notify::Event>, gap: Duration) -> Vec<Event> {
+        let (hub, _) = WatchHub::new().unwrap();
+        let (outbox, mut inbox) = mpsc::unbounded_channel();
+        hub.subscribe(dir, 1, outbox).unwrap();
+
+        let (tx, rx) = mpsc::unbounded_channel();
+        let feed = async move {
+            for event in raw {
+                tx.send(event).unwrap();
+                tokio::time::sleep(gap).await;
+            }
+        };
+        tokio::join!(dispatch(&hub, rx, |_| {}), feed);
+
+        let mut events = Vec::new();
+        while let Ok(Message { payload: MessagePayload::Event(event), .. }) = inbox.try_recv() {
+            events.push(event);
+        }
+        events
+    }
+
+    #[tokio::test]
+    async fn test_rename_pairing_and_flush() {
+        let dir = std::env::temp_dir().join(format!("faelight-daemon-watch-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let dir = dir.canonicalize().unwrap();
+
+        // A From/To pair is one rename
+        let events = run(&dir, vec![
+            renamed(RenameMode::From, dir.join("a"), 1),
+            renamed(RenameMode::To, dir.join("b"), 1),
+        ], Duration::ZERO).await;
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].kind, EventKind::Rename);
+        assert_eq!(events[0].from.as_deref(), Some(dir.join("a").to_string_lossy().as_ref()));
+
+        // An unmatched From becomes a delete while other events keep coming
+        let log = dir.join("build.log");
+        let mut raw = vec![renamed(RenameMode::From, dir.join("gone"), 2)];
+        raw.extend((0..20).map(|_| {
+            notify::Event::new(NotifyKind::Modify(ModifyKind::Data(DataChange::Any))).add_path(log.clone())
+        }));
+        let events = run(&dir, raw, Duration::from_millis(10)).await;
+        let delete = events.iter().position(|e| e.kind == EventKind::Delete).expect("flushed");
+        assert_eq!(events[delete].path, dir.join("gone").to_string_lossy());
+        assert!(delete < events.len() - 1, "flushed only when the stream ended");
+
+        // A To without its From is a create
+        let events = run(&dir, vec![
+            renamed(RenameMode::To, dir.join("new"), 3),
+            notify::Event::new(NotifyKind::Create(CreateKind::File)).add_path(dir.join("other")),
+        ], Duration::ZERO).await;
+        assert!(events.iter().all(|e| e.kind == EventKind::Create));
+
+        // Still a delete when the stream ends before the window does
+        let events = run(&dir, vec![renamed(RenameMode::From, dir.join("left"), 4)], Duration::ZERO).await;
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].kind, EventKind::Delete);
+        assert_eq!(events[0].path, dir.join("left").to_string_lossy());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -52,30 +52,11 @@ fn get_profile() -> String {
 }
 
 fn get_core_state() -> (String, String) {
-    // Check if 0-core directory has immutable attribute (chattr +i)
-    let output = Command::new("lsattr")
-        .arg("-d")
-        .arg("/home/christian/0-core")
-        .output();
-    
-    match output {
-        Ok(result) if result.status.success() => {
-            let stdout = String::from_utf8_lossy(&result.stdout);
-            // lsattr output: "----i----------------- /home/christian/0-core"
-            // Only check the first part (attributes), not the path!
-            let parts: Vec<&str> = stdout.split_whitespace().collect();
-            if let Some(attrs) = parts.first() {
-                // Check if 'i' flag is in the attributes part
-                if attrs.contains('i') {
-                    return ("locked".to_string(), crate::icons::LOCKED.to_string());
-                }
-            }
-            ("unlocked".to_string(), crate::icons::UNLOCKED.to_string())
-        }
-        _ => {
-            // Fallback: assume unlocked if we can't check
-            ("unlocked".to_string(), crate::icons::UNLOCKED.to_string())
-        }
+    // Shared lock state, so fetch agrees with the bar and faelight-git
+    if faelight_zone::is_core_locked() {
+        ("locked".to_string(), crate::icons::LOCKED.to_string())
+    } else {
+        ("unlocked".to_string(), crate::icons::UNLOCKED.to_string())
     }
 }
 
@@ -135,7 +116,7 @@ fn get_shell() -> String {
     std::env::var("SHELL")
         .unwrap_or_else(|_| "unknown".to_string())
         .split('/')
-        .last()
+        .next_back()
         .unwrap_or("unknown")
         .to_string()
 }
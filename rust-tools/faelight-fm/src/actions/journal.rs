@@ -0,0 +1,117 @@
+//! Undo journal: completed file operations, newest last, so `u` can step back
+use std::fs;
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use crate::error::{FmError, Result};
+
+/// Relative to $HOME
+pub const JOURNAL_PATH: &str = ".local/state/faelight-fm/journal.toml";
+
+/// Oldest groups are dropped past this
+const MAX_GROUPS: usize = 100;
+
+/// What it takes to reverse one operation
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum Undo {
+    /// A copy was made at `dst`
+    Copy { dst: PathBuf },
+    Move { src: PathBuf, dst: PathBuf },
+    Rename { from: PathBuf, to: PathBuf },
+    /// `original` went to the trash as `trashed`
+    Trash { original: PathBuf, trashed: String },
+    /// A new file or directory was made
+    Create { path: PathBuf },
+    /// Permissions were `mode` before
+    Chmod { path: PathBuf, mode: u32 },
+}
+
+/// The operations of one command, undone together
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Group {
+    pub label: String,
+    /// Seconds since the Unix epoch
+    pub at: u64,
+    pub ops: Vec<Undo>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Journal {
+    #[serde(default, rename = "group")]
+    pub groups: Vec<Group>,
+}
+
+impl Journal {
+    pub fn load(home: &Path) -> Result<Journal> {
+        let path = home.join(JOURNAL_PATH);
+        if !path.exists() {
+            return Ok(Journal::default());
+        }
+        let content = fs::read_to_string(&path)?;
+        toml::from_str(&content).map_err(|e| FmError::Journal(e.message().to_string()))
+    }
+    
+    pub fn save(&self, home: &Path) -> Result<()> {
+        let path = home.join(JOURNAL_PATH);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let content = toml::to_string(self).map_err(|e| FmError::Journal(e.to_string()))?;
+        fs::write(&path, content)?;
+        Ok(())
+    }
+    
+    /// Record a finished command
+    pub fn push(home: &Path, label: &str, ops: Vec<Undo>) -> Result<()> {
+        if ops.is_empty() {
+            return Ok(());
+        }
+        
+        let mut journal = Journal::load(home).unwrap_or_default();
+        let at = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        journal.groups.push(Group { label: label.to_string(), at, ops });
+        
+        let excess = journal.groups.len().saturating_sub(MAX_GROUPS);
+        journal.groups.drain(..excess);
+        journal.save(home)
+    }
+    
+    /// Take the newest command off the journal
+    pub fn pop(home: &Path) -> Result<Option<Group>> {
+        let mut journal = Journal::load(home)?;
+        let group = journal.groups.pop();
+        if group.is_some() {
+            journal.save(home)?;
+        }
+        Ok(group)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_journal_stack() {
+        let home = std::env::temp_dir().join(format!("faelight-fm-journal-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&home);
+        
+        Journal::push(&home, "Copying a", vec![Undo::Copy { dst: "/tmp/a".into() }]).unwrap();
+        Journal::push(&home, "Deleting 2 items", vec![
+            Undo::Trash { original: "/tmp/b".into(), trashed: "b".to_string() },
+            Undo::Trash { original: "/tmp/c".into(), trashed: "c.2".to_string() },
+        ]).unwrap();
+        Journal::push(&home, "Nothing", vec![]).unwrap();
+        
+        let last = Journal::pop(&home).unwrap().unwrap();
+        assert_eq!(last.label, "Deleting 2 items");
+        assert_eq!(last.ops.len(), 2);
+        assert_eq!(Journal::pop(&home).unwrap().unwrap().ops, vec![Undo::Copy { dst: "/tmp/a".into() }]);
+        assert_eq!(Journal::pop(&home).unwrap(), None);
+        
+        fs::remove_dir_all(&home).unwrap();
+    }
+}
@@ -0,0 +1,295 @@
+//! File operations on one or many paths, checked against zone policy
+pub mod journal;
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use faelight_zone::{Decision, Op};
+use crate::daemon::client::Command;
+use crate::error::Result;
+use crate::fs;
+use crate::fs::trash::{self, TrashItem};
+
+pub use journal::{Journal, Undo};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FileOp {
+    Copy { src: PathBuf, dst: PathBuf },
+    Move { src: PathBuf, dst: PathBuf },
+    Trash { path: PathBuf },
+    Rename { path: PathBuf, new_name: String },
+    /// Put a trashed item back
+    Restore { item: TrashItem },
+    /// Delete a trashed item for good
+    Purge { item: TrashItem },
+    Mkdir { path: PathBuf },
+    /// Create an empty file, or bump its modified time
+    Touch { path: PathBuf },
+    Chmod { path: PathBuf, mode: u32 },
+}
+
+impl FileOp {
+    /// The zone checks this operation needs, same as the daemon does
+    fn checks(&self) -> Vec<(Op, &Path)> {
+        match self {
+            FileOp::Copy { dst, .. } => vec![(Op::Write, dst)],
+            FileOp::Move { src, dst } => vec![(Op::Move, src), (Op::Write, dst)],
+            FileOp::Trash { path } => vec![(Op::Delete, path)],
+            FileOp::Rename { path, .. } => vec![(Op::Move, path)],
+            FileOp::Restore { item } => vec![(Op::Write, &item.original)],
+            FileOp::Purge { .. } => vec![],
+            FileOp::Mkdir { path } | FileOp::Touch { path } | FileOp::Chmod { path, .. } => vec![(Op::Write, path)],
+        }
+    }
+    
+    /// The daemon command for this operation. Trash, touch and chmod
+    /// always run here.
+    pub fn to_command(&self) -> Option<Command> {
+        let s = |p: &Path| p.to_string_lossy().to_string();
+        match self {
+            FileOp::Copy { src, dst } => Some(Command::Copy { src: s(src), dst: s(dst) }),
+            FileOp::Move { src, dst } => Some(Command::Move { src: s(src), dst: s(dst) }),
+            FileOp::Rename { path, new_name } => Some(Command::Rename { path: s(path), new_name: new_name.clone() }),
+            FileOp::Mkdir { path } => Some(Command::Mkdir { path: s(path) }),
+            FileOp::Trash { .. }
+            | FileOp::Restore { .. }
+            | FileOp::Purge { .. }
+            | FileOp::Touch { .. }
+            | FileOp::Chmod { .. } => None,
+        }
+    }
+    
+    /// Run without the daemon. Returns how to undo it.
+    pub fn run_local(&self) -> Result<Option<Undo>> {
+        match self {
+            FileOp::Copy { dst, .. } | FileOp::Move { dst, .. } if dst.exists() => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::AlreadyExists,
+                    format!("{} already exists", dst.display()),
+                ).into());
+            }
+            FileOp::Copy { src, dst } => fs::copy_tree(src, dst)?,
+            FileOp::Move { src, dst } => fs::move_path(src, dst)?,
+            FileOp::Rename { path, new_name } => fs::rename_file(path, new_name)?,
+            FileOp::Trash { path } => {
+                let item = trash::trash(path)?;
+                return Ok(Some(Undo::Trash { original: item.original, trashed: item.name }));
+            }
+            FileOp::Restore { item } => trash::restore(item)?,
+            FileOp::Purge { item } => trash::purge(item)?,
+            FileOp::Mkdir { path } => fs::make_dir(path)?,
+            FileOp::Touch { path } => {
+                let created = fs::touch_file(path)?;
+                return Ok(created.then(|| Undo::Create { path: path.clone() }));
+            }
+            FileOp::Chmod { path, mode } => {
+                let old = fs::set_mode(path, *mode)?;
+                return Ok(Some(Undo::Chmod { path: path.clone(), mode: old }));
+            }
+        }
+        Ok(self.undo())
+    }
+    
+    /// How to undo this operation once it's done
+    pub fn undo(&self) -> Option<Undo> {
+        match self {
+            FileOp::Copy { dst, .. } => Some(Undo::Copy { dst: dst.clone() }),
+            FileOp::Move { src, dst } => Some(Undo::Move { src: src.clone(), dst: dst.clone() }),
+            FileOp::Rename { path, new_name } => Some(Undo::Rename {
+                from: path.clone(),
+                to: path.with_file_name(new_name),
+            }),
+            FileOp::Mkdir { path } => Some(Undo::Create { path: path.clone() }),
+            // Trash names, whether touch created the file and the old mode
+            // are only known once it's done
+            FileOp::Trash { .. }
+            | FileOp::Restore { .. }
+            | FileOp::Purge { .. }
+            | FileOp::Touch { .. }
+            | FileOp::Chmod { .. } => None,
+        }
+    }
+}
+
+/// The operation that reverses a journal entry. Copies and new files go
+/// to the trash rather than being deleted.
+pub fn reverse(undo: &Undo) -> std::result::Result<FileOp, String> {
+    let op = match undo {
+        Undo::Copy { dst: path } | Undo::Create { path } => FileOp::Trash { path: path.clone() },
+        Undo::Move { src, dst } => FileOp::Move { src: dst.clone(), dst: src.clone() },
+        Undo::Rename { from, to } => FileOp::Rename { path: to.clone(), new_name: display_name(from) },
+        Undo::Trash { original, trashed } => {
+            let item = trash::find(trashed)
+                .ok_or_else(|| format!("{} is no longer in the trash", display_name(original)))?;
+            FileOp::Restore { item }
+        }
+        Undo::Chmod { path, mode } => FileOp::Chmod { path: path.clone(), mode: *mode },
+    };
+    Ok(op)
+}
+
+/// Zone verdict for a whole batch: denied if any check is denied,
+/// otherwise the reasons that need confirming
+pub fn check(ops: &[FileOp]) -> std::result::Result<Vec<String>, String> {
+    let mut confirm = Vec::new();
+    
+    for op in ops {
+        for (op, path) in op.checks() {
+            match faelight_zone::can(op, path) {
+                Decision::Allow => {}
+                Decision::Confirm(reason) => {
+                    if !confirm.contains(&reason) {
+                        confirm.push(reason);
+                    }
+                }
+                Decision::Deny(reason) => return Err(format!("{}: {}", display_name(path), reason)),
+            }
+        }
+    }
+    
+    Ok(confirm)
+}
+
+/// Renames from a bulk-rename buffer: one name per line, in the order of
+/// `paths`. Unchanged lines are skipped. Names are taken as typed, spaces
+/// included. Renames onto another renamed path (a swap or a chain) go
+/// through a temporary name first.
+pub fn plan_renames(paths: &[PathBuf], edited: &str) -> std::result::Result<Vec<FileOp>, String> {
+    let names: Vec<&str> = edited.lines().map(|l| l.strip_suffix('\r').unwrap_or(l)).collect();
+    if names.len() != paths.len() {
+        return Err(format!("Expected {} names, got {}", paths.len(), names.len()));
+    }
+    
+    let mut renames = Vec::new();
+    let mut targets = HashSet::new();
+    
+    for (path, name) in paths.iter().zip(names) {
+        if name.is_empty() || name == "." || name == ".." || name.contains('/') {
+            return Err(format!("Invalid name '{}'", name));
+        }
+        
+        let target = path.with_file_name(name);
+        if !targets.insert(target.clone()) {
+            return Err(format!("'{}' is used twice", name));
+        }
+        if target == *path {
+            continue;
+        }
+        renames.push((path, name, target));
+    }
+    
+    // Paths that are renamed away free their names for the others
+    let moving: HashSet<&PathBuf> = renames.iter().map(|(path, _, _)| *path).collect();
+    let mut park = Vec::new();
+    let mut direct = Vec::new();
+    let mut unpark = Vec::new();
+    
+    for (path, name, target) in renames {
+        if !moving.contains(&target) {
+            if target.exists() || target.is_symlink() {
+                return Err(format!("'{}' already exists", name));
+            }
+            direct.push(FileOp::Rename { path: path.clone(), new_name: name.to_string() });
+            continue;
+        }
+        
+        let temp = (park.len()..)
+            .map(|n| path.with_file_name(format!(".faelight-rename-{}-{}", std::process::id(), n)))
+            .find(|t| !t.exists() && !t.is_symlink() && !targets.contains(t))
+            .expect("a free temporary name");
+        park.push(FileOp::Rename { path: path.clone(), new_name: display_name(&temp) });
+        unpark.push(FileOp::Rename { path: temp, new_name: name.to_string() });
+    }
+    
+    Ok(park.into_iter().chain(direct).chain(unpark).collect())
+}
+
+pub fn display_name(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.display().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_plan_renames() {
+        let dir = std::env::temp_dir().join(format!("faelight-fm-rename-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let paths: Vec<PathBuf> = ["a.txt", "b.txt", "c.txt"].iter().map(|n| dir.join(n)).collect();
+        for p in &paths {
+            std::fs::write(p, display_name(p)).unwrap();
+        }
+        
+        let ops = plan_renames(&paths, "a.txt\nbee.txt\nc.md\n").unwrap();
+        assert_eq!(ops, vec![
+            FileOp::Rename { path: paths[1].clone(), new_name: "bee.txt".to_string() },
+            FileOp::Rename { path: paths[2].clone(), new_name: "c.md".to_string() },
+        ]);
+        
+        assert!(plan_renames(&paths, "a.txt\nb.txt").is_err());
+        assert!(plan_renames(&paths, "x\nx\nc.txt").is_err());
+        std::fs::write(dir.join("taken.txt"), "").unwrap();
+        assert!(plan_renames(&paths, "taken.txt\nb.txt\nc.txt").is_err());
+        assert!(plan_renames(&paths, "a.txt\nsub/b.txt\nc.txt").is_err());
+        
+        // Spaces are part of the name
+        let ops = plan_renames(&paths, " a.txt\nb.txt\nc.txt ").unwrap();
+        assert_eq!(ops[0], FileOp::Rename { path: paths[0].clone(), new_name: " a.txt".to_string() });
+        assert_eq!(ops[1], FileOp::Rename { path: paths[2].clone(), new_name: "c.txt ".to_string() });
+        
+        // A swap and a chain both park on a temporary name first
+        let run = |edited: &str| {
+            for op in plan_renames(&paths, edited).unwrap() {
+                op.run_local().unwrap();
+            }
+        };
+        run("b.txt\na.txt\nc.txt");
+        assert_eq!(std::fs::read_to_string(&paths[0]).unwrap(), "b.txt");
+        assert_eq!(std::fs::read_to_string(&paths[1]).unwrap(), "a.txt");
+        run("b.txt\nc.txt\nd.txt");
+        assert_eq!(std::fs::read_to_string(dir.join("d.txt")).unwrap(), "c.txt");
+        let mut left: Vec<String> = std::fs::read_dir(&dir).unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        left.sort();
+        assert_eq!(left, ["b.txt", "c.txt", "d.txt", "taken.txt"]);
+        
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+    
+    #[test]
+    fn test_copy_and_move_directories() {
+        let dir = std::env::temp_dir().join(format!("faelight-fm-copy-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("src/sub")).unwrap();
+        std::fs::write(dir.join("src/sub/file"), "hi").unwrap();
+        std::os::unix::fs::symlink("sub/file", dir.join("src/link")).unwrap();
+        
+        let copy = FileOp::Copy { src: dir.join("src"), dst: dir.join("copy") };
+        assert_eq!(copy.run_local().unwrap(), Some(Undo::Copy { dst: dir.join("copy") }));
+        assert_eq!(std::fs::read_to_string(dir.join("copy/sub/file")).unwrap(), "hi");
+        assert_eq!(std::fs::read_link(dir.join("copy/link")).unwrap(), PathBuf::from("sub/file"));
+        assert!(copy.run_local().is_err());
+        
+        FileOp::Copy { src: dir.join("src/sub/file"), dst: dir.join("file") }.run_local().unwrap();
+        assert_eq!(std::fs::read_to_string(dir.join("file")).unwrap(), "hi");
+        
+        FileOp::Move { src: dir.join("copy"), dst: dir.join("moved") }.run_local().unwrap();
+        assert!(!dir.join("copy").exists());
+        assert!(dir.join("moved/sub/file").exists());
+        
+        // Into itself, even through "..", and with a FIFO inside
+        let nested = FileOp::Copy { src: dir.join("moved"), dst: dir.join("moved/sub/../inner") };
+        assert!(nested.run_local().is_err());
+        assert!(!dir.join("moved/inner").exists());
+        assert!(FileOp::Move { src: dir.join("moved"), dst: dir.join("moved/inner") }.run_local().is_err());
+        assert!(std::process::Command::new("mkfifo").arg(dir.join("moved/pipe")).status().unwrap().success());
+        let with_fifo = FileOp::Copy { src: dir.join("moved"), dst: dir.join("again") };
+        assert!(with_fifo.run_local().unwrap_err().to_string().contains("FIFO"));
+        assert!(!dir.join("again").exists());
+        
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
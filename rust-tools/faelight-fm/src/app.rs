@@ -1,112 +1,283 @@
-use std::path::PathBuf;
-use faelight_fm::git::{self, GitStatus};
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use faelight_fm::git::{self, GitCache};
+use faelight_fm::health::HealthCheck;
 use faelight_fm::error::Result;
 use faelight_fm::model::{FaelightEntry, HealthStatus, IntentInfo, Zone};
-use faelight_fm::{fs, zones, intent};
-use faelight_fm::daemon::DaemonClient;
+use faelight_fm::{actions, command, fs, zones, intent, open};
+use faelight_fm::actions::{FileOp, Journal, Undo};
+use faelight_fm::command::{Command, History};
+use faelight_fm::fs::trash::{self, TrashItem};
+use faelight_fm::open::Handler;
+use faelight_fm::config::{Config, SortBy, View};
+use faelight_fm::daemon::{DaemonClient, OperationUpdate, PendingOperation};
+use faelight_fm::daemon::client::{
+    FilePreview, HighlightSpan, PreviewKind, Response as DaemonResponse,
+};
+use crate::tab::Tab;
 
-#[derive(Debug, Clone, Copy)]
-#[allow(dead_code)]
+/// Lines read for the miller preview column
+const COLUMN_PREVIEW_LINES: usize = 200;
+
+/// `:find` stops after this many matches
+const FIND_LIMIT: usize = 500;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Mode {
     Normal,
     Command,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
 pub enum YankMode {
+    #[default]
     Copy,  // yy - copy file
     Cut,   // dd - move file
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
 pub enum MessageColor {
+    #[default]
     Success,  // Green - operation succeeded
     Error,    // Red - operation failed
     Warning,  // Yellow - zone protection, etc.
 }
 
-impl Default for YankMode {
-    fn default() -> Self {
-        YankMode::Copy
-    }
+/// The trash browser
+pub struct TrashView {
+    pub items: Vec<TrashItem>,
+    pub selected: usize,
+}
+
+/// A diff of the selected entry
+pub struct DiffView {
+    pub name: String,
+    pub lines: Vec<String>,
+    pub scroll: usize,
+}
+
+/// The "open with" chooser
+pub struct OpenWithView {
+    pub path: PathBuf,
+    pub mime: String,
+    pub handlers: Vec<Handler>,
+    pub selected: usize,
+}
+
+/// Results of `:find`
+pub struct FindView {
+    pub query: String,
+    /// Relative to `root`
+    pub results: Vec<PathBuf>,
+    pub root: PathBuf,
+    pub selected: usize,
+}
+
+/// The `:` line being typed
+#[derive(Default)]
+pub struct CommandLine {
+    pub input: String,
+    /// ":" or a question
+    pub prompt: String,
+    /// A critical shell command that runs once DELETE is typed
+    confirm: Option<String>,
+    /// Index into the history while walking it, and what was typed before
+    history_pos: Option<usize>,
+    draft: String,
+    /// Candidates from the last Tab, cycled by pressing it again
+    pub completions: Vec<String>,
+    completion: Option<usize>,
+}
+
+/// What the miller columns show beside the current directory
+#[derive(Default)]
+pub struct Columns {
+    pub parent: Vec<FaelightEntry>,
+    /// The selected entry: a listing or its first lines
+    pub preview: Vec<String>,
+    pub preview_highlights: Vec<HighlightSpan>,
+    parent_of: Option<PathBuf>,
+    preview_of: Option<PathBuf>,
 }
 
-impl Default for MessageColor {
-    fn default() -> Self {
-        MessageColor::Success
+impl Columns {
+    /// Load both side columns again on the next update
+    fn invalidate(&mut self) {
+        self.parent_of = None;
+        self.preview_of = None;
     }
 }
 
+/// Preview of one entry
+struct PreviewText {
+    lines: Vec<String>,
+    /// Type, size and language summary
+    info: Option<String>,
+    highlights: Vec<HighlightSpan>,
+}
+
+/// File operations run one after another, through the daemon if it's there
+struct Batch {
+    label: String,
+    total: usize,
+    ops: VecDeque<FileOp>,
+    current: Option<(FileOp, PendingOperation)>,
+    errors: Vec<String>,
+    /// How to undo what's done so far
+    journal: Vec<Undo>,
+    /// A cut was pasted, so the yank is used up once everything moved
+    clears_yank: bool,
+    /// Reverses the newest journal entry instead of adding one
+    undoes: bool,
+}
+
+impl Batch {
+    fn new(label: String, ops: Vec<FileOp>) -> Self {
+        Self {
+            label,
+            total: ops.len(),
+            ops: ops.into(),
+            current: None,
+            errors: Vec::new(),
+            journal: Vec::new(),
+            clears_yank: false,
+            undoes: false,
+        }
+    }
+    
+    fn run_local(&mut self, op: &FileOp) {
+        match op.run_local() {
+            Ok(undo) => self.journal.extend(undo),
+            Err(e) => self.errors.push(e.to_string()),
+        }
+    }
+    
+    /// "Copying 3 items (2/3)"
+    fn progress(&self) -> String {
+        if self.total == 1 {
+            return self.label.clone();
+        }
+        let started = self.total - self.ops.len();
+        format!("{} ({}/{})", self.label, started, self.total)
+    }
+}
 
 pub struct AppState {
-    pub cwd: PathBuf,
-    pub entries: Vec<FaelightEntry>,
-    pub filtered_entries: Vec<FaelightEntry>,  // NEW: filtered view
-    pub selected: usize,
-    pub zone: Zone,
-    #[allow(dead_code)]
+    pub tabs: Vec<Tab>,
+    pub active: usize,
+    /// The other pane in dual view, and where copy/move to pane go
+    pub other: Option<usize>,
+    pub view: View,
+    pub columns: Columns,
     pub mode: Mode,
     pub running: bool,
     pub help_visible: bool,
     pub info_visible: bool,
-    pub search_mode: bool,      // NEW: search active
-    pub search_query: String,   // NEW: search text
+    pub show_hidden: bool,
     pub preview_visible: bool,  // NEW: preview overlay
     pub preview_content: Option<Vec<String>>,  // NEW: file lines
     pub preview_path: Option<String>,  // NEW: previewed file name
+    pub preview_info: Option<String>,  // Type, size and language summary
+    pub preview_highlights: Vec<HighlightSpan>,  // Syntax spans for preview lines
     pub daemon_client: Option<DaemonClient>,  // Daemon connection
+    batch: Option<Batch>,  // File ops in flight
+    confirm: Option<Batch>,  // File ops waiting for (y/n)
+    home: PathBuf,
     intent_dir: PathBuf,
-    pub yanked_file: Option<PathBuf>,
+    pub trash_view: Option<TrashView>,
+    pub open_with: Option<OpenWithView>,
+    pub find_view: Option<FindView>,
+    pub diff_view: Option<DiffView>,
+    git: GitCache,
+    health: HealthCheck,
+    /// List only entries with a health warning or error
+    pub unhealthy_only: bool,
+    pub command_line: CommandLine,
+    history: History,
+    /// A risky shell command waiting for (y/n)
+    shell_confirm: Option<String>,
+    pub yanked: Vec<PathBuf>,
     pub yank_mode: YankMode,
     pub status_message: Option<String>,
     pub message_color: MessageColor,
+    pub config: Config,
 }
 
 impl AppState {
     pub fn new(start_path: PathBuf) -> Result<Self> {
-        let zone = zones::classify(&start_path);
-        
         let home = std::env::var("HOME")
             .map(PathBuf::from)
             .unwrap_or_else(|_| PathBuf::from("/home"));
         let intent_dir = home.join("0-core/INTENT");
         
+        // A broken fm.toml shouldn't keep the file manager from starting
+        let (config, config_error) = match Config::load(&home) {
+            Ok(config) => (config, None),
+            Err(e) => (Config::default(), Some(format!("{} (using defaults)", e))),
+        };
+        
+        let daemon_client = DaemonClient::connect();
+        let watch = daemon_client.as_ref().and_then(|c| c.subscribe());
+        
         let mut app = Self {
-            cwd: start_path.clone(),
-            entries: Vec::new(),
-            filtered_entries: Vec::new(),
-            selected: 0,
-            zone,
+            tabs: vec![Tab::new(start_path, watch)],
+            active: 0,
+            other: None,
+            view: config.view,
+            columns: Columns::default(),
             mode: Mode::Normal,
             running: true,
             help_visible: false,
             info_visible: false,
-            search_mode: false,
-            search_query: String::new(),
+            show_hidden: config.show_hidden,
             preview_visible: false,
             preview_content: None,
             preview_path: None,
-            daemon_client: {
-                let client = DaemonClient::new();
-                if client.is_available() {
-                    Some(client)
-                } else {
-                    None
-                }
-            },
+            preview_info: None,
+            preview_highlights: Vec::new(),
+            daemon_client,
+            batch: None,
+            confirm: None,
+            home: home.clone(),
             intent_dir,
-            yanked_file: None,
+            trash_view: None,
+            open_with: None,
+            find_view: None,
+            diff_view: None,
+            git: GitCache::default(),
+            health: HealthCheck::new(&home),
+            unhealthy_only: false,
+            command_line: CommandLine::default(),
+            history: History::load(&home),
+            shell_confirm: None,
+            yanked: Vec::new(),
             yank_mode: YankMode::Copy,
             status_message: None,
             message_color: MessageColor::Success,
+            config,
         };
         
         app.reload()?;
+        if app.view == View::Dual {
+            app.view = View::List;
+            app.toggle_dual()?;
+        }
+        if let Some(err) = config_error {
+            app.set_message(err, MessageColor::Warning);
+        }
         Ok(app)
     }
     
+    pub fn tab(&self) -> &Tab {
+        &self.tabs[self.active]
+    }
+    
+    pub fn tab_mut(&mut self) -> &mut Tab {
+        &mut self.tabs[self.active]
+    }
+    
     /// Try to load entries from daemon
-    fn try_daemon_load(&mut self) -> Option<Vec<FaelightEntry>> {
+    fn try_daemon_load(&mut self, dir: &Path) -> Option<Vec<FaelightEntry>> {
         let client = self.daemon_client.as_mut()?;
         
         // Create a tokio runtime for this sync context
@@ -115,7 +286,7 @@ impl AppState {
             .build()
             .ok()?;
         
-        let path_str = self.cwd.to_string_lossy().to_string();
+        let path_str = dir.to_string_lossy().to_string();
         
         // Get entries from daemon
         let response = rt.block_on(async {
@@ -125,7 +296,7 @@ impl AppState {
         
         // Convert daemon entries to FaelightEntry
         if let faelight_fm::daemon::client::Response::Entries { entries } = response {
-            let git_statuses = git::get_status(&self.cwd);
+            let git_statuses = self.git.statuses(dir);
             
             let faelight_entries: Vec<FaelightEntry> = entries.into_iter()
                 .map(|daemon_entry| {
@@ -142,7 +313,8 @@ impl AppState {
                     let git_status = git_statuses
                         .get(&daemon_entry.name)
                         .copied()
-                        .unwrap_or(GitStatus::Clean);
+                        .unwrap_or_default();
+                    let (health, health_reason) = self.health_of(&path);
                     
                     FaelightEntry {
                         path,
@@ -151,7 +323,8 @@ impl AppState {
                         is_symlink: false, // TODO: daemon should track this
                         git_status,
                         zone,
-                        health: HealthStatus::Ok,
+                        health,
+                        health_reason,
                         intent_info,
                     }
                 })
@@ -162,15 +335,25 @@ impl AppState {
         
         None
     }
-    pub fn reload(&mut self) -> Result<()> {
+    
+    /// Health badge of `path`, and why when it isn't ✔
+    fn health_of(&self, path: &Path) -> (HealthStatus, Option<String>) {
+        match self.health.check(path) {
+            Some((status, reason)) => (status, Some(reason)),
+            None => (HealthStatus::Ok, None),
+        }
+    }
+    
+    /// Entries of `dir`, sorted as configured
+    fn load_entries(&mut self, dir: &Path) -> Result<Vec<FaelightEntry>> {
         // Try daemon first, fall back to filesystem
-        self.entries = if let Some(entries) = self.try_daemon_load() {
+        let mut entries = if let Some(entries) = self.try_daemon_load(dir) {
             entries
         } else {
-            
+        
             // Get git status for all files in directory
-            let git_statuses = git::get_status(&self.cwd);
-            let paths = fs::read_dir(&self.cwd)?;
+            let git_statuses = self.git.statuses(dir);
+            let paths = fs::read_dir(dir)?;
             
             paths
                 .into_iter()
@@ -191,7 +374,8 @@ impl AppState {
                         status: i.status.clone(),
                     });
                     
-                    let git_status = git_statuses.get(&name).copied().unwrap_or(GitStatus::Clean);
+                    let git_status = git_statuses.get(&name).copied().unwrap_or_default();
+                    let (health, health_reason) = self.health_of(&path);
                     
                     Some(FaelightEntry {
                         path,
@@ -200,58 +384,134 @@ impl AppState {
                         is_symlink,
                         git_status,
                         zone,
-                        health: HealthStatus::Ok,
+                        health,
+                        health_reason,
                         intent_info,
                     })
                 })
                 .collect()
         };
         
-        self.apply_filter();
-        self.selected = 0;
-        Ok(())
+        fs::sort_entries(&mut entries, &self.config.sort);
+        Ok(entries)
     }
     
-    pub fn apply_filter(&mut self) {
-        if self.search_query.is_empty() {
-            self.filtered_entries = self.entries.clone();
-        } else {
-            let query = self.search_query.to_lowercase();
-            self.filtered_entries = self.entries
-                .iter()
-                .filter(|e| e.name.to_lowercase().contains(&query))
-                .cloned()
-                .collect();
+    /// Reload the active tab, selecting the first entry
+    pub fn reload(&mut self) -> Result<()> {
+        self.reload_tab(self.active)
+    }
+    
+    fn reload_tab(&mut self, index: usize) -> Result<()> {
+        let cwd = self.tabs[index].cwd.clone();
+        let entries = self.load_entries(&cwd)?;
+        
+        let (show_hidden, unhealthy_only) = (self.show_hidden, self.unhealthy_only);
+        let tab = &mut self.tabs[index];
+        if let Some(watch) = tab.watch.as_mut() {
+            watch.watch(&cwd);
         }
         
-        // Keep selection valid
-        if self.selected >= self.filtered_entries.len() && !self.filtered_entries.is_empty() {
-            self.selected = self.filtered_entries.len() - 1;
+        tab.entries = entries;
+        tab.apply_filter(show_hidden, unhealthy_only);
+        tab.selected = 0;
+        tab.visual_anchor = None;
+        // The parent listing and preview may have changed on disk too
+        self.columns.invalidate();
+        Ok(())
+    }
+    
+    /// Reload a tab, keeping the selection
+    fn refresh_tab(&mut self, index: usize) -> Result<()> {
+        let selected_path = self.tabs[index].selected_entry().map(|e| e.path.clone());
+        self.reload_tab(index)?;
+        
+        let tab = &mut self.tabs[index];
+        if let Some(path) = selected_path {
+            if let Some(idx) = tab.filtered_entries.iter().position(|e| e.path == path) {
+                tab.selected = idx;
+            }
+        }
+        Ok(())
+    }
+    
+    /// Reload every tab with fresh git status, after files changed
+    fn refresh_all(&mut self) -> Result<()> {
+        self.git.invalidate();
+        for i in 0..self.tabs.len() {
+            self.refresh_tab(i)?;
+        }
+        Ok(())
+    }
+    
+    /// Reload tabs the daemon reported changes in
+    pub fn refresh_if_changed(&mut self) -> Result<()> {
+        for i in 0..self.tabs.len() {
+            let changed = self.tabs[i].watch.as_ref().map(|w| w.poll_changed()).unwrap_or(false);
+            if changed {
+                self.git.invalidate();
+                self.refresh_tab(i)?;
+            }
         }
+        Ok(())
+    }
+    
+    pub fn apply_filter(&mut self) {
+        let (show_hidden, unhealthy_only) = (self.show_hidden, self.unhealthy_only);
+        self.tab_mut().apply_filter(show_hidden, unhealthy_only);
     }
     
     pub fn start_search(&mut self) {
-        self.search_mode = true;
-        self.search_query.clear();
+        let tab = self.tab_mut();
+        tab.search_mode = true;
+        tab.search_query.clear();
         self.apply_filter();
     }
     
     pub fn exit_search(&mut self) {
-        self.search_mode = false;
-        self.search_query.clear();
+        let tab = self.tab_mut();
+        tab.search_mode = false;
+        tab.search_query.clear();
         self.apply_filter();
     }
     
     pub fn search_add_char(&mut self, c: char) {
-        self.search_query.push(c);
+        self.tab_mut().search_query.push(c);
         self.apply_filter();
     }
     
     pub fn search_backspace(&mut self) {
-        self.search_query.pop();
+        self.tab_mut().search_query.pop();
         self.apply_filter();
     }
     
+    pub fn toggle_hidden(&mut self) {
+        self.show_hidden = !self.show_hidden;
+        let (show_hidden, unhealthy_only) = (self.show_hidden, self.unhealthy_only);
+        for tab in &mut self.tabs {
+            tab.apply_filter(show_hidden, unhealthy_only);
+        }
+        self.columns.invalidate();
+        
+        let text = if self.show_hidden { "Showing hidden files" } else { "Hiding hidden files" };
+        self.set_message(text.to_string(), MessageColor::Success);
+    }
+    
+    pub fn toggle_unhealthy(&mut self) {
+        self.unhealthy_only = !self.unhealthy_only;
+        let (show_hidden, unhealthy_only) = (self.show_hidden, self.unhealthy_only);
+        for tab in &mut self.tabs {
+            tab.apply_filter(show_hidden, unhealthy_only);
+        }
+        
+        let text = if self.unhealthy_only {
+            let count = self.tab().filtered_entries.len();
+            format!("Showing only unhealthy entries ({})", count)
+        } else {
+            "Showing all entries".to_string()
+        };
+        self.set_message(text, MessageColor::Success);
+    }
+    
     pub fn toggle_help(&mut self) {
         self.help_visible = !self.help_visible;
     }
@@ -261,11 +521,10 @@ impl AppState {
     }
     
     pub fn enter_selected(&mut self) -> Result<()> {
-        if let Some(entry) = self.filtered_entries.get(self.selected) {
+        if let Some(entry) = self.selected_entry() {
             if entry.is_dir {
-                self.cwd = entry.path.clone();
-                self.zone = zones::classify(&self.cwd);
-                self.exit_search();  // Clear search when navigating
+                let path = entry.path.clone();
+                self.tab_mut().set_cwd(path);
                 self.reload()?;
             }
         }
@@ -273,24 +532,26 @@ impl AppState {
     }
     
     pub fn go_parent(&mut self) -> Result<()> {
-        if let Some(parent) = self.cwd.parent() {
-            self.cwd = parent.to_path_buf();
-            self.zone = zones::classify(&self.cwd);
-            self.exit_search();  // Clear search when navigating
+        let cwd = self.tab().cwd.clone();
+        if let Some(parent) = cwd.parent() {
+            self.tab_mut().set_cwd(parent.to_path_buf());
             self.reload()?;
+            
+            // Land on the directory we came from
+            let tab = self.tab_mut();
+            if let Some(idx) = tab.filtered_entries.iter().position(|e| e.path == cwd) {
+                tab.selected = idx;
+            }
         }
         Ok(())
     }
     
     pub fn jump_to_zone(&mut self, zone: Zone) -> Result<()> {
-        if let Some(zone_path) = zones::zone_root(zone) {
-            let expanded = shellexpand::tilde(zone_path).to_string();
-            let path = PathBuf::from(&expanded);
-            
+        if let Some(path) = zones::zone_root(&zone) {
             if path.exists() {
-                self.cwd = path.clone();
-                self.zone = zone;
-                self.exit_search();  // Clear search when navigating
+                let tab = self.tab_mut();
+                tab.set_cwd(path);
+                tab.zone = zone;
                 self.reload()?;
             }
         }
@@ -298,19 +559,143 @@ impl AppState {
     }
     
     pub fn select_prev(&mut self) {
-        if self.selected > 0 {
-            self.selected -= 1;
-        }
+        self.tab_mut().select_prev();
     }
     
     pub fn select_next(&mut self) {
-        if self.selected + 1 < self.filtered_entries.len() {
-            self.selected += 1;
-        }
+        self.tab_mut().select_next();
     }
     
     pub fn selected_entry(&self) -> Option<&FaelightEntry> {
-        self.filtered_entries.get(self.selected)
+        self.tab().selected_entry()
+    }
+    
+    pub fn toggle_miller(&mut self) {
+        self.view = if self.view == View::Miller { View::List } else { View::Miller };
+    }
+    
+    /// Show the active tab beside another one, opening a second tab here
+    /// if there's only one
+    pub fn toggle_dual(&mut self) -> Result<()> {
+        if self.view == View::Dual {
+            self.view = View::List;
+            return Ok(());
+        }
+        
+        if self.other.is_none_or(|o| o == self.active || o >= self.tabs.len()) {
+            let index = self.open_tab()?;
+            self.other = Some(index);
+        }
+        self.view = View::Dual;
+        Ok(())
+    }
+    
+    /// Focus the other pane (or the previous tab)
+    pub fn switch_pane(&mut self) {
+        if let Some(other) = self.other {
+            self.other = Some(self.active);
+            self.active = other;
+        }
+    }
+    
+    /// Add a tab in the active tab's directory, returning its index
+    fn open_tab(&mut self) -> Result<usize> {
+        let watch = self.daemon_client.as_ref().and_then(|c| c.subscribe());
+        self.tabs.push(Tab::new(self.tab().cwd.clone(), watch));
+        
+        let index = self.tabs.len() - 1;
+        self.reload_tab(index)?;
+        Ok(index)
+    }
+    
+    pub fn new_tab(&mut self) -> Result<()> {
+        if self.tabs.len() >= 9 {
+            self.set_message("Already 9 tabs".to_string(), MessageColor::Warning);
+            return Ok(());
+        }
+        let index = self.open_tab()?;
+        self.go_tab(index);
+        Ok(())
+    }
+    
+    pub fn close_tab(&mut self) {
+        if self.tabs.len() == 1 {
+            self.set_message("Last tab, quit with q".to_string(), MessageColor::Warning);
+            return;
+        }
+        
+        let closed = self.active;
+        self.tabs.remove(closed);
+        let shift = |i: usize| if i > closed { i - 1 } else { i };
+        
+        self.active = match self.other {
+            Some(other) if other != closed => shift(other),
+            _ => closed.min(self.tabs.len() - 1),
+        };
+        self.other = None;
+        if self.view == View::Dual {
+            self.view = View::List;
+        }
+    }
+    
+    /// Switch to tab `index`. In dual view the other pane stays put.
+    pub fn go_tab(&mut self, index: usize) {
+        if index >= self.tabs.len() {
+            self.set_message(format!("No tab {}", index + 1), MessageColor::Warning);
+            return;
+        }
+        if index == self.active {
+            return;
+        }
+        
+        if self.view != View::Dual || self.other == Some(index) {
+            self.other = Some(self.active);
+        }
+        self.active = index;
+    }
+    
+    pub fn next_tab(&mut self) {
+        self.go_tab((self.active + 1) % self.tabs.len());
+    }
+    
+    pub fn prev_tab(&mut self) {
+        self.go_tab((self.active + self.tabs.len() - 1) % self.tabs.len());
+    }
+    
+    /// Load the miller columns for the current directory and selection
+    pub fn update_columns(&mut self) {
+        if self.view != View::Miller {
+            return;
+        }
+        
+        let cwd = self.tab().cwd.clone();
+        if self.columns.parent_of.as_ref() != Some(&cwd) {
+            self.columns.parent = match cwd.parent() {
+                Some(parent) => {
+                    let mut entries = self.load_entries(parent).unwrap_or_default();
+                    entries.retain(|e| self.show_hidden || !e.name.starts_with('.'));
+                    entries
+                }
+                None => Vec::new(),
+            };
+            self.columns.parent_of = Some(cwd);
+        }
+        
+        let selected = self.selected_entry().cloned();
+        if self.columns.preview_of.as_ref() != selected.as_ref().map(|e| &e.path) {
+            let preview = selected.as_ref().map(|e| self.read_preview(e, COLUMN_PREVIEW_LINES));
+            self.columns.preview_of = selected.map(|e| e.path);
+            match preview {
+                Some(preview) => {
+                    self.columns.preview = preview.lines;
+                    self.columns.preview_highlights = preview.highlights;
+                }
+                None => {
+                    self.columns.preview.clear();
+                    self.columns.preview_highlights.clear();
+                }
+            }
+        }
     }
     
     pub fn quit(&mut self) {
@@ -322,40 +707,60 @@ impl AppState {
         if !self.preview_visible {
             self.preview_content = None;
             self.preview_path = None;
+            self.preview_info = None;
+            self.preview_highlights.clear();
         }
     }
     pub fn load_preview(&mut self) {
-        // Clone the entry data first to avoid borrow conflicts
-        let entry_data = self.selected_entry().map(|e| (e.path.clone(), e.name.clone(), e.is_dir, e.is_symlink));
-        
-        if let Some((path, name, is_dir, is_symlink)) = entry_data {
-            if !is_dir && !is_symlink {
-                match std::fs::read_to_string(&path) {
-                    Ok(content) => {
-                        let lines: Vec<String> = content
-                            .lines()
-                            .take(40)
-                            .map(|l| l.to_string())
-                            .collect();
-                        self.preview_content = Some(lines);
-                        self.preview_path = Some(name);
-                    }
-                    Err(_) => {
-                        self.preview_content = Some(vec!["[Binary or unreadable file]".to_string()]);
-                        self.preview_path = Some(name);
-                    }
-                }
-            } else if is_dir {
-                self.preview_content = Some(vec!["[Directory - no preview]".to_string()]);
-                self.preview_path = Some(name);
-            } else {
-                self.preview_content = Some(vec!["[Symlink - no preview]".to_string()]);
-                self.preview_path = Some(name);
-            }
+        let Some(entry) = self.selected_entry().cloned() else {
+            return;
+        };
+        
+        let preview = self.read_preview(&entry, self.config.preview.lines);
+        self.preview_info = preview.info;
+        self.preview_content = Some(preview.lines);
+        self.preview_highlights = preview.highlights;
+        self.preview_path = Some(entry.name);
+    }
+    
+    /// Up to `lines` lines of an entry: a listing for directories
+    fn read_preview(&mut self, entry: &FaelightEntry, lines: usize) -> PreviewText {
+        // The daemon detects file types and never loads whole files
+        let rich = self.daemon_client.as_mut().and_then(|c| c.preview(&entry.path, lines));
+        if let Some(preview) = rich {
+            let highlights = if self.config.preview.highlight { preview.highlights.clone() } else { Vec::new() };
+            return PreviewText {
+                info: Some(preview_summary(&preview)),
+                lines: preview.lines,
+                highlights,
+            };
         }
+        
+        let lines = if entry.is_symlink {
+            vec!["[Symlink - no preview]".to_string()]
+        } else if entry.is_dir {
+            match fs::read_dir(&entry.path) {
+                Ok(paths) => paths
+                    .iter()
+                    .filter_map(|p| p.file_name().map(|n| n.to_string_lossy().to_string()))
+                    .filter(|n| self.show_hidden || !n.starts_with('.'))
+                    .take(lines)
+                    .collect(),
+                Err(_) => vec!["[Unreadable directory]".to_string()],
+            }
+        } else if !std::fs::metadata(&entry.path).is_ok_and(|m| m.is_file()) {
+            // Reading a FIFO or device would block or never end
+            vec!["[Special file - no preview]".to_string()]
+        } else {
+            match std::fs::read_to_string(&entry.path) {
+                Ok(content) => content.lines().take(lines).map(|l| l.to_string()).collect(),
+                Err(_) => vec!["[Binary or unreadable file]".to_string()],
+            }
+        };
+        PreviewText { lines, info: None, highlights: Vec::new() }
     }
-
-    /// Edit the selected file in nvim
+    
+    /// Edit the selected file in the configured editor
     pub fn edit_selected<B: ratatui::backend::Backend>(
         &mut self,
         terminal: &mut ratatui::Terminal<B>,
@@ -370,127 +775,1056 @@ impl AppState {
             return Ok(());
         }
         
-        let path = entry.path.to_string_lossy().to_string();
-        
-        // Fully cleanup terminal before launching nvim
-        crossterm::terminal::disable_raw_mode()?;
-        crossterm::execute!(
-            std::io::stdout(),
-            crossterm::terminal::LeaveAlternateScreen,
-            crossterm::cursor::Show
-        )?;
-        
-        // Launch nvim
-        let _status = std::process::Command::new("nvim")
-            .arg(&path)
-            .status()?;
-        
-        // Fully restore terminal state
-        crossterm::execute!(
-            std::io::stdout(),
-            crossterm::terminal::EnterAlternateScreen,
-            crossterm::terminal::Clear(crossterm::terminal::ClearType::All),
-            crossterm::cursor::Hide
-        )?;
-        crossterm::terminal::enable_raw_mode()?;
-        
-        // Force terminal backend to clear and redraw
-        terminal.clear()?;
+        self.run_editor(terminal, &entry.path)?;
         
         // Reload in case file changed
-        self.reload()?;
-        
-        Ok(())
+        self.refresh_all()
     }
-
-    /// Yank (copy/cut) the selected file
-    pub fn yank_file(&mut self, mode: YankMode) {
-        if let Some(entry) = self.get_selected_entry() {
-            let path = entry.path.clone();
-            let filename = path.file_name().unwrap().to_string_lossy().to_string();
-            
-            self.yanked_file = Some(path);
-            self.yank_mode = mode;
-            
-            let mode_text = match mode {
-                YankMode::Copy => "Copy",
-                YankMode::Cut => "Cut",
-            };
-            
-            self.status_message = Some(format!(
-                "Yanked: {} ({})",
-                filename,
-                mode_text
-            ));
-            self.message_color = MessageColor::Success;
+    
+    /// Run the editor on `path` outside the TUI. Returns whether it exited
+    /// successfully.
+    fn run_editor<B: ratatui::backend::Backend>(
+        &mut self,
+        terminal: &mut ratatui::Terminal<B>,
+        path: &Path,
+    ) -> Result<bool> {
+        let (program, mut args) = self.config.editor_command();
+        args.push(path.to_string_lossy().to_string());
+        self.run_in_terminal(terminal, &program, &args)
+    }
+    
+    /// Run a program in the terminal, with the TUI suspended until it exits
+    fn run_in_terminal<B: ratatui::backend::Backend>(
+        &mut self,
+        terminal: &mut ratatui::Terminal<B>,
+        program: &str,
+        args: &[String],
+    ) -> Result<bool> {
+        suspend_tui()?;
+        let status = std::process::Command::new(program)
+            .args(args)
+            .status();
+        resume_tui(terminal)?;
+        
+        match status {
+            Ok(status) => Ok(status.success()),
+            Err(e) => {
+                self.set_message(format!("Cannot run {}: {}", program, e), MessageColor::Error);
+                Ok(false)
+            }
         }
     }
     
-    /// Paste the yanked file
-    pub fn paste_file(&mut self) -> Result<()> {
-        let src = match &self.yanked_file {
-            Some(path) => path.clone(),
+    /// Enter the selected directory, or open the selected file with its
+    /// first handler
+    pub fn open_selected<B: ratatui::backend::Backend>(
+        &mut self,
+        terminal: &mut ratatui::Terminal<B>,
+    ) -> Result<()> {
+        let Some(entry) = self.selected_entry().cloned() else {
+            return Ok(());
+        };
+        if entry.is_dir {
+            return self.enter_selected();
+        }
+        
+        let mime = open::mime::detect(&entry.path);
+        let handlers = open::handlers(&entry.path, &mime, &self.config.open, self.config.editor_command());
+        match handlers.first() {
+            Some(handler) => self.run_handler(terminal, &handler.clone(), &entry.path),
             None => {
-                self.status_message = Some("No file yanked".to_string());
-                self.message_color = MessageColor::Warning;
-                return Ok(());
+                self.set_message(format!("Nothing opens {} ({}), add an [[open]] rule", entry.name, mime), MessageColor::Warning);
+                Ok(())
             }
+        }
+    }
+    
+    /// List everything that can open the selected entry
+    pub fn open_with_selected(&mut self) {
+        let Some(entry) = self.selected_entry().cloned() else {
+            return;
         };
         
-        if self.zone == Zone::Core && faelight_fm::fs::is_core_locked() {
-            self.status_message = Some("Cannot modify locked Core zone".to_string());
-            self.message_color = MessageColor::Warning;
+        let mime = open::mime::detect(&entry.path);
+        let handlers = open::handlers(&entry.path, &mime, &self.config.open, self.config.editor_command());
+        if handlers.is_empty() {
+            self.set_message(format!("Nothing opens {} ({})", entry.name, mime), MessageColor::Warning);
+            return;
+        }
+        self.open_with = Some(OpenWithView { path: entry.path, mime, handlers, selected: 0 });
+    }
+    
+    pub fn close_open_with(&mut self) {
+        self.open_with = None;
+    }
+    
+    pub fn open_with_next(&mut self) {
+        if let Some(view) = self.open_with.as_mut() {
+            if view.selected + 1 < view.handlers.len() {
+                view.selected += 1;
+            }
+        }
+    }
+    
+    pub fn open_with_prev(&mut self) {
+        if let Some(view) = self.open_with.as_mut() {
+            view.selected = view.selected.saturating_sub(1);
+        }
+    }
+    
+    /// Open the file with the chosen handler
+    pub fn open_with_run<B: ratatui::backend::Backend>(
+        &mut self,
+        terminal: &mut ratatui::Terminal<B>,
+    ) -> Result<()> {
+        let Some(view) = self.open_with.take() else {
             return Ok(());
+        };
+        match view.handlers.get(view.selected) {
+            Some(handler) => self.run_handler(terminal, handler, &view.path),
+            None => Ok(()),
         }
+    }
+    
+    /// Terminal programs take over the screen until they exit, others are
+    /// started detached so they outlive the TUI
+    fn run_handler<B: ratatui::backend::Backend>(
+        &mut self,
+        terminal: &mut ratatui::Terminal<B>,
+        handler: &Handler,
+        path: &Path,
+    ) -> Result<()> {
+        let name = actions::display_name(path);
         
-        let filename = src.file_name().unwrap();
-        let dst = self.cwd.join(filename);
-        
-        if dst.exists() {
-            self.status_message = Some(format!(
-                "File '{}' already exists",
-                filename.to_string_lossy()
-            ));
-            self.message_color = MessageColor::Error;
-            return Ok(());
+        if handler.terminal {
+            self.run_in_terminal(terminal, &handler.program, &handler.args)?;
+            return self.refresh_all();
         }
         
-        let result = match self.yank_mode {
-            YankMode::Copy => {
-                faelight_fm::fs::copy_file(&src, &dst)?;
-                format!("Copied {}", filename.to_string_lossy())
+        use std::os::unix::process::CommandExt;
+        let child = std::process::Command::new(&handler.program)
+            .args(&handler.args)
+            .stdin(std::process::Stdio::null())
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null())
+            .process_group(0)
+            .spawn();
+        
+        match child {
+            Ok(mut child) => {
+                // Reap it whenever it exits
+                std::thread::spawn(move || child.wait());
+                self.set_message(format!("Opened {} with {}", name, handler.name), MessageColor::Success);
             }
-            YankMode::Cut => {
-                faelight_fm::fs::move_file(&src, &dst)?;
-                self.yanked_file = None;
-                format!("Moved {}", filename.to_string_lossy())
+            Err(e) => {
+                self.set_message(format!("Cannot run {}: {}", handler.program, e), MessageColor::Error);
             }
-        };
-        
-        self.status_message = Some(result);
-        self.message_color = MessageColor::Success;
-        self.reload()?;
-        
+        }
         Ok(())
     }
     
-    /// Get selected entry
-    fn get_selected_entry(&self) -> Option<&FaelightEntry> {
-        if self.search_mode && !self.filtered_entries.is_empty() {
-            self.filtered_entries.get(self.selected)
-        } else {
-            self.entries.get(self.selected)
+    /// Stage the marked entries, or the selected one
+    pub fn git_stage(&mut self) -> Result<()> {
+        let targets = self.targets();
+        if targets.is_empty() {
+            return Ok(());
+        }
+        match git::ops::stage(&targets) {
+            Ok(()) => self.set_message(format!("Staged {}", describe(&targets)), MessageColor::Success),
+            Err(e) => self.set_message(e.to_string(), MessageColor::Error),
         }
+        self.clear_marks();
+        self.refresh_all()
     }
     
-    /// Set status message
-    pub fn set_message(&mut self, msg: String, color: MessageColor) {
-        self.status_message = Some(msg);
-        self.message_color = color;
+    /// Unstage the marked entries, or the selected one
+    pub fn git_unstage(&mut self) -> Result<()> {
+        let targets = self.targets();
+        if targets.is_empty() {
+            return Ok(());
+        }
+        match git::ops::unstage(&targets) {
+            Ok(()) => self.set_message(format!("Unstaged {}", describe(&targets)), MessageColor::Success),
+            Err(e) => self.set_message(e.to_string(), MessageColor::Error),
+        }
+        self.clear_marks();
+        self.refresh_all()
     }
     
-    /// Clear status message  
-    pub fn clear_message(&mut self) {
-        self.status_message = None;
+    /// Show staged and unstaged changes to the selected entry
+    pub fn git_diff(&mut self) {
+        let Some(entry) = self.selected_entry().cloned() else {
+            return;
+        };
+        match git::ops::diff(&entry.path) {
+            Ok(lines) if lines.is_empty() => {
+                self.set_message(format!("No changes in {}", entry.name), MessageColor::Success);
+            }
+            Ok(lines) => self.diff_view = Some(DiffView { name: entry.name, lines, scroll: 0 }),
+            Err(e) => self.set_message(e.to_string(), MessageColor::Error),
+        }
+    }
+    
+    pub fn close_diff(&mut self) {
+        self.diff_view = None;
+    }
+    
+    pub fn diff_scroll(&mut self, down: bool) {
+        if let Some(view) = self.diff_view.as_mut() {
+            view.scroll = if down {
+                (view.scroll + 1).min(view.lines.len().saturating_sub(1))
+            } else {
+                view.scroll.saturating_sub(1)
+            };
+        }
+    }
+    
+    /// Marked paths, or the selected entry when nothing is marked
+    pub fn targets(&self) -> Vec<PathBuf> {
+        if !self.tab().marked.is_empty() {
+            return self.tab().marked.iter().cloned().collect();
+        }
+        self.selected_entry().map(|e| vec![e.path.clone()]).unwrap_or_default()
+    }
+    
+    pub fn toggle_mark(&mut self) {
+        if let Some(path) = self.selected_entry().map(|e| e.path.clone()) {
+            let marked = &mut self.tab_mut().marked;
+            if !marked.remove(&path) {
+                marked.insert(path);
+            }
+            self.select_next();
+        }
     }
+    
+    /// Start a range at the selection, or mark the range and end it
+    pub fn toggle_visual(&mut self) {
+        let tab = self.tab_mut();
+        match tab.visual_anchor.take() {
+            Some(anchor) => {
+                let range = anchor.min(tab.selected)..=anchor.max(tab.selected);
+                let paths: Vec<PathBuf> = tab.filtered_entries[range].iter().map(|e| e.path.clone()).collect();
+                tab.marked.extend(paths);
+                let count = tab.marked.len();
+                self.set_message(format!("{} marked", count), MessageColor::Success);
+            }
+            None if !tab.filtered_entries.is_empty() => {
+                tab.visual_anchor = Some(tab.selected);
+                self.set_message("Move to extend the range, V to mark it".to_string(), MessageColor::Success);
+            }
+            None => {}
+        }
+    }
+    
+    pub fn cancel_visual(&mut self) {
+        self.tab_mut().visual_anchor = None;
+        self.status_message = None;
+    }
+    
+    /// Drop the marks and range of the active tab
+    pub fn clear_marks(&mut self) {
+        let tab = self.tab_mut();
+        tab.visual_anchor = None;
+        tab.marked.clear();
+    }
+    
+    /// Yank (copy/cut) the marked files, or the selected one
+    pub fn yank_file(&mut self, mode: YankMode) {
+        let targets = self.targets();
+        if targets.is_empty() {
+            return;
+        }
+        
+        let mode_text = match mode {
+            YankMode::Copy => "Copy",
+            YankMode::Cut => "Cut",
+        };
+        
+        self.status_message = Some(format!(
+            "Yanked: {} ({})",
+            describe(&targets),
+            mode_text
+        ));
+        self.message_color = MessageColor::Success;
+        
+        self.yanked = targets;
+        self.yank_mode = mode;
+        self.clear_marks();
+    }
+    
+    /// Paste the yanked files into cwd
+    pub fn paste_file(&mut self) -> Result<()> {
+        if self.yanked.is_empty() {
+            self.status_message = Some("No file yanked".to_string());
+            self.message_color = MessageColor::Warning;
+            return Ok(());
+        }
+        
+        let mut batch = transfer(&self.yanked, &self.tab().cwd, self.yank_mode);
+        batch.clears_yank = self.yank_mode == YankMode::Cut;
+        self.start_ops(batch, None)
+    }
+    
+    /// Copy or move the marked files, or the selected one, into the
+    /// other pane's directory
+    pub fn send_to_pane(&mut self, mode: YankMode) -> Result<()> {
+        let Some(other) = self.other.filter(|&o| o != self.active) else {
+            let keys = self.config.keymap.keys_for(&faelight_fm::config::Action::DualPane);
+            self.set_message(format!("No other pane, open one with {}", keys), MessageColor::Warning);
+            return Ok(());
+        };
+        let targets = self.targets();
+        if targets.is_empty() {
+            return Ok(());
+        }
+        
+        let batch = transfer(&targets, &self.tabs[other].cwd, mode);
+        self.start_ops(batch, None)
+    }
+    
+    /// Move the marked files, or the selected one, to the trash
+    pub fn delete_selected(&mut self) -> Result<()> {
+        let targets = self.targets();
+        if targets.is_empty() {
+            return Ok(());
+        }
+        
+        let label = format!("Trashing {}", describe(&targets));
+        let ops = targets.into_iter().map(|path| FileOp::Trash { path }).collect();
+        self.start_ops(Batch::new(label, ops), None)
+    }
+    
+    /// Reverse the newest command in the undo journal
+    pub fn undo(&mut self) -> Result<()> {
+        let group = match Journal::load(&self.home) {
+            Ok(journal) => journal.groups.last().cloned(),
+            Err(e) => {
+                self.set_message(e.to_string(), MessageColor::Error);
+                return Ok(());
+            }
+        };
+        let Some(group) = group else {
+            self.set_message("Nothing to undo".to_string(), MessageColor::Warning);
+            return Ok(());
+        };
+        
+        let ops: std::result::Result<Vec<FileOp>, String> = group.ops.iter().rev().map(actions::reverse).collect();
+        match ops {
+            Ok(ops) => {
+                let mut batch = Batch::new(format!("Undo: {}", group.label), ops);
+                batch.undoes = true;
+                self.start_ops(batch, None)
+            }
+            Err(e) => {
+                // It can never be undone, so don't let it block older entries
+                Journal::pop(&self.home)?;
+                self.set_message(format!("Cannot undo {}: {}", group.label, e), MessageColor::Error);
+                Ok(())
+            }
+        }
+    }
+    
+    pub fn open_trash(&mut self) {
+        self.trash_view = Some(TrashView { items: trash::list(), selected: 0 });
+    }
+    
+    pub fn close_trash(&mut self) {
+        self.trash_view = None;
+    }
+    
+    pub fn trash_select_next(&mut self) {
+        if let Some(view) = self.trash_view.as_mut() {
+            if view.selected + 1 < view.items.len() {
+                view.selected += 1;
+            }
+        }
+    }
+    
+    pub fn trash_select_prev(&mut self) {
+        if let Some(view) = self.trash_view.as_mut() {
+            view.selected = view.selected.saturating_sub(1);
+        }
+    }
+    
+    fn selected_trash_item(&self) -> Option<TrashItem> {
+        self.trash_view.as_ref().and_then(|v| v.items.get(v.selected)).cloned()
+    }
+    
+    /// Put the selected trash item back where it came from
+    pub fn restore_trash_item(&mut self) -> Result<()> {
+        let Some(item) = self.selected_trash_item() else {
+            return Ok(());
+        };
+        let label = format!("Restoring {}", item.name);
+        self.start_ops(Batch::new(label, vec![FileOp::Restore { item }]), None)
+    }
+    
+    /// Delete the selected trash item for good, after confirming
+    pub fn purge_trash_item(&mut self) -> Result<()> {
+        let Some(item) = self.selected_trash_item() else {
+            return Ok(());
+        };
+        let label = format!("Purging {}", item.name);
+        let question = format!("Delete {} for good?", item.name);
+        self.start_ops(Batch::new(label, vec![FileOp::Purge { item }]), Some(question))
+    }
+    
+    /// Edit the names of the marked files (or the selected one) in the
+    /// editor and rename them to what was saved
+    pub fn bulk_rename<B: ratatui::backend::Backend>(
+        &mut self,
+        terminal: &mut ratatui::Terminal<B>,
+    ) -> Result<()> {
+        let targets = self.targets();
+        if targets.is_empty() {
+            return Ok(());
+        }
+        
+        let names: Vec<String> = targets.iter().map(|p| actions::display_name(p)).collect();
+        // Private to this user, and never through a link someone left there
+        let state = self.home.join(".local/state/faelight-fm");
+        std::fs::create_dir_all(&state)?;
+        let buffer = state.join(format!("rename-{}.txt", std::process::id()));
+        let _ = std::fs::remove_file(&buffer);
+        {
+            use std::io::Write;
+            use std::os::unix::fs::OpenOptionsExt;
+            let mut file = std::fs::OpenOptions::new().write(true).create_new(true).mode(0o600).open(&buffer)?;
+            file.write_all((names.join("\n") + "\n").as_bytes())?;
+        }
+        
+        let saved = self.run_editor(terminal, &buffer)?;
+        let edited = std::fs::read_to_string(&buffer);
+        let _ = std::fs::remove_file(&buffer);
+        if !saved {
+            if self.status_message.is_none() {
+                self.set_message("Rename cancelled".to_string(), MessageColor::Warning);
+            }
+            return Ok(());
+        }
+        
+        match actions::plan_renames(&targets, &edited?) {
+            Ok(ops) if ops.is_empty() => {
+                self.set_message("Nothing renamed".to_string(), MessageColor::Success);
+            }
+            Ok(ops) => {
+                let label = format!("Renaming {}", describe_count(ops.len()));
+                self.start_ops(Batch::new(label, ops), None)?;
+            }
+            Err(e) => self.set_message(format!("Rename: {}", e), MessageColor::Error),
+        }
+        Ok(())
+    }
+    
+    /// Check a batch against zone policy, then run it or ask first.
+    /// `question` is asked even when the zones allow everything.
+    fn start_ops(&mut self, batch: Batch, question: Option<String>) -> Result<()> {
+        if self.batch.is_some() {
+            self.set_message("Another operation is still running".to_string(), MessageColor::Warning);
+            return Ok(());
+        }
+        
+        let ops: Vec<FileOp> = batch.ops.iter().cloned().collect();
+        let reasons = match actions::check(&ops) {
+            Ok(reasons) => reasons,
+            Err(reason) => {
+                self.set_message(reason, MessageColor::Warning);
+                return Ok(());
+            }
+        };
+        
+        let mut prompt: Vec<String> = question.into_iter().collect();
+        if !reasons.is_empty() {
+            prompt.push(format!("This will {}.", reasons.join(", ")));
+        }
+        if prompt.is_empty() {
+            return self.run_ops(batch);
+        }
+        
+        self.set_message(format!("{} Continue? (y/n)", prompt.join(" ")), MessageColor::Warning);
+        self.confirm = Some(batch);
+        Ok(())
+    }
+    
+    pub fn has_confirm(&self) -> bool {
+        self.confirm.is_some() || self.shell_confirm.is_some()
+    }
+    
+    /// Answer the pending (y/n) question
+    pub fn answer_confirm<B: ratatui::backend::Backend>(
+        &mut self,
+        yes: bool,
+        terminal: &mut ratatui::Terminal<B>,
+    ) -> Result<()> {
+        if !yes {
+            self.confirm = None;
+            self.shell_confirm = None;
+            self.set_message("Cancelled".to_string(), MessageColor::Warning);
+            return Ok(());
+        }
+        
+        if let Some(cmd) = self.shell_confirm.take() {
+            return self.run_shell(terminal, &cmd);
+        }
+        match self.confirm.take() {
+            Some(batch) => self.run_ops(batch),
+            None => Ok(()),
+        }
+    }
+    
+    /// Hand the batch to the daemon, one operation at a time, or run it here
+    fn run_ops(&mut self, mut batch: Batch) -> Result<()> {
+        self.clear_marks();
+        
+        if self.daemon_client.as_ref().is_some_and(|c| c.supports("file-ops")) {
+            self.set_message(format!("{}...", batch.label), MessageColor::Success);
+            self.batch = Some(batch);
+            self.start_next_op();
+            return Ok(());
+        }
+        
+        while let Some(op) = batch.ops.pop_front() {
+            batch.run_local(&op);
+        }
+        self.batch = Some(batch);
+        self.finish_batch()
+    }
+    
+    /// Send the next operation to the daemon, running trash operations
+    /// here on the way
+    fn start_next_op(&mut self) {
+        let Some(batch) = self.batch.as_mut() else {
+            return;
+        };
+        
+        batch.current = None;
+        while let Some(op) = batch.ops.pop_front() {
+            match (op.to_command(), self.daemon_client.as_ref()) {
+                (Some(cmd), Some(client)) => {
+                    let operation = client.spawn_command(cmd);
+                    batch.current = Some((op, operation));
+                    return;
+                }
+                _ => batch.run_local(&op),
+            }
+        }
+    }
+    
+    /// Report progress of a running daemon operation and finish it
+    pub fn poll_operation(&mut self) -> Result<()> {
+        let Some(batch) = self.batch.as_mut() else {
+            return Ok(());
+        };
+        let Some((op, operation)) = batch.current.as_ref() else {
+            return Ok(());
+        };
+        
+        let mut message = None;
+        let mut done = false;
+        for update in operation.poll() {
+            match update {
+                OperationUpdate::Progress(p) if p.bytes_total > 0 => {
+                    let pct = p.bytes_done * 100 / p.bytes_total;
+                    message = Some(format!("{}... {}%", batch.progress(), pct));
+                }
+                OperationUpdate::Progress(_) => {}
+                OperationUpdate::Done(response) => {
+                    if let DaemonResponse::Error { message, .. } = response {
+                        batch.errors.push(message);
+                    } else {
+                        batch.journal.extend(op.undo());
+                    }
+                    done = true;
+                    break;
+                }
+            }
+        }
+        
+        if let Some(message) = message {
+            self.set_message(message, MessageColor::Success);
+        }
+        if !done {
+            return Ok(());
+        }
+        
+        self.start_next_op();
+        if self.batch.as_ref().is_some_and(|b| b.current.is_some()) {
+            let progress = self.batch.as_ref().unwrap().progress();
+            self.set_message(format!("{}...", progress), MessageColor::Success);
+            return Ok(());
+        }
+        self.finish_batch()
+    }
+    
+    fn finish_batch(&mut self) -> Result<()> {
+        let Some(mut batch) = self.batch.take() else {
+            return Ok(());
+        };
+        
+        // An undo leaves its entry in place until every step went through
+        if !batch.undoes {
+            if let Err(e) = Journal::push(&self.home, &batch.label, batch.journal) {
+                batch.errors.push(e.to_string());
+            }
+        } else if batch.errors.is_empty() {
+            if let Err(e) = Journal::pop(&self.home) {
+                batch.errors.push(e.to_string());
+            }
+        }
+        if let Some(view) = self.trash_view.as_mut() {
+            view.items = trash::list();
+            view.selected = view.selected.min(view.items.len().saturating_sub(1));
+        }
+        
+        match batch.errors.first() {
+            None => {
+                if batch.clears_yank {
+                    self.yanked.clear();
+                }
+                self.set_message(format!("{} done", batch.label), MessageColor::Success);
+            }
+            Some(first) if batch.total == 1 => {
+                self.set_message(first.clone(), MessageColor::Error);
+            }
+            Some(first) => {
+                self.set_message(
+                    format!("{}: {} of {} failed ({})", batch.label, batch.errors.len(), batch.total, first),
+                    MessageColor::Error,
+                );
+            }
+        }
+        
+        self.refresh_all()
+    }
+    
+    /// Open the `:` line
+    pub fn start_command(&mut self) {
+        self.mode = Mode::Command;
+        self.command_line = CommandLine { prompt: ":".to_string(), ..CommandLine::default() };
+    }
+    
+    pub fn cancel_command(&mut self) {
+        self.mode = Mode::Normal;
+        self.command_line = CommandLine::default();
+    }
+    
+    pub fn command_add_char(&mut self, c: char) {
+        let line = &mut self.command_line;
+        line.input.push(c);
+        line.completions.clear();
+        line.completion = None;
+    }
+    
+    /// Delete a character, or close the line when it's empty
+    pub fn command_backspace(&mut self) {
+        if self.command_line.input.pop().is_none() {
+            self.cancel_command();
+            return;
+        }
+        self.command_line.completions.clear();
+        self.command_line.completion = None;
+    }
+    
+    /// Step through earlier commands, back to what was typed
+    pub fn command_history(&mut self, older: bool) {
+        let line = &mut self.command_line;
+        let lines = &self.history.lines;
+        if line.confirm.is_some() || lines.is_empty() {
+            return;
+        }
+        
+        let pos = match (line.history_pos, older) {
+            (None, true) => {
+                line.draft = line.input.clone();
+                Some(lines.len() - 1)
+            }
+            (None, false) => None,
+            (Some(pos), true) => Some(pos.saturating_sub(1)),
+            (Some(pos), false) => (pos + 1 < lines.len()).then_some(pos + 1),
+        };
+        
+        line.input = match pos {
+            Some(pos) => lines[pos].clone(),
+            None => line.draft.clone(),
+        };
+        line.history_pos = pos;
+    }
+    
+    /// Complete the word being typed: as far as every candidate agrees,
+    /// then cycle through them on further presses
+    pub fn command_complete(&mut self) {
+        let cwd = self.tab().cwd.clone();
+        let line = &mut self.command_line;
+        if line.confirm.is_some() {
+            return;
+        }
+        
+        if !line.completions.is_empty() {
+            let next = line.completion.map(|i| (i + 1) % line.completions.len()).unwrap_or(0);
+            line.input = line.completions[next].clone();
+            line.completion = Some(next);
+            return;
+        }
+        
+        let candidates = command::complete::complete(&line.input, &cwd, &self.home);
+        match candidates.as_slice() {
+            [] => {}
+            [only] => line.input = only.clone(),
+            _ => {
+                let prefix = command::complete::common_prefix(&candidates);
+                if prefix.len() > line.input.len() {
+                    line.input = prefix;
+                }
+                line.completions = candidates;
+            }
+        }
+    }
+    
+    /// Run what was typed on the `:` line
+    pub fn run_command_line<B: ratatui::backend::Backend>(
+        &mut self,
+        terminal: &mut ratatui::Terminal<B>,
+    ) -> Result<()> {
+        let line = std::mem::take(&mut self.command_line);
+        self.mode = Mode::Normal;
+        
+        if let Some(cmd) = line.confirm {
+            if line.input.trim() == "DELETE" {
+                return self.run_shell(terminal, &cmd);
+            }
+            self.set_message("Cancelled".to_string(), MessageColor::Warning);
+            return Ok(());
+        }
+        
+        let input = line.input.trim();
+        if input.is_empty() {
+            return Ok(());
+        }
+        // History is a convenience, a failed save isn't worth reporting
+        let _ = self.history.push(&self.home, input);
+        
+        match command::parse(input) {
+            Ok(cmd) => self.execute(cmd, terminal),
+            Err(e) => {
+                self.set_message(e, MessageColor::Error);
+                Ok(())
+            }
+        }
+    }
+    
+    fn execute<B: ratatui::backend::Backend>(
+        &mut self,
+        cmd: Command,
+        terminal: &mut ratatui::Terminal<B>,
+    ) -> Result<()> {
+        let cwd = self.tab().cwd.clone();
+        match cmd {
+            Command::Cd(dir) => {
+                let path = match dir {
+                    Some(dir) => command::resolve(&dir, &cwd, &self.home),
+                    None => self.home.clone(),
+                };
+                match std::fs::canonicalize(&path) {
+                    Ok(path) if path.is_dir() => {
+                        self.tab_mut().set_cwd(path);
+                        self.reload()?;
+                    }
+                    _ => self.set_message(format!("Not a directory: {}", path.display()), MessageColor::Error),
+                }
+            }
+            Command::Mkdir(names) => {
+                let paths: Vec<PathBuf> = names.iter().map(|n| command::resolve(n, &cwd, &self.home)).collect();
+                let label = format!("Creating {}", describe(&paths));
+                let ops = paths.into_iter().map(|path| FileOp::Mkdir { path }).collect();
+                self.start_ops(Batch::new(label, ops), None)?;
+            }
+            Command::Touch(names) => {
+                let paths: Vec<PathBuf> = names.iter().map(|n| command::resolve(n, &cwd, &self.home)).collect();
+                let label = format!("Touching {}", describe(&paths));
+                let ops = paths.into_iter().map(|path| FileOp::Touch { path }).collect();
+                self.start_ops(Batch::new(label, ops), None)?;
+            }
+            Command::Rename(name) => {
+                let targets = self.targets();
+                if targets.len() != 1 {
+                    let keys = self.config.keymap.keys_for(&faelight_fm::config::Action::Rename);
+                    self.set_message(format!(":rename takes one entry, {} renames several", keys), MessageColor::Warning);
+                    return Ok(());
+                }
+                match actions::plan_renames(&targets, &name) {
+                    Ok(ops) if ops.is_empty() => self.set_message("Nothing renamed".to_string(), MessageColor::Success),
+                    Ok(ops) => {
+                        let label = format!("Renaming {}", describe(&targets));
+                        self.start_ops(Batch::new(label, ops), None)?;
+                    }
+                    Err(e) => self.set_message(format!("Rename: {}", e), MessageColor::Error),
+                }
+            }
+            Command::Chmod(spec) => {
+                use std::os::unix::fs::PermissionsExt;
+                let targets = self.targets();
+                let mut ops = Vec::new();
+                for path in &targets {
+                    let old = match std::fs::metadata(path) {
+                        Ok(meta) => meta.permissions().mode(),
+                        Err(e) => {
+                            self.set_message(format!("{}: {}", actions::display_name(path), e), MessageColor::Error);
+                            return Ok(());
+                        }
+                    };
+                    match command::apply_mode(&spec, old) {
+                        Ok(mode) => ops.push(FileOp::Chmod { path: path.clone(), mode }),
+                        Err(e) => {
+                            self.set_message(e, MessageColor::Error);
+                            return Ok(());
+                        }
+                    }
+                }
+                if !ops.is_empty() {
+                    let label = format!("Setting {} on {}", spec, describe(&targets));
+                    self.start_ops(Batch::new(label, ops), None)?;
+                }
+            }
+            Command::Find(text) => self.find(&text),
+            Command::Sort { by, reverse } => {
+                self.config.sort.by = by;
+                self.config.sort.reverse = reverse;
+                self.refresh_all()?;
+                let order = if reverse { ", reversed" } else { "" };
+                self.set_message(format!("Sorted by {}{}", sort_name(by), order), MessageColor::Success);
+            }
+            Command::Shell(cmd) => self.shell(terminal, &cmd)?,
+        }
+        Ok(())
+    }
+    
+    /// Look for names containing `text` below cwd
+    fn find(&mut self, text: &str) {
+        let root = self.tab().cwd.clone();
+        let query = text.to_lowercase();
+        let show_hidden = self.show_hidden;
+        
+        let results: Vec<PathBuf> = walkdir::WalkDir::new(&root)
+            .min_depth(1)
+            .into_iter()
+            .filter_entry(|e| show_hidden || !e.file_name().to_string_lossy().starts_with('.'))
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_name().to_string_lossy().to_lowercase().contains(&query))
+            .filter_map(|e| e.path().strip_prefix(&root).ok().map(Path::to_path_buf))
+            .take(FIND_LIMIT)
+            .collect();
+        
+        if results.is_empty() {
+            self.set_message(format!("Nothing named like '{}' here", text), MessageColor::Warning);
+            return;
+        }
+        self.find_view = Some(FindView { query: text.to_string(), results, root, selected: 0 });
+    }
+    
+    pub fn close_find(&mut self) {
+        self.find_view = None;
+    }
+    
+    pub fn find_select_next(&mut self) {
+        if let Some(view) = self.find_view.as_mut() {
+            if view.selected + 1 < view.results.len() {
+                view.selected += 1;
+            }
+        }
+    }
+    
+    pub fn find_select_prev(&mut self) {
+        if let Some(view) = self.find_view.as_mut() {
+            view.selected = view.selected.saturating_sub(1);
+        }
+    }
+    
+    /// Go to the directory holding the chosen match and select it
+    pub fn find_go(&mut self) -> Result<()> {
+        let Some(view) = self.find_view.take() else {
+            return Ok(());
+        };
+        let Some(path) = view.results.get(view.selected).map(|p| view.root.join(p)) else {
+            return Ok(());
+        };
+        
+        if let Some(dir) = path.parent() {
+            self.tab_mut().set_cwd(dir.to_path_buf());
+            self.reload()?;
+            let tab = self.tab_mut();
+            if let Some(idx) = tab.filtered_entries.iter().position(|e| e.path == path) {
+                tab.selected = idx;
+            }
+        }
+        Ok(())
+    }
+    
+    /// Fill in the selection and check the command with intent-guard.
+    /// Medium and high risks ask (y/n), critical ones want DELETE typed.
+    fn shell<B: ratatui::backend::Backend>(
+        &mut self,
+        terminal: &mut ratatui::Terminal<B>,
+        cmd: &str,
+    ) -> Result<()> {
+        let targets = self.targets();
+        if cmd.contains("%s") && targets.is_empty() {
+            self.set_message("Nothing selected for %s".to_string(), MessageColor::Warning);
+            return Ok(());
+        }
+        let cmd = command::expand_selection(cmd, &targets);
+        
+        let Some(pattern) = command::guard(&cmd) else {
+            return self.run_shell(terminal, &cmd);
+        };
+        let warning = format!("{} risk: {}.", pattern.risk.label(), pattern.description);
+        match pattern.risk {
+            intent_guard::RiskLevel::Low => self.run_shell(terminal, &cmd),
+            intent_guard::RiskLevel::Medium | intent_guard::RiskLevel::High => {
+                self.set_message(format!("{} Run `{}`? (y/n)", warning, cmd), MessageColor::Warning);
+                self.shell_confirm = Some(cmd);
+                Ok(())
+            }
+            intent_guard::RiskLevel::Critical => {
+                self.set_message(format!("{} `{}`", warning, cmd), MessageColor::Error);
+                self.mode = Mode::Command;
+                self.command_line = CommandLine {
+                    prompt: "Type DELETE to run it: ".to_string(),
+                    confirm: Some(cmd),
+                    ..CommandLine::default()
+                };
+                Ok(())
+            }
+        }
+    }
+    
+    /// Run a shell command in cwd with the TUI suspended, waiting for
+    /// Enter afterwards so its output can be read
+    fn run_shell<B: ratatui::backend::Backend>(
+        &mut self,
+        terminal: &mut ratatui::Terminal<B>,
+        cmd: &str,
+    ) -> Result<()> {
+        suspend_tui()?;
+        println!("$ {}", cmd);
+        let status = std::process::Command::new("sh")
+            .arg("-c")
+            .arg(cmd)
+            .current_dir(&self.tab().cwd)
+            .status();
+        print!("\nPress Enter to return to faelight-fm ");
+        std::io::Write::flush(&mut std::io::stdout())?;
+        let _ = std::io::stdin().read_line(&mut String::new());
+        resume_tui(terminal)?;
+        
+        match status {
+            Ok(status) if status.success() => self.set_message(format!("Ran `{}`", cmd), MessageColor::Success),
+            Ok(status) => {
+                let code = status.code().map(|c| c.to_string()).unwrap_or_else(|| "a signal".to_string());
+                self.set_message(format!("`{}` exited with {}", cmd, code), MessageColor::Error);
+            }
+            Err(e) => self.set_message(format!("Cannot run sh: {}", e), MessageColor::Error),
+        }
+        
+        self.refresh_all()
+    }
+    
+    /// Set status message
+    pub fn set_message(&mut self, msg: String, color: MessageColor) {
+        self.status_message = Some(msg);
+        self.message_color = color;
+    }
+    
+    /// Clear status message  
+    pub fn clear_message(&mut self) {
+        self.status_message = None;
+    }
+}
+
+/// Fully clean up the terminal before handing it to another program
+fn suspend_tui() -> Result<()> {
+    crossterm::terminal::disable_raw_mode()?;
+    crossterm::execute!(
+        std::io::stdout(),
+        crossterm::terminal::LeaveAlternateScreen,
+        crossterm::cursor::Show
+    )?;
+    Ok(())
+}
+
+/// Fully restore terminal state and force a redraw
+fn resume_tui<B: ratatui::backend::Backend>(terminal: &mut ratatui::Terminal<B>) -> Result<()> {
+    crossterm::execute!(
+        std::io::stdout(),
+        crossterm::terminal::EnterAlternateScreen,
+        crossterm::terminal::Clear(crossterm::terminal::ClearType::All),
+        crossterm::cursor::Hide
+    )?;
+    crossterm::terminal::enable_raw_mode()?;
+    terminal.clear()?;
+    Ok(())
+}
+
+fn sort_name(by: SortBy) -> &'static str {
+    match by {
+        SortBy::Name => "name",
+        SortBy::Size => "size",
+        SortBy::Modified => "modified",
+        SortBy::Extension => "extension",
+    }
+}
+
+/// Copy or move `srcs` into `dir`
+fn transfer(srcs: &[PathBuf], dir: &Path, mode: YankMode) -> Batch {
+    let ops = srcs
+        .iter()
+        .map(|src| {
+            let dst = dir.join(src.file_name().unwrap_or_default());
+            match mode {
+                YankMode::Copy => FileOp::Copy { src: src.clone(), dst },
+                YankMode::Cut => FileOp::Move { src: src.clone(), dst },
+            }
+        })
+        .collect();
+    
+    let verb = match mode {
+        YankMode::Copy => "Copying",
+        YankMode::Cut => "Moving",
+    };
+    Batch::new(format!("{} {}", verb, describe(srcs)), ops)
+}
+
+/// "notes.md" for one path, "3 items" for more
+fn describe(paths: &[PathBuf]) -> String {
+    match paths {
+        [path] => actions::display_name(path),
+        _ => describe_count(paths.len()),
+    }
+}
+
+fn describe_count(n: usize) -> String {
+    if n == 1 { "1 item".to_string() } else { format!("{} items", n) }
+}
+
+/// One-line description of a daemon preview, e.g. "rust · 4.2 KB"
+fn preview_summary(preview: &FilePreview) -> String {
+    let mut parts = Vec::new();
+    
+    match preview.kind {
+        PreviewKind::Text => parts.push(preview.language.clone().unwrap_or_else(|| "text".to_string())),
+        PreviewKind::Binary => parts.push("binary".to_string()),
+        PreviewKind::Image | PreviewKind::Archive => {
+            parts.push(preview.format.clone().unwrap_or_default());
+        }
+        PreviewKind::Directory => parts.push(format!("{} entries", preview.lines.len())),
+        PreviewKind::Special => parts.push("special file".to_string()),
+    }
+    
+    if !matches!(preview.kind, PreviewKind::Directory | PreviewKind::Special) {
+        parts.push(crate::ui::info::format_size(preview.size));
+    }
+    if preview.truncated {
+        parts.push("truncated".to_string());
+    }
+    
+    parts.join(" · ")
 }
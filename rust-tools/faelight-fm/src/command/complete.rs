@@ -0,0 +1,87 @@
+//! Tab completion of command names and paths
+use std::fs;
+use std::path::Path;
+use super::{quote, BUILTINS};
+
+/// Ways to finish `input`, each a whole new command line. The first
+/// word completes to a built-in, later ones to paths under `cwd`.
+pub fn complete(input: &str, cwd: &Path, home: &Path) -> Vec<String> {
+    let start = input.rfind(char::is_whitespace).map(|i| i + 1).unwrap_or(0);
+    let (head, word) = input.split_at(start);
+    
+    if head.trim().is_empty() && !word.starts_with('!') {
+        return BUILTINS
+            .iter()
+            .filter(|b| b.starts_with(word))
+            .map(|b| format!("{}{} ", head, b))
+            .collect();
+    }
+    
+    // The directory typed so far, and the start of a name in it
+    let (dir_part, prefix) = match word.rfind('/') {
+        Some(i) => word.split_at(i + 1),
+        None => ("", word),
+    };
+    let dir = if dir_part.is_empty() { cwd.to_path_buf() } else { super::resolve(dir_part, cwd, home) };
+    let Ok(read) = fs::read_dir(&dir) else {
+        return Vec::new();
+    };
+    
+    let mut names: Vec<(String, bool)> = read
+        .filter_map(|e| e.ok())
+        .map(|e| (e.file_name().to_string_lossy().to_string(), e.path().is_dir()))
+        .filter(|(name, _)| name.starts_with(prefix) && (prefix.starts_with('.') || !name.starts_with('.')))
+        .collect();
+    names.sort();
+    
+    names
+        .into_iter()
+        .map(|(name, is_dir)| {
+            let slash = if is_dir { "/" } else { "" };
+            format!("{}{}{}", head, quote(&format!("{}{}", dir_part, name)), slash)
+        })
+        .collect()
+}
+
+/// Longest start that all candidates share
+pub fn common_prefix(candidates: &[String]) -> String {
+    let Some(first) = candidates.first() else {
+        return String::new();
+    };
+    let mut len = first.len();
+    for other in &candidates[1..] {
+        len = first
+            .char_indices()
+            .zip(other.chars())
+            .take_while(|((_, a), b)| a == b)
+            .last()
+            .map(|((i, c), _)| i + c.len_utf8())
+            .unwrap_or(0)
+            .min(len);
+    }
+    first[..len].to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_complete() {
+        let cwd = std::env::temp_dir().join(format!("faelight-fm-complete-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&cwd);
+        fs::create_dir_all(cwd.join("notes/drafts")).unwrap();
+        fs::write(cwd.join("notes.md"), "").unwrap();
+        fs::write(cwd.join("new file"), "").unwrap();
+        fs::write(cwd.join(".hidden"), "").unwrap();
+        
+        assert_eq!(complete("mk", &cwd, &cwd), vec!["mkdir "]);
+        assert_eq!(complete("cd no", &cwd, &cwd), vec!["cd notes/", "cd notes.md"]);
+        assert_eq!(complete("cd notes/d", &cwd, &cwd), vec!["cd notes/drafts/"]);
+        assert_eq!(complete("!cat ne", &cwd, &cwd), vec!["!cat 'new file'"]);
+        assert_eq!(complete("cd .h", &cwd, &cwd), vec!["cd .hidden"]);
+        assert_eq!(common_prefix(&complete("cd no", &cwd, &cwd)), "cd notes");
+        
+        fs::remove_dir_all(&cwd).unwrap();
+    }
+}
@@ -0,0 +1,37 @@
+//! Command-line history, one line per command, newest last
+use std::fs;
+use std::path::Path;
+use crate::error::Result;
+
+/// Relative to $HOME
+pub const HISTORY_PATH: &str = ".local/state/faelight-fm/history";
+
+/// Oldest commands are dropped past this
+const MAX_LINES: usize = 500;
+
+#[derive(Debug, Default)]
+pub struct History {
+    pub lines: Vec<String>,
+}
+
+impl History {
+    pub fn load(home: &Path) -> History {
+        let content = fs::read_to_string(home.join(HISTORY_PATH)).unwrap_or_default();
+        History { lines: content.lines().filter(|l| !l.is_empty()).map(String::from).collect() }
+    }
+    
+    /// Add a command, moving it to the end if it was already there
+    pub fn push(&mut self, home: &Path, line: &str) -> Result<()> {
+        self.lines.retain(|l| l != line);
+        self.lines.push(line.to_string());
+        let excess = self.lines.len().saturating_sub(MAX_LINES);
+        self.lines.drain(..excess);
+        
+        let path = home.join(HISTORY_PATH);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&path, self.lines.join("\n") + "\n")?;
+        Ok(())
+    }
+}
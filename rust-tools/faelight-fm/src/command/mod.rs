@@ -0,0 +1,252 @@
+//! The `:` command line: built-ins and `:!` shell commands
+pub mod complete;
+pub mod history;
+
+use std::path::{Path, PathBuf};
+use intent_guard::Pattern;
+use crate::config::SortBy;
+
+pub use history::History;
+
+/// Built-in commands, for completion and errors
+pub const BUILTINS: [&str; 7] = ["cd", "chmod", "find", "mkdir", "rename", "sort", "touch"];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    /// Home without an argument
+    Cd(Option<String>),
+    Mkdir(Vec<String>),
+    Touch(Vec<String>),
+    /// New name for the selected entry
+    Rename(String),
+    /// Octal ("755") or symbolic ("u+x,go-w") mode
+    Chmod(String),
+    /// Names under cwd containing this
+    Find(String),
+    Sort { by: SortBy, reverse: bool },
+    /// `:!cmd`, with %s still in it
+    Shell(String),
+}
+
+/// Parse a command line, without the leading ':'
+pub fn parse(line: &str) -> Result<Command, String> {
+    let line = line.trim();
+    if let Some(shell) = line.strip_prefix('!') {
+        let shell = shell.trim();
+        if shell.is_empty() {
+            return Err("Usage: :!command (%s is the selection)".to_string());
+        }
+        return Ok(Command::Shell(shell.to_string()));
+    }
+    
+    let (name, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+    let rest = rest.trim();
+    let required = |usage: &str| {
+        if rest.is_empty() { Err(format!("Usage: :{} {}", name, usage)) } else { Ok(rest.to_string()) }
+    };
+    
+    match name {
+        "cd" => match split_args(rest)?.as_slice() {
+            [] => Ok(Command::Cd(None)),
+            [dir] => Ok(Command::Cd(Some(dir.clone()))),
+            _ => Err("Usage: :cd [DIR] (quote names with spaces)".to_string()),
+        },
+        "mkdir" | "touch" => {
+            let names = split_args(&required("NAME...")?)?;
+            Ok(if name == "mkdir" { Command::Mkdir(names) } else { Command::Touch(names) })
+        }
+        "rename" => Ok(Command::Rename(required("NEW_NAME")?)),
+        "chmod" => {
+            let mode = required("MODE")?;
+            apply_mode(&mode, 0o644)?;
+            Ok(Command::Chmod(mode))
+        }
+        "find" => Ok(Command::Find(required("TEXT")?)),
+        "sort" => {
+            let mut by = None;
+            let mut reverse = false;
+            for word in split_args(&required("name|size|modified|extension [reverse]")?)? {
+                match word.as_str() {
+                    "name" => by = Some(SortBy::Name),
+                    "size" => by = Some(SortBy::Size),
+                    "modified" | "time" => by = Some(SortBy::Modified),
+                    "extension" | "ext" => by = Some(SortBy::Extension),
+                    "reverse" | "rev" => reverse = true,
+                    other => return Err(format!("Cannot sort by '{}'", other)),
+                }
+            }
+            Ok(Command::Sort { by: by.unwrap_or_default(), reverse })
+        }
+        "" => Err("Empty command".to_string()),
+        other => Err(format!("Unknown command '{}' (try {})", other, BUILTINS.join(", "))),
+    }
+}
+
+/// Words, with '…' and "…" keeping spaces
+pub fn split_args(line: &str) -> Result<Vec<String>, String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut quote = None;
+    
+    for c in line.chars() {
+        match (quote, c) {
+            (None, '"' | '\'') => {
+                quote = Some(c);
+                in_word = true;
+            }
+            (Some(q), c) if c == q => quote = None,
+            (None, c) if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+            }
+            (_, c) => {
+                word.push(c);
+                in_word = true;
+            }
+        }
+    }
+    
+    if quote.is_some() {
+        return Err("Unclosed quote".to_string());
+    }
+    if in_word {
+        words.push(word);
+    }
+    Ok(words)
+}
+
+/// A path typed on the command line: "~" is home, relative to `cwd`
+pub fn resolve(arg: &str, cwd: &Path, home: &Path) -> PathBuf {
+    match arg.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with('/') => home.join(rest.trim_start_matches('/')),
+        _ => cwd.join(arg),
+    }
+}
+
+/// Replace %s with the selected paths, quoted for sh
+pub fn expand_selection(cmd: &str, paths: &[PathBuf]) -> String {
+    let selection: Vec<String> = paths.iter().map(|p| quote(&p.to_string_lossy())).collect();
+    cmd.replace("%s", &selection.join(" "))
+}
+
+/// Quote a word for sh unless it's plainly safe
+pub fn quote(word: &str) -> String {
+    let safe = !word.is_empty()
+        && word.chars().all(|c| c.is_ascii_alphanumeric() || "/._-+=:,@%".contains(c));
+    if safe {
+        word.to_string()
+    } else {
+        format!("'{}'", word.replace('\'', r"'\''"))
+    }
+}
+
+/// The riskiest intent-guard pattern that the command, or one of the
+/// commands chained, piped, backgrounded or substituted in it, matches
+pub fn guard(cmd: &str) -> Option<&'static Pattern> {
+    // `|` and `&` also split `||` and `&&`; `(` and `)` split `$(...)`
+    let parts = cmd.split([';', '\n', '|', '&', '`', '(', ')']);
+    
+    let mut riskiest: Option<&'static Pattern> = None;
+    for part in std::iter::once(cmd).chain(parts) {
+        if let Some(pattern) = intent_guard::check(part.trim()).first() {
+            if !riskiest.is_some_and(|r| r.risk >= pattern.risk) {
+                riskiest = Some(pattern);
+            }
+        }
+    }
+    riskiest
+}
+
+/// The permission bits `spec` gives a file that has `mode`
+pub fn apply_mode(spec: &str, mode: u32) -> Result<u32, String> {
+    let invalid = || format!("Invalid mode '{}'", spec);
+    
+    if spec.chars().all(|c| c.is_digit(8)) {
+        return match u32::from_str_radix(spec, 8) {
+            Ok(bits) if spec.len() <= 4 => Ok(bits),
+            _ => Err(invalid()),
+        };
+    }
+    
+    let mut mode = mode & 0o7777;
+    for clause in spec.split(',') {
+        let op_at = clause.find(['+', '-', '=']).ok_or_else(invalid)?;
+        let (who, rest) = clause.split_at(op_at);
+        let (op, perms) = rest.split_at(1);
+        
+        let mut mask = 0;
+        for c in who.chars() {
+            mask |= match c {
+                'u' => 0o700,
+                'g' => 0o070,
+                'o' => 0o007,
+                'a' => 0o777,
+                _ => return Err(invalid()),
+            };
+        }
+        if who.is_empty() {
+            mask = 0o777;
+        }
+        
+        let mut bits = 0;
+        for c in perms.chars() {
+            bits |= match c {
+                'r' => 0o444,
+                'w' => 0o222,
+                'x' => 0o111,
+                _ => return Err(invalid()),
+            };
+        }
+        
+        match op {
+            "+" => mode |= bits & mask,
+            "-" => mode &= !(bits & mask),
+            _ => mode = (mode & !mask) | (bits & mask),
+        }
+    }
+    Ok(mode)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_parse_commands() {
+        assert_eq!(parse("cd ~/notes").unwrap(), Command::Cd(Some("~/notes".to_string())));
+        assert_eq!(parse("cd").unwrap(), Command::Cd(None));
+        assert_eq!(parse("cd 'new file'").unwrap(), Command::Cd(Some("new file".to_string())));
+        assert_eq!(
+            parse("mkdir a 'b c'").unwrap(),
+            Command::Mkdir(vec!["a".to_string(), "b c".to_string()])
+        );
+        assert_eq!(parse("rename my notes.md").unwrap(), Command::Rename("my notes.md".to_string()));
+        assert_eq!(parse("sort size rev").unwrap(), Command::Sort { by: SortBy::Size, reverse: true });
+        assert_eq!(parse("!wc -l %s").unwrap(), Command::Shell("wc -l %s".to_string()));
+        assert!(parse("touch").is_err());
+        assert!(parse("chmod 9").is_err());
+        assert!(parse("fly").is_err());
+        
+        let paths = [PathBuf::from("/tmp/a.txt"), PathBuf::from("/tmp/it's here")];
+        assert_eq!(expand_selection("wc -l %s", &paths), r"wc -l /tmp/a.txt '/tmp/it'\''s here'");
+        
+        assert_eq!(apply_mode("755", 0o644), Ok(0o755));
+        assert_eq!(apply_mode("+x", 0o644), Ok(0o755));
+        assert_eq!(apply_mode("go-r,u+x", 0o644), Ok(0o700));
+        assert_eq!(apply_mode("o=r", 0o640), Ok(0o644));
+        assert!(apply_mode("u+z", 0o644).is_err());
+        
+        assert!(guard("ls -la %s").is_none());
+        assert_eq!(guard("rm -rf /tmp/old").unwrap().risk, intent_guard::RiskLevel::Critical);
+        assert_eq!(guard("cd /tmp && chmod 777 a; ls").unwrap().risk, intent_guard::RiskLevel::High);
+        let critical = |cmd: &str| guard(cmd).map(|p| p.risk) == Some(intent_guard::RiskLevel::Critical);
+        assert!(critical("ls | rm -rf /tmp/old"));
+        assert!(critical("sleep 1 & rm -rf /tmp/old"));
+        assert!(critical("echo $(rm -rf /tmp/old)"));
+        assert!(critical("echo `rm -rf /tmp/old`"));
+        assert!(critical("false || rm -rf /tmp/old"));
+    }
+}
@@ -0,0 +1,394 @@
+use std::collections::{BTreeMap, HashMap};
+use crossterm::event::KeyCode;
+use faelight_zone::Zones;
+
+/// Everything a key can be bound to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Action {
+    Down,
+    Up,
+    Enter,
+    Parent,
+    Search,
+    ToggleHidden,
+    /// List only entries with a health warning or error
+    Unhealthy,
+    /// Mark or unmark the selected entry
+    Mark,
+    /// Start or finish marking a range
+    Visual,
+    ClearMarks,
+    Yank,
+    Cut,
+    Paste,
+    Delete,
+    /// Rename the marked entries in $EDITOR
+    Rename,
+    /// Reverse the last file operation
+    Undo,
+    /// Open the trash browser
+    Trash,
+    /// Copy marked or selected into the other pane's directory
+    CopyToPane,
+    MoveToPane,
+    Edit,
+    /// Choose what opens the selected file
+    OpenWith,
+    /// `git add` the marked or selected entries
+    Stage,
+    Unstage,
+    /// Staged and unstaged changes of the selected entry
+    Diff,
+    /// Open the `:` command line
+    Command,
+    Preview,
+    Info,
+    Help,
+    /// Parent, current and preview columns
+    Miller,
+    DualPane,
+    /// Focus the other pane
+    SwitchPane,
+    NewTab,
+    CloseTab,
+    NextTab,
+    PrevTab,
+    Quit,
+    /// Jump to the root of the zone with this id
+    JumpZone(String),
+    /// Go to tab N (1-based), on digits no zone jump uses
+    GoTab(usize),
+}
+
+impl Action {
+    /// Actions that can be bound in `[keys]`, in help order
+    pub const NAMED: [Action; 36] = [
+        Action::Down,
+        Action::Up,
+        Action::Enter,
+        Action::Parent,
+        Action::Search,
+        Action::ToggleHidden,
+        Action::Unhealthy,
+        Action::Mark,
+        Action::Visual,
+        Action::ClearMarks,
+        Action::Yank,
+        Action::Cut,
+        Action::Paste,
+        Action::Delete,
+        Action::Rename,
+        Action::Undo,
+        Action::Trash,
+        Action::CopyToPane,
+        Action::MoveToPane,
+        Action::Edit,
+        Action::OpenWith,
+        Action::Stage,
+        Action::Unstage,
+        Action::Diff,
+        Action::Command,
+        Action::Preview,
+        Action::Info,
+        Action::Help,
+        Action::Miller,
+        Action::DualPane,
+        Action::SwitchPane,
+        Action::NewTab,
+        Action::CloseTab,
+        Action::NextTab,
+        Action::PrevTab,
+        Action::Quit,
+    ];
+    
+    pub fn name(&self) -> &str {
+        match self {
+            Action::Down => "down",
+            Action::Up => "up",
+            Action::Enter => "enter",
+            Action::Parent => "parent",
+            Action::Search => "search",
+            Action::ToggleHidden => "toggle_hidden",
+            Action::Unhealthy => "unhealthy",
+            Action::Mark => "mark",
+            Action::Visual => "visual",
+            Action::ClearMarks => "clear_marks",
+            Action::Yank => "yank",
+            Action::Cut => "cut",
+            Action::Paste => "paste",
+            Action::Delete => "delete",
+            Action::Rename => "rename",
+            Action::Undo => "undo",
+            Action::Trash => "trash",
+            Action::CopyToPane => "copy_to_pane",
+            Action::MoveToPane => "move_to_pane",
+            Action::Edit => "edit",
+            Action::OpenWith => "open_with",
+            Action::Stage => "stage",
+            Action::Unstage => "unstage",
+            Action::Diff => "diff",
+            Action::Command => "command",
+            Action::Preview => "preview",
+            Action::Info => "info",
+            Action::Help => "help",
+            Action::Miller => "miller",
+            Action::DualPane => "dual_pane",
+            Action::SwitchPane => "switch_pane",
+            Action::NewTab => "new_tab",
+            Action::CloseTab => "close_tab",
+            Action::NextTab => "next_tab",
+            Action::PrevTab => "prev_tab",
+            Action::Quit => "quit",
+            Action::JumpZone(id) => id,
+            Action::GoTab(_) => "tab",
+        }
+    }
+    
+    pub fn description(&self) -> &str {
+        match self {
+            Action::Down => "Move down",
+            Action::Up => "Move up",
+            Action::Enter => "Enter directory / open file",
+            Action::Parent => "Go to parent",
+            Action::Search => "Search/filter files",
+            Action::ToggleHidden => "Show/hide hidden files",
+            Action::Unhealthy => "Only unhealthy on/off",
+            Action::Mark => "Mark/unmark file",
+            Action::Visual => "Mark a range",
+            Action::ClearMarks => "Clear marks",
+            Action::Yank => "Yank (copy) marked or selected",
+            Action::Cut => "Cut marked or selected",
+            Action::Paste => "Paste",
+            Action::Delete => "Trash marked or selected",
+            Action::Rename => "Rename marked in $EDITOR",
+            Action::Undo => "Undo last operation",
+            Action::Trash => "Browse the trash",
+            Action::CopyToPane => "Copy to the other pane",
+            Action::MoveToPane => "Move to the other pane",
+            Action::Edit => "Edit in $EDITOR",
+            Action::OpenWith => "Open with...",
+            Action::Stage => "Git stage marked or selected",
+            Action::Unstage => "Git unstage marked or selected",
+            Action::Diff => "Git diff of selected",
+            Action::Command => "Command line (:cd, :!cmd %s...)",
+            Action::Preview => "Preview file",
+            Action::Info => "File information",
+            Action::Help => "Toggle this help",
+            Action::Miller => "Miller columns on/off",
+            Action::DualPane => "Dual pane on/off",
+            Action::SwitchPane => "Switch pane",
+            Action::NewTab => "New tab here",
+            Action::CloseTab => "Close tab",
+            Action::NextTab => "Next tab",
+            Action::PrevTab => "Previous tab",
+            Action::Quit => "Quit",
+            Action::JumpZone(_) => "Jump to zone",
+            Action::GoTab(_) => "Go to tab",
+        }
+    }
+    
+    /// Heading the action is listed under in the help
+    pub fn group(&self) -> &'static str {
+        match self {
+            Action::Down | Action::Up | Action::Enter | Action::Parent
+            | Action::Search | Action::ToggleHidden | Action::Unhealthy => "Navigation:",
+            Action::JumpZone(_) => "Zone Jumping:",
+            Action::Miller | Action::DualPane | Action::SwitchPane | Action::NewTab
+            | Action::CloseTab | Action::NextTab | Action::PrevTab | Action::GoTab(_) => "Tabs & Panes:",
+            Action::Stage | Action::Unstage | Action::Diff => "Git:",
+            Action::Command | Action::Preview | Action::Info | Action::Help | Action::Quit => "Other:",
+            _ => "Files:",
+        }
+    }
+    
+    fn from_name(name: &str) -> Option<Action> {
+        Action::NAMED.iter().find(|a| a.name() == name).cloned()
+    }
+    
+    fn default_keys(&self) -> &'static [KeyCode] {
+        match self {
+            Action::Down => &[KeyCode::Char('j'), KeyCode::Down],
+            Action::Up => &[KeyCode::Char('k'), KeyCode::Up],
+            Action::Enter => &[KeyCode::Char('l'), KeyCode::Right, KeyCode::Enter],
+            Action::Parent => &[KeyCode::Char('h'), KeyCode::Left],
+            Action::Search => &[KeyCode::Char('/')],
+            Action::ToggleHidden => &[KeyCode::Char('.')],
+            Action::Unhealthy => &[KeyCode::Char('!')],
+            Action::Mark => &[KeyCode::Char(' ')],
+            Action::Visual => &[KeyCode::Char('V')],
+            Action::ClearMarks => &[KeyCode::Char('C')],
+            Action::Yank => &[KeyCode::Char('y')],
+            Action::Cut => &[KeyCode::Char('d')],
+            Action::Paste => &[KeyCode::Char('v')],
+            Action::Delete => &[KeyCode::Char('D')],
+            Action::Rename => &[KeyCode::Char('r')],
+            Action::Undo => &[KeyCode::Char('u')],
+            Action::Trash => &[KeyCode::Char('T')],
+            Action::CopyToPane => &[KeyCode::Char('c'), KeyCode::F(5)],
+            Action::MoveToPane => &[KeyCode::Char('m'), KeyCode::F(6)],
+            Action::Edit => &[KeyCode::Char('e')],
+            Action::OpenWith => &[KeyCode::Char('o')],
+            Action::Stage => &[KeyCode::Char('s')],
+            Action::Unstage => &[KeyCode::Char('S')],
+            Action::Diff => &[KeyCode::Char('g')],
+            Action::Command => &[KeyCode::Char(':')],
+            Action::Preview => &[KeyCode::Char('p')],
+            Action::Info => &[KeyCode::Char('i')],
+            Action::Help => &[KeyCode::Char('?')],
+            Action::Miller => &[KeyCode::Char('M')],
+            Action::DualPane => &[KeyCode::Char('|')],
+            Action::SwitchPane => &[KeyCode::Tab],
+            Action::NewTab => &[KeyCode::Char('t')],
+            Action::CloseTab => &[KeyCode::Char('x')],
+            Action::NextTab => &[KeyCode::Char(']')],
+            Action::PrevTab => &[KeyCode::Char('[')],
+            Action::Quit => &[KeyCode::Char('q'), KeyCode::Esc],
+            Action::JumpZone(_) | Action::GoTab(_) => &[],
+        }
+    }
+}
+
+/// Which action each key triggers
+#[derive(Debug, Clone)]
+pub struct Keymap {
+    bindings: HashMap<KeyCode, Action>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        let mut bindings = HashMap::new();
+        for action in Action::NAMED {
+            for key in action.default_keys() {
+                bindings.insert(*key, action.clone());
+            }
+        }
+        
+        // 0-9 jump to the zones in zones.toml order
+        for (i, zone) in Zones::global().all().iter().take(10).enumerate() {
+            let key = KeyCode::Char(char::from(b'0' + i as u8));
+            bindings.insert(key, Action::JumpZone(zone.id.clone()));
+        }
+        
+        let mut map = Self { bindings };
+        map.bind_tab_digits();
+        map
+    }
+}
+
+impl Keymap {
+    /// Defaults overridden by the `[keys]` (action -> keys) and
+    /// `[zones]` (zone id -> keys) tables of fm.toml. Binding an action
+    /// replaces its default keys, an empty list unbinds it.
+    pub fn build(
+        keys: &BTreeMap<String, Vec<String>>,
+        zones: &BTreeMap<String, Vec<String>>,
+        known_zones: &Zones,
+    ) -> Result<Keymap, String> {
+        let mut user: Vec<(KeyCode, Action)> = Vec::new();
+        let mut rebound: Vec<Action> = Vec::new();
+        
+        for (name, list) in keys {
+            let action = Action::from_name(name)
+                .ok_or_else(|| format!("unknown action '{}' in [keys]", name))?;
+            add_user(&mut user, &action, list)?;
+            rebound.push(action);
+        }
+        for (id, list) in zones {
+            if known_zones.get(id).is_none() {
+                return Err(format!("unknown zone '{}' in [zones]", id));
+            }
+            let action = Action::JumpZone(id.clone());
+            add_user(&mut user, &action, list)?;
+            rebound.push(action);
+        }
+        
+        let mut map = Keymap::default();
+        map.bindings.retain(|key, action| {
+            !rebound.contains(action) && !user.iter().any(|(k, _)| k == key)
+        });
+        map.bindings.extend(user);
+        map.bind_tab_digits();
+        Ok(map)
+    }
+    
+    /// Digits 1-9 that nothing else uses go to tabs 1-9
+    fn bind_tab_digits(&mut self) {
+        for n in 1..=9 {
+            let key = KeyCode::Char(char::from(b'0' + n as u8));
+            self.bindings.entry(key).or_insert(Action::GoTab(n));
+        }
+    }
+    
+    pub fn get(&self, key: KeyCode) -> Option<&Action> {
+        self.bindings.get(&key)
+    }
+    
+    /// Keys bound to an action, as shown in the help, e.g. "j / ↓"
+    pub fn keys_for(&self, action: &Action) -> String {
+        let mut keys: Vec<String> = self.bindings
+            .iter()
+            .filter(|(_, a)| *a == action)
+            .map(|(k, _)| key_label(*k))
+            .collect();
+        // Single characters first, so "j / ↓" rather than "↓ / j"
+        keys.sort_by_key(|k| (!k.is_ascii() || k.len() > 1, k.clone()));
+        keys.join(" / ")
+    }
+}
+
+fn add_user(user: &mut Vec<(KeyCode, Action)>, action: &Action, list: &[String]) -> Result<(), String> {
+    for name in list {
+        let key = parse_key(name)
+            .ok_or_else(|| format!("invalid key '{}' for {}", name, action.name()))?;
+        if let Some((_, other)) = user.iter().find(|(k, _)| *k == key) {
+            return Err(format!("'{}' is bound to both {} and {}", name, other.name(), action.name()));
+        }
+        user.push((key, action.clone()));
+    }
+    Ok(())
+}
+
+/// A single character, or a key name such as "Down", "Enter", "Space" or "F5"
+pub fn parse_key(name: &str) -> Option<KeyCode> {
+    let mut chars = name.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Some(KeyCode::Char(c));
+    }
+    
+    let key = match name.to_lowercase().as_str() {
+        "space" => KeyCode::Char(' '),
+        "up" => KeyCode::Up,
+        "down" => KeyCode::Down,
+        "left" => KeyCode::Left,
+        "right" => KeyCode::Right,
+        "enter" => KeyCode::Enter,
+        "esc" => KeyCode::Esc,
+        "tab" => KeyCode::Tab,
+        "backspace" => KeyCode::Backspace,
+        "delete" => KeyCode::Delete,
+        "insert" => KeyCode::Insert,
+        "home" => KeyCode::Home,
+        "end" => KeyCode::End,
+        "pageup" => KeyCode::PageUp,
+        "pagedown" => KeyCode::PageDown,
+        f => {
+            let n: u8 = f.strip_prefix('f')?.parse().ok()?;
+            return (1..=12).contains(&n).then_some(KeyCode::F(n));
+        }
+    };
+    Some(key)
+}
+
+pub fn key_label(key: KeyCode) -> String {
+    match key {
+        KeyCode::Char(' ') => "Space".to_string(),
+        KeyCode::Char(c) => c.to_string(),
+        KeyCode::Up => "↑".to_string(),
+        KeyCode::Down => "↓".to_string(),
+        KeyCode::Left => "←".to_string(),
+        KeyCode::Right => "→".to_string(),
+        KeyCode::Enter => "⏎".to_string(),
+        KeyCode::F(n) => format!("F{}", n),
+        other => format!("{:?}", other),
+    }
+}
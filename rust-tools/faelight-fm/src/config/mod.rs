@@ -0,0 +1,275 @@
+//! User settings from ~/.config/faelight/fm.toml
+pub mod keymap;
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use serde::Deserialize;
+use faelight_zone::Zones;
+use crate::error::{FmError, Result};
+use crate::open::OpenRule;
+
+pub use keymap::{Action, Keymap};
+
+pub const CONFIG_PATH: &str = ".config/faelight/fm.toml";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortBy {
+    #[default]
+    Name,
+    Size,
+    Modified,
+    Extension,
+}
+
+/// How the file list is laid out
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum View {
+    #[default]
+    List,
+    /// Parent, current and preview columns
+    Miller,
+    /// Two panes side by side
+    Dual,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SortConfig {
+    pub by: SortBy,
+    pub dirs_first: bool,
+    pub reverse: bool,
+}
+
+impl Default for SortConfig {
+    fn default() -> Self {
+        Self { by: SortBy::Name, dirs_first: true, reverse: false }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PreviewConfig {
+    /// Lines shown in the preview overlay
+    pub lines: usize,
+    /// Color the preview with the daemon's syntax highlighting
+    pub highlight: bool,
+}
+
+impl Default for PreviewConfig {
+    fn default() -> Self {
+        Self { lines: 20, highlight: true }
+    }
+}
+
+/// A key or a list of keys
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum Keys {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl Keys {
+    fn into_vec(self) -> Vec<String> {
+        match self {
+            Keys::One(key) => vec![key],
+            Keys::Many(keys) => keys,
+        }
+    }
+}
+
+/// fm.toml as written
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct ConfigFile {
+    editor: Option<String>,
+    show_hidden: Option<bool>,
+    view: View,
+    sort: SortConfig,
+    preview: PreviewConfig,
+    keys: BTreeMap<String, Keys>,
+    zones: BTreeMap<String, Keys>,
+    open: Vec<OpenRule>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// Editor command, e.g. "nvim" or "code --wait"
+    pub editor: Option<String>,
+    pub show_hidden: bool,
+    /// Layout at startup
+    pub view: View,
+    pub sort: SortConfig,
+    pub preview: PreviewConfig,
+    pub keymap: Keymap,
+    /// What opens files, tried before the XDG defaults
+    pub open: Vec<OpenRule>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            editor: None,
+            show_hidden: true,
+            view: View::List,
+            sort: SortConfig::default(),
+            preview: PreviewConfig::default(),
+            keymap: Keymap::default(),
+            open: Vec::new(),
+        }
+    }
+}
+
+impl Config {
+    /// Load fm.toml from `home`. A missing file gives the defaults.
+    pub fn load(home: &Path) -> Result<Config> {
+        let path = home.join(CONFIG_PATH);
+        if !path.exists() {
+            return Ok(Config::default());
+        }
+        
+        let content = std::fs::read_to_string(&path)
+            .map_err(|e| FmError::Config(format!("cannot read fm.toml: {}", e)))?;
+        Self::parse(&content)
+    }
+    
+    pub fn parse(content: &str) -> Result<Config> {
+        Self::parse_with_zones(content, Zones::global())
+    }
+    
+    /// Parse against `zones` rather than the user's zones.toml
+    pub fn parse_with_zones(content: &str, zones_config: &Zones) -> Result<Config> {
+        let file: ConfigFile = toml::from_str(content).map_err(|e| {
+            // TOML errors span several lines, the status bar has one
+            let line = e.span().map(|s| content[..s.start].matches('\n').count() + 1);
+            match line {
+                Some(line) => FmError::Config(format!("fm.toml line {}: {}", line, e.message())),
+                None => FmError::Config(format!("fm.toml: {}", e.message())),
+            }
+        })?;
+        
+        if file.preview.lines == 0 {
+            return Err(FmError::Config("fm.toml: preview.lines must be at least 1".to_string()));
+        }
+        if file.editor.as_deref().is_some_and(|e| e.trim().is_empty()) {
+            return Err(FmError::Config("fm.toml: editor is empty".to_string()));
+        }
+        for (i, rule) in file.open.iter().enumerate() {
+            if rule.mime.is_none() && rule.ext.is_empty() {
+                return Err(FmError::Config(format!("fm.toml: [[open]] rule {} needs mime or ext", i + 1)));
+            }
+            if rule.run.trim().is_empty() {
+                return Err(FmError::Config(format!("fm.toml: [[open]] rule {} has an empty run", i + 1)));
+            }
+        }
+        
+        let keys = file.keys.into_iter().map(|(k, v)| (k, v.into_vec())).collect();
+        let zones = file.zones.into_iter().map(|(k, v)| (k, v.into_vec())).collect();
+        let keymap = Keymap::build(&keys, &zones, zones_config)
+            .map_err(|e| FmError::Config(format!("fm.toml: {}", e)))?;
+        
+        Ok(Config {
+            editor: file.editor,
+            show_hidden: file.show_hidden.unwrap_or(true),
+            view: file.view,
+            sort: file.sort,
+            preview: file.preview,
+            keymap,
+            open: file.open,
+        })
+    }
+    
+    /// The editor to run: fm.toml, then $VISUAL, then $EDITOR, then nvim.
+    /// Returns the program and its arguments.
+    pub fn editor_command(&self) -> (String, Vec<String>) {
+        let command = self.editor.clone()
+            .or_else(|| std::env::var("VISUAL").ok())
+            .or_else(|| std::env::var("EDITOR").ok())
+            .filter(|e| !e.trim().is_empty())
+            .unwrap_or_else(|| "nvim".to_string());
+        
+        let mut parts = command.split_whitespace().map(String::from);
+        let program = parts.next().unwrap_or_default();
+        (program, parts.collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::event::KeyCode;
+    
+    /// Against the built-in zones, never the developer's zones.toml
+    fn parse(content: &str) -> Result<Config> {
+        Config::parse_with_zones(content, &Zones::default())
+    }
+    
+    #[test]
+    fn test_keymap_overrides() {
+        let config = parse(r#"
+            editor = "hx"
+            view = "miller"
+            
+            [sort]
+            by = "modified"
+            
+            [keys]
+            down = ["n", "Down"]
+            preview = "e"
+            quit = []
+            
+            [zones]
+            core = "c"
+            
+            [[open]]
+            mime = "image/*"
+            run = "imv"
+            
+            [[open]]
+            ext = ["md"]
+            run = "glow -p %f"
+            terminal = true
+        "#).unwrap();
+        
+        let keymap = &config.keymap;
+        assert_eq!(keymap.get(KeyCode::Char('n')), Some(&Action::Down));
+        assert_eq!(keymap.get(KeyCode::Char('j')), None);
+        // 'e' moved from edit to preview
+        assert_eq!(keymap.get(KeyCode::Char('e')), Some(&Action::Preview));
+        assert_eq!(keymap.get(KeyCode::Char('q')), None);
+        assert_eq!(keymap.get(KeyCode::Char('c')), Some(&Action::JumpZone("core".to_string())));
+        assert_eq!(keymap.get(KeyCode::Char('0')), None);
+        assert_eq!(keymap.get(KeyCode::Char('1')), Some(&Action::JumpZone("workspace".to_string())));
+        assert_eq!(config.sort.by, SortBy::Modified);
+        assert_eq!(config.view, View::Miller);
+        assert!(config.open[0].matches("image/png", Path::new("cat.png")));
+        assert!(!config.open[0].matches("text/plain", Path::new("notes.txt")));
+        assert!(config.open[1].matches("text/markdown", Path::new("README.MD")));
+        assert_eq!(config.editor_command().0, "hx");
+        
+        // A zone moved off its digit frees it for tabs
+        let config = parse("[zones]\nworkspace = \"w\"").unwrap();
+        assert_eq!(config.keymap.get(KeyCode::Char('1')), Some(&Action::GoTab(1)));
+        
+        assert!(parse("[keys]\nfly = \"f\"").is_err());
+        assert!(parse("[keys]\ndown = \"x\"\nup = \"x\"").is_err());
+        assert!(parse("[keys]\ndown = \"Hyper\"").is_err());
+        assert!(parse("[zones]\nnowhere = \"n\"").is_err());
+        assert!(parse("shw_hidden = false").is_err());
+        assert!(parse("[[open]]\nrun = \"imv\"").is_err());
+    }
+    
+    #[test]
+    fn test_error_line() {
+        let line = |content: &str| match parse(content) {
+            Err(FmError::Config(message)) => message,
+            other => panic!("expected a config error, got {:?}", other.map(|_| ())),
+        };
+        // The bad key starts a line, right after a newline
+        assert!(line("editor = \"hx\"\nshw_hidden = false\n").starts_with("fm.toml line 2:"));
+        assert!(line("shw_hidden = false").starts_with("fm.toml line 1:"));
+        assert!(line("\n\n[sort]\nby = 3\n").starts_with("fm.toml line 4:"));
+    }
+}
@@ -2,22 +2,76 @@
 use tokio::net::UnixStream;
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
 use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
+use std::collections::BTreeMap;
 use std::path::Path;
+use std::time::Duration;
+
+/// Protocol version this client speaks
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// How long to wait for a handshake before assuming a pre-handshake daemon
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Longest the UI waits for a preview (archive listings can be slow)
+const PREVIEW_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Features every daemon build supports, even without a handshake
+const BASE_CAPABILITIES: &[&str] = &["entries", "preview"];
 
 // Re-define protocol types (should share with daemon eventually)
 #[derive(Debug, Serialize, Deserialize)]
 pub enum Command {
+    Hello { version: u32, capabilities: Vec<String> },
     GetEntries { path: String },
-    Preview { path: String },
+    Search { query: String, limit: Option<usize> },
+    Preview { path: String, max_lines: Option<usize> },
+    GitStatus { path: String },
+    Watch { path: String },
+    Unwatch { path: String },
+    Copy { src: String, dst: String },
+    Move { src: String, dst: String },
+    Delete { path: String },
+    Rename { path: String, new_name: String },
+    Mkdir { path: String },
+    Stats,
     Ping,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub enum Response {
+    Hello { version: u32, capabilities: Vec<String> },
     Entries { entries: Vec<Entry> },
-    Preview { content: String },
+    SearchResults { results: Vec<SearchHit> },
+    Preview {
+        content: String,
+        #[serde(default)]
+        preview: Option<Box<FilePreview>>,
+    },
+    GitStatus { repo_root: String, files: BTreeMap<String, GitFileStatus> },
+    Stats { stats: DaemonStats },
     Pong,
-    Error { message: String },
+    Ok,
+    Error {
+        #[serde(default)]
+        code: ErrorCode,
+        message: String,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ErrorCode {
+    NotFound,
+    PermissionDenied,
+    ZoneLocked,
+    Unsupported,
+    AlreadyExists,
+    InvalidRequest,
+    #[default]
+    Internal,
+    // Codes added by newer daemons, so the message still gets through
+    #[serde(other)]
+    Unknown,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -28,6 +82,110 @@ pub struct Entry {
     pub size: u64,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchHit {
+    pub entry: Entry,
+    pub score: i64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GitState {
+    Unmodified,
+    Modified,
+    Added,
+    Deleted,
+    Renamed,
+    Copied,
+    TypeChanged,
+    Untracked,
+    Ignored,
+    Conflicted,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GitFileStatus {
+    pub index: GitState,
+    pub worktree: GitState,
+    pub orig_path: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EventKind {
+    Create,
+    Modify,
+    Delete,
+    Rename,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Event {
+    pub kind: EventKind,
+    pub path: String,
+    pub from: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Progress {
+    pub bytes_done: u64,
+    pub bytes_total: u64,
+    pub current: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PreviewKind {
+    Text,
+    Binary,
+    Image,
+    Archive,
+    Directory,
+    /// FIFO, socket or device, described rather than read
+    Special,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HighlightKind {
+    Keyword,
+    Type,
+    String,
+    Number,
+    Comment,
+    Heading,
+    Key,
+    Variable,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HighlightSpan {
+    pub line: usize,
+    pub start: usize,
+    pub end: usize,
+    pub kind: HighlightKind,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FilePreview {
+    pub kind: PreviewKind,
+    pub size: u64,
+    pub lines: Vec<String>,
+    pub truncated: bool,
+    pub format: Option<String>,
+    pub dimensions: Option<(u32, u32)>,
+    pub language: Option<String>,
+    #[serde(default)]
+    pub highlights: Vec<HighlightSpan>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DaemonStats {
+    pub uptime_secs: u64,
+    pub clients: usize,
+    pub requests: u64,
+    pub requests_by_command: BTreeMap<String, u64>,
+    pub index_entries: usize,
+    pub watched_dirs: usize,
+    pub socket_activated: bool,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Message {
     pub id: u64,
@@ -39,11 +197,21 @@ pub struct Message {
 pub enum MessagePayload {
     Command(Command),
     Response(Response),
+    Event(Event),
+    Progress(Progress),
 }
 
 pub struct DaemonClient {
     socket_path: String,
     next_id: u64,
+    protocol_version: Option<u32>,
+    capabilities: Vec<String>,
+}
+
+impl Default for DaemonClient {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl DaemonClient {
@@ -51,40 +219,269 @@ impl DaemonClient {
         Self {
             socket_path: "/tmp/faelight-daemon.sock".to_string(),
             next_id: 1,
+            protocol_version: None,
+            capabilities: BASE_CAPABILITIES.iter().map(|c| c.to_string()).collect(),
+        }
+    }
+    
+    /// Connect and negotiate features, or None if no daemon is running
+    pub fn connect() -> Option<Self> {
+        let mut client = Self::new();
+        if !client.is_available() {
+            return None;
+        }
+        
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .ok()?;
+        
+        let hello = Command::Hello {
+            version: PROTOCOL_VERSION,
+            capabilities: Vec::new(),
+        };
+        
+        // Older daemons ignore or reject Hello; keep the base feature set then
+        let reply = rt.block_on(async {
+            tokio::time::timeout(HANDSHAKE_TIMEOUT, client.send_command(hello)).await
+        });
+        
+        match reply {
+            Ok(Ok(Response::Hello { version, capabilities })) => {
+                client.protocol_version = Some(version);
+                client.capabilities.extend(capabilities);
+            }
+            Ok(Err(_)) => return None,
+            _ => {}
         }
+        
+        Some(client)
     }
     
-    /// Check if daemon is available
+    /// Protocol version reported by the daemon, None for pre-handshake builds
+    pub fn protocol_version(&self) -> Option<u32> {
+        self.protocol_version
+    }
+    
+    /// Whether the daemon advertised a feature
+    pub fn supports(&self, capability: &str) -> bool {
+        self.capabilities.iter().any(|c| c == capability)
+    }
+    
+    /// Check if daemon is available.
+    /// Connecting (rather than checking the socket file) skips stale sockets
+    /// and starts a socket-activated daemon on demand.
     pub fn is_available(&self) -> bool {
-        Path::new(&self.socket_path).exists()
+        std::os::unix::net::UnixStream::connect(&self.socket_path).is_ok()
+    }
+    
+    /// Typed preview of a file or directory, None if the daemon can't provide one
+    pub fn preview(&mut self, path: &Path, max_lines: usize) -> Option<FilePreview> {
+        if !self.supports("rich-preview") {
+            return None;
+        }
+        
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .ok()?;
+        
+        let cmd = Command::Preview {
+            path: path.to_string_lossy().to_string(),
+            max_lines: Some(max_lines),
+        };
+        
+        match rt.block_on(async {
+            tokio::time::timeout(PREVIEW_TIMEOUT, self.send_command(cmd)).await
+        }) {
+            Ok(Ok(Response::Preview { preview: Some(preview), .. })) => Some(*preview),
+            _ => None,
+        }
     }
     
     /// Send a command and get response
     pub async fn send_command(&mut self, cmd: Command) -> Result<Response, Box<dyn std::error::Error>> {
-        let stream = UnixStream::connect(&self.socket_path).await?;
-        let (reader, mut writer) = stream.into_split();
-        let mut reader = BufReader::new(reader);
-        
-        // Send command
-        let msg = Message {
-            id: self.next_id,
-            payload: MessagePayload::Command(cmd),
-        };
+        let id = self.next_id;
         self.next_id += 1;
+        request(&self.socket_path, id, cmd, |_| {}).await
+    }
+    
+    /// Run a command on a background thread without blocking the caller
+    pub fn spawn_command(&self, cmd: Command) -> PendingOperation {
+        let (tx, updates) = std::sync::mpsc::channel();
+        let socket_path = self.socket_path.clone();
         
-        let json = serde_json::to_string(&msg)?;
-        writer.write_all(json.as_bytes()).await?;
-        writer.write_all(b"\n").await?;
-        writer.flush().await?;
+        std::thread::spawn(move || {
+            let response = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .map_err(|e| e.to_string())
+                .and_then(|rt| {
+                    rt.block_on(request(&socket_path, 1, cmd, |p| {
+                        let _ = tx.send(OperationUpdate::Progress(p));
+                    }))
+                    .map_err(|e| e.to_string())
+                })
+                .unwrap_or_else(|message| Response::Error {
+                    code: ErrorCode::Internal,
+                    message,
+                });
+            let _ = tx.send(OperationUpdate::Done(response));
+        });
         
-        // Read response
-        let mut line = String::new();
-        reader.read_line(&mut line).await?;
-        let msg: Message = serde_json::from_str(&line)?;
+        PendingOperation { updates }
+    }
+}
+
+/// Update from an operation started with `spawn_command`
+#[derive(Debug)]
+pub enum OperationUpdate {
+    Progress(Progress),
+    Done(Response),
+}
+
+/// Handle to an operation running in the daemon
+pub struct PendingOperation {
+    updates: std::sync::mpsc::Receiver<OperationUpdate>,
+}
+
+impl PendingOperation {
+    /// Drain updates received so far
+    pub fn poll(&self) -> Vec<OperationUpdate> {
+        self.updates.try_iter().collect()
+    }
+}
+
+/// Send one request on a fresh connection and wait for its response
+async fn request(
+    socket_path: &str,
+    id: u64,
+    cmd: Command,
+    mut on_progress: impl FnMut(Progress),
+) -> Result<Response, Box<dyn std::error::Error>> {
+    let stream = UnixStream::connect(socket_path).await?;
+    let (reader, mut writer) = stream.into_split();
+    let mut reader = BufReader::new(reader);
+    
+    // Send command
+    let msg = Message {
+        id,
+        payload: MessagePayload::Command(cmd),
+    };
+    
+    let json = serde_json::to_string(&msg)?;
+    writer.write_all(json.as_bytes()).await?;
+    writer.write_all(b"\n").await?;
+    writer.flush().await?;
+    
+    // Read until the response matching our id arrives
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if reader.read_line(&mut line).await? == 0 {
+            return Err("Daemon closed the connection".into());
+        }
+        
+        let reply: Message = serde_json::from_str(&line)?;
+        if reply.id != msg.id {
+            continue;
+        }
+        
+        match reply.payload {
+            MessagePayload::Response(resp) => return Ok(resp),
+            MessagePayload::Progress(progress) => on_progress(progress),
+            _ => return Err("Expected response".into()),
+        }
+    }
+}
+
+impl DaemonClient {
+    /// Open a long-lived connection that receives pushed change events
+    pub fn subscribe(&self) -> Option<WatchSubscription> {
+        if !self.is_available() || !self.supports("watch") {
+            return None;
+        }
+        
+        let (commands, mut pending) = mpsc::unbounded_channel::<Command>();
+        let (events_tx, events) = std::sync::mpsc::channel::<Event>();
+        let socket_path = self.socket_path.clone();
+        
+        std::thread::spawn(move || {
+            let rt = match tokio::runtime::Builder::new_current_thread().enable_all().build() {
+                Ok(rt) => rt,
+                Err(_) => return,
+            };
+            
+            rt.block_on(async move {
+                let Ok(stream) = UnixStream::connect(&socket_path).await else {
+                    return;
+                };
+                let (reader, mut writer) = stream.into_split();
+                // next_line is cancel-safe, so a command sent mid-line
+                // doesn't lose the bytes read so far
+                let mut lines = BufReader::new(reader).lines();
+                let mut next_id = 1;
+                
+                loop {
+                    tokio::select! {
+                        cmd = pending.recv() => {
+                            // Subscription dropped
+                            let Some(cmd) = cmd else { break };
+                            
+                            let msg = Message { id: next_id, payload: MessagePayload::Command(cmd) };
+                            next_id += 1;
+                            let Ok(json) = serde_json::to_string(&msg) else { continue };
+                            if writer.write_all(format!("{}\n", json).as_bytes()).await.is_err() {
+                                break;
+                            }
+                        }
+                        read = lines.next_line() => {
+                            let Ok(Some(line)) = read else { break };
+                            if let Ok(Message { payload: MessagePayload::Event(event), .. }) =
+                                serde_json::from_str::<Message>(&line)
+                            {
+                                if events_tx.send(event).is_err() {
+                                    break;
+                                }
+                            }
+                        }
+                    }
+                }
+            });
+        });
+        
+        Some(WatchSubscription {
+            commands,
+            events,
+            watched: None,
+        })
+    }
+}
+
+/// Live directory subscription backed by a background connection
+pub struct WatchSubscription {
+    commands: mpsc::UnboundedSender<Command>,
+    events: std::sync::mpsc::Receiver<Event>,
+    watched: Option<String>,
+}
+
+impl WatchSubscription {
+    /// Follow `path`, dropping the previous directory
+    pub fn watch(&mut self, path: &Path) {
+        let path = path.to_string_lossy().to_string();
+        if self.watched.as_deref() == Some(path.as_str()) {
+            return;
+        }
         
-        match msg.payload {
-            MessagePayload::Response(resp) => Ok(resp),
-            _ => Err("Expected response".into()),
+        if let Some(old) = self.watched.take() {
+            let _ = self.commands.send(Command::Unwatch { path: old });
         }
+        let _ = self.commands.send(Command::Watch { path: path.clone() });
+        self.watched = Some(path);
+    }
+    
+    /// Drain pending events, returning true if anything changed
+    pub fn poll_changed(&self) -> bool {
+        self.events.try_iter().count() > 0
     }
 }
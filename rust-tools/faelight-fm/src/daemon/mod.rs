@@ -1,4 +1,4 @@
 //! Daemon client for faelight-fm
 pub mod client;
 
-pub use client::DaemonClient;
+pub use client::{DaemonClient, OperationUpdate, PendingOperation, WatchSubscription};
@@ -16,6 +16,15 @@ pub enum FmError {
     #[error("Zone violation: {0}")]
     #[allow(dead_code)]
     ZoneViolation(String),
+    
+    #[error("Config error: {0}")]
+    Config(String),
+    
+    #[error("Undo journal: {0}")]
+    Journal(String),
+    
+    #[error("Git: {0}")]
+    Git(String),
 }
 
 pub type Result<T> = std::result::Result<T, FmError>;
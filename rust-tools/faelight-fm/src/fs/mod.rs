@@ -1,5 +1,7 @@
 pub mod scan;
 pub mod ops;
+pub mod trash;
 
-pub use scan::read_dir;
-pub use ops::{copy_file, move_file, rename_file, delete_file, is_core_locked};
+pub use scan::{read_dir, sort_entries};
+pub use ops::{copy_tree, move_path, rename_file, make_dir, touch_file, set_mode};
+pub use faelight_zone::is_core_locked;
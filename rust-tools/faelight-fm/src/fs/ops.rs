@@ -14,19 +14,98 @@ pub fn parent(path: &Path) -> Option<&Path> {
 
 use crate::error::Result;
 use std::fs;
+use std::io;
+use walkdir::WalkDir;
 
-/// Copy a file to a new location
-pub fn copy_file(src: &std::path::Path, dst: &std::path::Path) -> Result<()> {
-    fs::copy(src, dst)?;
+/// Copy a file, link or whole directory to a new location. A failed copy
+/// leaves nothing behind.
+pub fn copy_tree(from: &Path, to: &Path) -> io::Result<()> {
+    ensure_not_nested(from, to)?;
+    let existed = to.exists() || to.is_symlink();
+    let copied = copy_entries(from, to);
+    if copied.is_err() && !existed && (to.exists() || to.is_symlink()) {
+        let _ = if to.is_dir() && !to.is_symlink() { fs::remove_dir_all(to) } else { fs::remove_file(to) };
+    }
+    copied
+}
+
+fn copy_entries(from: &Path, to: &Path) -> io::Result<()> {
+    for entry in WalkDir::new(from) {
+        let entry = entry.map_err(io::Error::other)?;
+        let relative = entry.path().strip_prefix(from).map_err(io::Error::other)?;
+        // `from` itself lands on `to`, not `to/`
+        let target = if relative.as_os_str().is_empty() { to.to_path_buf() } else { to.join(relative) };
+        let file_type = entry.file_type();
+        
+        if entry.path_is_symlink() {
+            std::os::unix::fs::symlink(fs::read_link(entry.path())?, &target)?;
+        } else if file_type.is_dir() {
+            fs::create_dir_all(&target)?;
+        } else if let Some(kind) = special_kind(file_type) {
+            // Reading a FIFO blocks and a device never ends
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{} is a {}, it can't be copied", entry.path().display(), kind),
+            ));
+        } else {
+            fs::copy(entry.path(), &target)?;
+        }
+    }
     Ok(())
 }
 
-/// Move a file to a new location
-pub fn move_file(src: &std::path::Path, dst: &std::path::Path) -> Result<()> {
-    fs::rename(src, dst)?;
+/// Rename, or copy and remove when crossing filesystems
+pub fn move_path(from: &Path, to: &Path) -> io::Result<()> {
+    ensure_not_nested(from, to)?;
+    match fs::rename(from, to) {
+        Err(e) if e.kind() == io::ErrorKind::CrossesDevices => {
+            copy_tree(from, to)?;
+            if from.is_dir() && !from.is_symlink() {
+                fs::remove_dir_all(from)
+            } else {
+                fs::remove_file(from)
+            }
+        }
+        other => other,
+    }
+}
+
+/// Refuse to copy or move a directory into itself, comparing resolved
+/// parents so links and ".." can't hide it
+fn ensure_not_nested(from: &Path, to: &Path) -> io::Result<()> {
+    let from = resolve_parent(from).unwrap_or_else(|| from.to_path_buf());
+    let to = resolve_parent(to).unwrap_or_else(|| to.to_path_buf());
+    if to.starts_with(&from) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Cannot copy {} into itself", from.display()),
+        ));
+    }
     Ok(())
 }
 
+/// `path` with its parent canonicalized and its last component kept
+fn resolve_parent(path: &Path) -> Option<std::path::PathBuf> {
+    let (Some(parent), Some(name)) = (path.parent(), path.file_name()) else {
+        return path.canonicalize().ok();
+    };
+    let parent = if parent.as_os_str().is_empty() { Path::new(".") } else { parent };
+    Some(parent.canonicalize().ok()?.join(name))
+}
+
+fn special_kind(file_type: fs::FileType) -> Option<&'static str> {
+    use std::os::unix::fs::FileTypeExt;
+    if file_type.is_fifo() {
+        Some("FIFO")
+    } else if file_type.is_socket() {
+        Some("socket")
+    } else if file_type.is_char_device() || file_type.is_block_device() {
+        Some("device")
+    } else {
+        None
+    }
+}
+
 /// Rename a file in place
 pub fn rename_file(old: &std::path::Path, new_name: &str) -> Result<()> {
     if let Some(parent) = old.parent() {
@@ -36,34 +115,33 @@ pub fn rename_file(old: &std::path::Path, new_name: &str) -> Result<()> {
     Ok(())
 }
 
-/// Delete a file
-pub fn delete_file(path: &std::path::Path) -> Result<()> {
-    if path.is_dir() {
-        fs::remove_dir_all(path)?;
-    } else {
-        fs::remove_file(path)?;
+/// Create a directory, and any missing parents
+pub fn make_dir(path: &std::path::Path) -> Result<()> {
+    if path.exists() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::AlreadyExists,
+            format!("{} already exists", path.display()),
+        ).into());
     }
+    fs::create_dir_all(path)?;
     Ok(())
 }
 
-/// Check if core zone is locked
-pub fn is_core_locked() -> bool {
-    let core_path = std::path::PathBuf::from("/home")
-        .join(std::env::var("USER").unwrap_or_else(|_| "christian".to_string()))
-        .join("0-core");
-    
-    // Check if directory has immutable attribute
-    if let Ok(output) = std::process::Command::new("lsattr")
-        .arg("-d")
-        .arg(&core_path)
-        .output()
-    {
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        let parts: Vec<&str> = stdout.split_whitespace().collect();
-        if let Some(attrs) = parts.first() {
-            return attrs.contains('i');
-        }
+/// Create an empty file, or bump the modified time of an existing one.
+/// Returns whether the file is new.
+pub fn touch_file(path: &std::path::Path) -> Result<bool> {
+    let existed = path.exists();
+    let file = fs::OpenOptions::new().create(true).append(true).open(path)?;
+    if existed {
+        file.set_modified(std::time::SystemTime::now())?;
     }
-    
-    false
+    Ok(!existed)
+}
+
+/// Set the permission bits, returning the old ones
+pub fn set_mode(path: &std::path::Path, mode: u32) -> Result<u32> {
+    use std::os::unix::fs::PermissionsExt;
+    let old = fs::metadata(path)?.permissions().mode() & 0o7777;
+    fs::set_permissions(path, fs::Permissions::from_mode(mode))?;
+    Ok(old)
 }
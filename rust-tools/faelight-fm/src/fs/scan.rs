@@ -1,6 +1,8 @@
 use std::path::{Path, PathBuf};
 use std::fs;
+use crate::config::{SortBy, SortConfig};
 use crate::error::Result;
+use crate::model::FaelightEntry;
 
 /// Read directory contents (non-recursive)
 pub fn read_dir(path: &Path) -> Result<Vec<PathBuf>> {
@@ -25,3 +27,47 @@ pub fn read_dir(path: &Path) -> Result<Vec<PathBuf>> {
     
     Ok(entries)
 }
+
+/// Sort entries as configured in fm.toml, ties broken by name
+pub fn sort_entries(entries: &mut Vec<FaelightEntry>, sort: &SortConfig) {
+    let key = |e: &FaelightEntry| {
+        let meta = fs::metadata(&e.path).ok();
+        match sort.by {
+            SortBy::Name => SortKey::None,
+            SortBy::Size => SortKey::Number(meta.map(|m| m.len()).unwrap_or(0) as u128),
+            SortBy::Modified => SortKey::Number(
+                meta.and_then(|m| m.modified().ok())
+                    .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+                    .map(|d| d.as_nanos())
+                    .unwrap_or(0),
+            ),
+            SortBy::Extension => SortKey::Text(
+                e.path.extension().map(|x| x.to_string_lossy().to_lowercase()).unwrap_or_default(),
+            ),
+        }
+    };
+    
+    let mut keyed: Vec<(SortKey, FaelightEntry)> = std::mem::take(entries)
+        .into_iter()
+        .map(|e| (key(&e), e))
+        .collect();
+    keyed.sort_by(|(ka, a), (kb, b)| {
+        let order = ka.cmp(kb).then_with(|| a.name.cmp(&b.name));
+        let order = if sort.reverse { order.reverse() } else { order };
+        
+        if sort.dirs_first {
+            b.is_dir.cmp(&a.is_dir).then(order)
+        } else {
+            order
+        }
+    });
+    
+    *entries = keyed.into_iter().map(|(_, e)| e).collect();
+}
+
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+enum SortKey {
+    None,
+    Number(u128),
+    Text(String),
+}
@@ -0,0 +1,208 @@
+//! freedesktop.org Trash in $XDG_DATA_HOME/Trash (~/.local/share/Trash)
+//!
+//! Only the home trash is used. Per-mount `$topdir/.Trash-$uid` dirs are
+//! not, so trashing from another filesystem copies the whole tree into the
+//! home trash and then removes the original.
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use chrono::{Local, NaiveDateTime};
+use crate::error::Result;
+use super::ops::move_path;
+
+const DATE_FORMAT: &str = "%Y-%m-%dT%H:%M:%S";
+
+/// Something in the trash
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrashItem {
+    /// Name under Trash/files (and Trash/info with ".trashinfo")
+    pub name: String,
+    /// Where it was trashed from
+    pub original: PathBuf,
+    pub deleted: Option<NaiveDateTime>,
+}
+
+impl TrashItem {
+    pub fn path(&self) -> PathBuf {
+        trash_dir().join("files").join(&self.name)
+    }
+    
+    fn info_path(&self) -> PathBuf {
+        trash_dir().join("info").join(format!("{}.trashinfo", self.name))
+    }
+}
+
+pub fn trash_dir() -> PathBuf {
+    let data = std::env::var("XDG_DATA_HOME")
+        .ok()
+        .filter(|d| !d.is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(|| {
+            let home = std::env::var("HOME").unwrap_or_else(|_| "/".to_string());
+            PathBuf::from(home).join(".local/share")
+        });
+    data.join("Trash")
+}
+
+/// Move `path` to the trash. Across filesystems this is a full copy.
+pub fn trash(path: &Path) -> Result<TrashItem> {
+    let original = std::path::absolute(path)?;
+    if !original.exists() && !original.is_symlink() {
+        return Err(io::Error::new(io::ErrorKind::NotFound, format!("{} not found", path.display())).into());
+    }
+    
+    let dir = trash_dir();
+    fs::create_dir_all(dir.join("files"))?;
+    fs::create_dir_all(dir.join("info"))?;
+    
+    let now = Local::now().naive_local();
+    let info = format!(
+        "[Trash Info]\nPath={}\nDeletionDate={}\n",
+        encode_path(&original),
+        now.format(DATE_FORMAT)
+    );
+    
+    // Creating the .trashinfo first claims the name
+    let base = original.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    let mut n = 1;
+    let item = loop {
+        let name = if n == 1 { base.clone() } else { format!("{}.{}", base, n) };
+        let item = TrashItem { name, original: original.clone(), deleted: Some(now) };
+        match fs::OpenOptions::new().write(true).create_new(true).open(item.info_path()) {
+            Ok(mut file) => {
+                io::Write::write_all(&mut file, info.as_bytes())?;
+                break item;
+            }
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => n += 1,
+            Err(e) => return Err(e.into()),
+        }
+    };
+    
+    if let Err(e) = move_path(&original, &item.path()) {
+        let _ = fs::remove_file(item.info_path());
+        return Err(e.into());
+    }
+    Ok(item)
+}
+
+/// Everything in the trash, newest first
+pub fn list() -> Vec<TrashItem> {
+    let Ok(entries) = fs::read_dir(trash_dir().join("info")) else {
+        return Vec::new();
+    };
+    
+    let mut items: Vec<TrashItem> = entries
+        .filter_map(|e| e.ok())
+        .filter_map(|e| {
+            let file_name = e.file_name().to_string_lossy().to_string();
+            let name = file_name.strip_suffix(".trashinfo")?.to_string();
+            let content = fs::read_to_string(e.path()).ok()?;
+            parse_info(name, &content)
+        })
+        .collect();
+    
+    items.sort_by(|a, b| b.deleted.cmp(&a.deleted).then_with(|| a.name.cmp(&b.name)));
+    items
+}
+
+/// Find an item by its name under Trash/files
+pub fn find(name: &str) -> Option<TrashItem> {
+    let path = trash_dir().join("info").join(format!("{}.trashinfo", name));
+    let content = fs::read_to_string(path).ok()?;
+    parse_info(name.to_string(), &content)
+}
+
+/// Put an item back where it was trashed from
+pub fn restore(item: &TrashItem) -> Result<()> {
+    if item.original.exists() || item.original.is_symlink() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{} already exists", item.original.display()),
+        ).into());
+    }
+    
+    if let Some(parent) = item.original.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    move_path(&item.path(), &item.original)?;
+    fs::remove_file(item.info_path())?;
+    Ok(())
+}
+
+/// Delete an item for good
+pub fn purge(item: &TrashItem) -> Result<()> {
+    let path = item.path();
+    if path.is_dir() && !path.is_symlink() {
+        fs::remove_dir_all(&path)?;
+    } else if path.is_symlink() || path.exists() {
+        fs::remove_file(&path)?;
+    }
+    fs::remove_file(item.info_path())?;
+    Ok(())
+}
+
+fn parse_info(name: String, content: &str) -> Option<TrashItem> {
+    let mut original = None;
+    let mut deleted = None;
+    
+    for line in content.lines() {
+        if let Some(path) = line.strip_prefix("Path=") {
+            original = Some(PathBuf::from(decode_path(path)));
+        } else if let Some(date) = line.strip_prefix("DeletionDate=") {
+            deleted = NaiveDateTime::parse_from_str(date.trim(), DATE_FORMAT).ok();
+        }
+    }
+    
+    Some(TrashItem { name, original: original?, deleted })
+}
+
+/// Percent-encode a path for the Path= key
+fn encode_path(path: &Path) -> String {
+    let mut out = String::new();
+    for byte in path.as_os_str().as_encoded_bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'/' | b'-' | b'_' | b'.' | b'~' => out.push(*byte as char),
+            _ => out.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    out
+}
+
+fn decode_path(encoded: &str) -> String {
+    let bytes = encoded.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    
+    while i < bytes.len() {
+        let hex = bytes.get(i + 1..i + 3).and_then(|h| std::str::from_utf8(h).ok());
+        match (bytes[i], hex.and_then(|h| u8::from_str_radix(h, 16).ok())) {
+            (b'%', Some(byte)) => {
+                out.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                out.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_trashinfo_round_trip() {
+        let path = Path::new("/home/faelight/notes & ideas/ünïcode.md");
+        let encoded = encode_path(path);
+        assert_eq!(encoded.find(' '), None);
+        assert_eq!(decode_path(&encoded), path.to_string_lossy());
+        
+        let info = format!("[Trash Info]\nPath={}\nDeletionDate=2026-01-29T14:30:12\n", encoded);
+        let item = parse_info("ünïcode.md".to_string(), &info).unwrap();
+        assert_eq!(item.original, path);
+        assert_eq!(item.deleted.unwrap().format(DATE_FORMAT).to_string(), "2026-01-29T14:30:12");
+    }
+}
@@ -1,60 +1,324 @@
-//! Git status detection for file markers
-use std::path::Path;
-use std::process::Command;
+//! Git status for file markers, from libgit2 and cached per repository
+pub mod ops;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum GitStatus {
-    Clean,
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+use git2::{Repository, Status, StatusEntry, StatusOptions};
+
+/// A repository's status is reused this long, unless its index changes
+/// or the cache is invalidated first
+const MAX_AGE: Duration = Duration::from_secs(5);
+
+/// One side of a path's status
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GitState {
+    #[default]
+    Unmodified,
     Modified,
     Added,
     Deleted,
+    Renamed,
+    TypeChanged,
     Untracked,
+    Ignored,
+    Conflicted,
 }
 
-impl GitStatus {
-    pub fn marker(&self) -> &str {
+impl GitState {
+    /// Letter as in `git status --short`
+    pub fn code(&self) -> char {
+        match self {
+            GitState::Unmodified => ' ',
+            GitState::Modified => 'M',
+            GitState::Added => 'A',
+            GitState::Deleted => 'D',
+            GitState::Renamed => 'R',
+            GitState::TypeChanged => 'T',
+            GitState::Untracked => '?',
+            GitState::Ignored => '!',
+            GitState::Conflicted => 'U',
+        }
+    }
+    
+    pub fn label(&self) -> &'static str {
+        match self {
+            GitState::Unmodified => "unmodified",
+            GitState::Modified => "modified",
+            GitState::Added => "added",
+            GitState::Deleted => "deleted",
+            GitState::Renamed => "renamed",
+            GitState::TypeChanged => "type changed",
+            GitState::Untracked => "untracked",
+            GitState::Ignored => "ignored",
+            GitState::Conflicted => "conflicted",
+        }
+    }
+    
+    /// Which state a directory shows when its files differ
+    fn weight(&self) -> u8 {
         match self {
-            GitStatus::Clean => "  ",
-            GitStatus::Modified => "M ",
-            GitStatus::Added => "A ",
-            GitStatus::Deleted => "D ",
-            GitStatus::Untracked => "??",
-        }
-    }
-}
-
-/// Get git status for all files in a directory
-pub fn get_status(dir: &Path) -> std::collections::HashMap<String, GitStatus> {
-    let mut status_map = std::collections::HashMap::new();
-    
-    // Run git status --porcelain
-    let output = Command::new("git")
-        .arg("status")
-        .arg("--porcelain")
-        .current_dir(dir)
-        .output();
-    
-    if let Ok(output) = output {
-        if output.status.success() {
-            let status_text = String::from_utf8_lossy(&output.stdout);
-            for line in status_text.lines() {
-                if line.len() >= 3 {
-                    let status_code = &line[0..2];
-                    let filename = line[3..].trim();
-                    
-                    let status = match status_code.trim() {
-                        "M" | " M" | "MM" => GitStatus::Modified,
-                        "A" | "AM" => GitStatus::Added,
-                        "D" | " D" => GitStatus::Deleted,
-                        "??" => GitStatus::Untracked,
-                        _ => GitStatus::Clean,
-                    };
-                    
-                    status_map.insert(filename.to_string(), status);
+            GitState::Unmodified | GitState::Ignored => 0,
+            GitState::Untracked => 1,
+            GitState::TypeChanged => 2,
+            GitState::Renamed => 3,
+            GitState::Added => 4,
+            GitState::Modified => 5,
+            GitState::Deleted => 6,
+            GitState::Conflicted => 7,
+        }
+    }
+}
+
+/// Staged and unstaged state of a path
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct GitStatus {
+    /// HEAD against the index
+    pub index: GitState,
+    /// The index against the working tree
+    pub worktree: GitState,
+}
+
+impl GitStatus {
+    /// Two letters, staged then unstaged, as in `git status --short`
+    pub fn marker(&self) -> String {
+        format!("{}{}", self.index.code(), self.worktree.code())
+    }
+    
+    pub fn is_clean(&self) -> bool {
+        *self == GitStatus::default()
+    }
+    
+    fn both(state: GitState) -> Self {
+        GitStatus { index: state, worktree: state }
+    }
+    
+    fn from_flags(flags: Status) -> Self {
+        if flags.is_conflicted() {
+            return GitStatus::both(GitState::Conflicted);
+        }
+        if flags.is_ignored() {
+            return GitStatus::both(GitState::Ignored);
+        }
+        
+        let index = if flags.is_index_new() {
+            GitState::Added
+        } else if flags.is_index_modified() {
+            GitState::Modified
+        } else if flags.is_index_deleted() {
+            GitState::Deleted
+        } else if flags.is_index_renamed() {
+            GitState::Renamed
+        } else if flags.is_index_typechange() {
+            GitState::TypeChanged
+        } else {
+            GitState::Unmodified
+        };
+        
+        let worktree = if flags.is_wt_new() {
+            // Not in the index at all: "??"
+            if index == GitState::Unmodified {
+                return GitStatus::both(GitState::Untracked);
+            }
+            GitState::Untracked
+        } else if flags.is_wt_modified() {
+            GitState::Modified
+        } else if flags.is_wt_deleted() {
+            GitState::Deleted
+        } else if flags.is_wt_renamed() {
+            GitState::Renamed
+        } else if flags.is_wt_typechange() {
+            GitState::TypeChanged
+        } else {
+            GitState::Unmodified
+        };
+        
+        GitStatus { index, worktree }
+    }
+    
+    /// The weightier state of each side
+    fn merge(self, other: GitStatus) -> GitStatus {
+        let pick = |a: GitState, b: GitState| if b.weight() > a.weight() { b } else { a };
+        GitStatus {
+            index: pick(self.index, other.index),
+            worktree: pick(self.worktree, other.worktree),
+        }
+    }
+}
+
+/// Status of one repository, by path relative to its root
+struct RepoStatus {
+    /// Changed files, and untracked or ignored directories git reports whole
+    paths: HashMap<PathBuf, GitStatus>,
+    /// Directories, rolled up from the changes below them
+    dirs: HashMap<PathBuf, GitStatus>,
+    index_mtime: Option<SystemTime>,
+    loaded: Instant,
+}
+
+impl RepoStatus {
+    fn load(repo: &Repository) -> Option<RepoStatus> {
+        let mut opts = StatusOptions::new();
+        opts.include_untracked(true)
+            .recurse_untracked_dirs(false)
+            .include_ignored(true)
+            .recurse_ignored_dirs(false)
+            .renames_head_to_index(true)
+            .renames_index_to_workdir(true);
+        let statuses = repo.statuses(Some(&mut opts)).ok()?;
+        
+        let mut paths = HashMap::new();
+        let mut dirs: HashMap<PathBuf, GitStatus> = HashMap::new();
+        for entry in statuses.iter() {
+            let Some(path) = current_path(&entry) else {
+                continue;
+            };
+            let status = GitStatus::from_flags(entry.status());
+            
+            if status.index != GitState::Ignored {
+                for dir in path.ancestors().skip(1).filter(|d| !d.as_os_str().is_empty()) {
+                    let rolled = dirs.entry(dir.to_path_buf()).or_default();
+                    *rolled = rolled.merge(status);
                 }
             }
+            paths.insert(path, status);
+        }
+        
+        Some(RepoStatus { paths, dirs, index_mtime: index_mtime(repo), loaded: Instant::now() })
+    }
+    
+    fn is_fresh(&self, repo: &Repository) -> bool {
+        self.loaded.elapsed() < MAX_AGE && self.index_mtime == index_mtime(repo)
+    }
+    
+    /// Status of the entries of `dir` (relative to the root), by name
+    fn children(&self, dir: &Path, abs_dir: &Path) -> HashMap<String, GitStatus> {
+        // Inside an untracked or ignored directory everything shares its state
+        let whole = dir.ancestors().find_map(|d| self.paths.get(d));
+        if let Some(status) = whole {
+            let names = std::fs::read_dir(abs_dir).into_iter().flatten().filter_map(|e| e.ok());
+            return names.map(|e| (e.file_name().to_string_lossy().to_string(), *status)).collect();
+        }
+        
+        let mut children = HashMap::new();
+        for (path, status) in self.paths.iter().chain(&self.dirs) {
+            if path.parent() == Some(dir) {
+                if let Some(name) = path.file_name() {
+                    children.entry(name.to_string_lossy().to_string()).or_insert(*status);
+                }
+            }
+        }
+        children
+    }
+}
+
+/// Status of each repository seen, so moving around inside one doesn't
+/// rescan it
+#[derive(Default)]
+pub struct GitCache {
+    repos: HashMap<PathBuf, RepoStatus>,
+}
+
+impl GitCache {
+    /// Status of the entries of `dir` by name. Clean entries, and
+    /// everything outside a repository, are left out.
+    pub fn statuses(&mut self, dir: &Path) -> HashMap<String, GitStatus> {
+        let Some((repo, root, rel)) = locate(dir) else {
+            return HashMap::new();
+        };
+        
+        if !self.repos.get(&root).is_some_and(|s| s.is_fresh(&repo)) {
+            match RepoStatus::load(&repo) {
+                Some(status) => self.repos.insert(root.clone(), status),
+                None => return HashMap::new(),
+            };
         }
+        self.repos[&root].children(&rel, dir)
     }
     
-    status_map
+    /// Forget everything, after files changed
+    pub fn invalidate(&mut self) {
+        self.repos.clear();
+    }
+}
+
+/// The repository holding `path`, its working directory and `path`
+/// relative to that
+fn locate(path: &Path) -> Option<(Repository, PathBuf, PathBuf)> {
+    let repo = Repository::discover(path).ok()?;
+    let root = repo.workdir()?.canonicalize().ok()?;
+    let rel = path.canonicalize().ok()?.strip_prefix(&root).ok()?.to_path_buf();
+    Some((repo, root, rel))
+}
+
+/// Where the file is now: the new name of a rename
+fn current_path(entry: &StatusEntry) -> Option<PathBuf> {
+    let renamed = entry
+        .index_to_workdir()
+        .or_else(|| entry.head_to_index())
+        .and_then(|delta| delta.new_file().path().map(Path::to_path_buf));
+    let path = renamed.or_else(|| entry.path().map(PathBuf::from))?;
+    
+    // Whole directories come with a trailing slash
+    Some(PathBuf::from(path.to_string_lossy().trim_end_matches('/')))
+}
+
+fn index_mtime(repo: &Repository) -> Option<SystemTime> {
+    std::fs::metadata(repo.path().join("index")).and_then(|m| m.modified()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    
+    #[test]
+    fn test_repo_statuses() {
+        let root = std::env::temp_dir().join(format!("faelight-fm-git-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("src/deep")).unwrap();
+        let repo = Repository::init(&root).unwrap();
+        
+        fs::write(root.join(".gitignore"), "target/\n").unwrap();
+        fs::write(root.join("README.md"), "hello\n").unwrap();
+        fs::write(root.join("src/deep/lib.rs"), "fn a() {}\n").unwrap();
+        fs::write(root.join("src/old.rs"), "fn old() {}\n").unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_all(["*"], git2::IndexAddOption::DEFAULT, None).unwrap();
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let sig = git2::Signature::now("fm", "fm@example.com").unwrap();
+        repo.commit(Some("HEAD"), &sig, &sig, "init", &tree, &[]).unwrap();
+        
+        // Staged and then edited again, renamed, new and ignored
+        fs::write(root.join("README.md"), "hello again\n").unwrap();
+        index.add_path(Path::new("README.md")).unwrap();
+        fs::write(root.join("README.md"), "hello again, and again\n").unwrap();
+        fs::rename(root.join("src/old.rs"), root.join("src/new.rs")).unwrap();
+        index.remove_path(Path::new("src/old.rs")).unwrap();
+        index.add_path(Path::new("src/new.rs")).unwrap();
+        index.write().unwrap();
+        fs::write(root.join("src/deep/lib.rs"), "fn b() {}\n").unwrap();
+        fs::create_dir_all(root.join("target/debug")).unwrap();
+        fs::write(root.join("notes.txt"), "").unwrap();
+        
+        let mut cache = GitCache::default();
+        let top = cache.statuses(&root);
+        assert_eq!(top["README.md"].marker(), "MM");
+        assert_eq!(top["notes.txt"].marker(), "??");
+        assert_eq!(top["target"].marker(), "!!");
+        // src holds a staged rename and an unstaged edit
+        assert_eq!(top["src"].marker(), "RM");
+        assert!(!top.contains_key(".gitignore"));
+        
+        // Paths are relative to the repository root, not cwd
+        let src = cache.statuses(&root.join("src"));
+        assert_eq!(src["new.rs"].marker(), "R ");
+        assert_eq!(src["deep"].marker(), " M");
+        assert!(!src.contains_key("old.rs"));
+        assert_eq!(cache.statuses(&root.join("src/deep"))["lib.rs"].marker(), " M");
+        assert_eq!(cache.statuses(&root.join("target"))["debug"].marker(), "!!");
+        
+        fs::remove_dir_all(&root).unwrap();
+    }
 }
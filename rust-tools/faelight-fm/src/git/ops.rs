@@ -0,0 +1,166 @@
+//! Staging, unstaging and diffs of entries, like `git add`,
+//! `git restore --staged` and `git diff`
+use std::path::{Path, PathBuf};
+use git2::{DiffFormat, DiffOptions, IndexAddOption, Repository};
+use crate::error::{FmError, Result};
+
+fn git_error(e: git2::Error) -> FmError {
+    FmError::Git(e.message().to_string())
+}
+
+/// The repository holding `paths` and the paths relative to its root
+fn open(paths: &[PathBuf]) -> Result<(Repository, Vec<PathBuf>)> {
+    let first = paths.first().ok_or_else(|| FmError::Git("Nothing selected".to_string()))?;
+    let dir = first.parent().unwrap_or(first);
+    let repo = Repository::discover(dir)
+        .map_err(|_| FmError::Git(format!("{} is not in a git repository", dir.display())))?;
+    let root = repo
+        .workdir()
+        .ok_or_else(|| FmError::Git("Bare repository".to_string()))?
+        .canonicalize()?;
+    
+    // Resolve the directory only, a symlink is staged as itself
+    let mut rel = Vec::new();
+    for path in paths {
+        let parent = path.parent().unwrap_or(path).canonicalize()?;
+        let full = parent.join(path.file_name().unwrap_or_default());
+        let inside = full
+            .strip_prefix(&root)
+            .map_err(|_| FmError::Git(format!("{} is outside {}", full.display(), root.display())))?;
+        if inside.starts_with(".git") {
+            return Err(FmError::Git(format!("{} is git's own", full.display())));
+        }
+        rel.push(inside.to_path_buf());
+    }
+    Ok((repo, rel))
+}
+
+/// Stage `paths`. Directories are staged with everything below them,
+/// deletions included; ignored files are refused.
+pub fn stage(paths: &[PathBuf]) -> Result<()> {
+    let (repo, rel) = open(paths)?;
+    let mut index = repo.index().map_err(git_error)?;
+    
+    for (path, rel) in paths.iter().zip(&rel) {
+        if path.is_dir() && !path.is_symlink() {
+            index.add_all([rel], IndexAddOption::DEFAULT, None).map_err(git_error)?;
+            index.update_all([rel], None).map_err(git_error)?;
+        } else if path.exists() || path.is_symlink() {
+            let tracked = index.get_path(rel, 0).is_some();
+            if !tracked && repo.status_should_ignore(rel).map_err(git_error)? {
+                return Err(FmError::Git(format!("{} is ignored", rel.display())));
+            }
+            index.add_path(rel).map_err(git_error)?;
+        } else {
+            index.remove_path(rel).map_err(git_error)?;
+        }
+    }
+    index.write().map_err(git_error)
+}
+
+/// Put the staged state of `paths` back to HEAD, leaving the files alone
+pub fn unstage(paths: &[PathBuf]) -> Result<()> {
+    let (repo, rel) = open(paths)?;
+    let head = repo.head().ok().and_then(|h| h.peel_to_commit().ok());
+    
+    match head {
+        Some(commit) => repo.reset_default(Some(commit.as_object()), &rel).map_err(git_error),
+        // Nothing committed yet: take them out of the index
+        None => {
+            let mut index = repo.index().map_err(git_error)?;
+            index.remove_all(&rel, None).map_err(git_error)?;
+            index.write().map_err(git_error)
+        }
+    }
+}
+
+/// Staged then unstaged changes to `path` as patch lines, each part
+/// under a heading. Empty when nothing changed.
+pub fn diff(path: &Path) -> Result<Vec<String>> {
+    let (repo, rel) = open(&[path.to_path_buf()])?;
+    let mut opts = DiffOptions::new();
+    // A literal path: "[ab].txt" must not pull in a.txt
+    opts.pathspec(&rel[0])
+        .disable_pathspec_match(true)
+        .include_untracked(true)
+        .recurse_untracked_dirs(true)
+        .show_untracked_content(true);
+    
+    let head = repo.head().ok().and_then(|h| h.peel_to_tree().ok());
+    let staged = repo.diff_tree_to_index(head.as_ref(), None, Some(&mut opts)).map_err(git_error)?;
+    let unstaged = repo.diff_index_to_workdir(None, Some(&mut opts)).map_err(git_error)?;
+    
+    let mut lines = Vec::new();
+    for (heading, diff) in [("Staged", staged), ("Unstaged", unstaged)] {
+        let mut patch = Vec::new();
+        diff.print(DiffFormat::Patch, |_, _, line| {
+            let origin = match line.origin() {
+                c @ ('+' | '-' | ' ') => c.to_string(),
+                _ => String::new(),
+            };
+            let content = String::from_utf8_lossy(line.content());
+            patch.extend(content.lines().map(|l| format!("{}{}", origin, l)));
+            true
+        }).map_err(git_error)?;
+        
+        if !patch.is_empty() {
+            if !lines.is_empty() {
+                lines.push(String::new());
+            }
+            lines.push(format!("{} changes:", heading));
+            lines.extend(patch);
+        }
+    }
+    Ok(lines)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use crate::git::GitCache;
+    
+    #[test]
+    fn test_stage_unstage_diff() {
+        let root = std::env::temp_dir().join(format!("faelight-fm-git-ops-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("dir")).unwrap();
+        let repo = Repository::init(&root).unwrap();
+        let file = root.join("a.txt");
+        fs::write(&file, "one\n").unwrap();
+        fs::write(root.join("dir/b.txt"), "two\n").unwrap();
+        
+        // Before the first commit
+        stage(&[file.clone(), root.join("dir")]).unwrap();
+        let marker = |cache: &mut GitCache| cache.statuses(&root)["a.txt"].marker();
+        assert_eq!(marker(&mut GitCache::default()), "A ");
+        unstage(std::slice::from_ref(&file)).unwrap();
+        assert_eq!(marker(&mut GitCache::default()), "??");
+        assert_eq!(GitCache::default().statuses(&root)["dir"].marker(), "A ");
+        
+        stage(std::slice::from_ref(&file)).unwrap();
+        let mut index = repo.index().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let sig = git2::Signature::now("fm", "fm@example.com").unwrap();
+        repo.commit(Some("HEAD"), &sig, &sig, "init", &tree, &[]).unwrap();
+        
+        fs::write(&file, "one\nthree\n").unwrap();
+        stage(std::slice::from_ref(&file)).unwrap();
+        fs::write(&file, "one\nthree\nfour\n").unwrap();
+        let lines = diff(&file).unwrap();
+        assert_eq!(lines.iter().filter(|l| l.ends_with("changes:")).count(), 2);
+        assert!(lines.contains(&"+three".to_string()));
+        assert!(lines.contains(&"+four".to_string()));
+        
+        unstage(std::slice::from_ref(&file)).unwrap();
+        assert_eq!(marker(&mut GitCache::default()), " M");
+        assert!(diff(&root.join("dir/b.txt")).unwrap().is_empty());
+        
+        fs::write(root.join("[ab].txt"), "glob\n").unwrap();
+        let lines = diff(&root.join("[ab].txt")).unwrap();
+        assert!(lines.contains(&"+glob".to_string()));
+        assert!(!lines.contains(&"+four".to_string()));
+        
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,157 @@
+//! Per-entry health: broken links, stow links gone astray, scripts that
+//! can't run, configs that don't parse and packages without a .dotmeta
+use std::fs::{self, File};
+use std::io::Read;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use crate::model::HealthStatus;
+
+/// Configs bigger than this aren't parsed
+const MAX_CONFIG_LEN: u64 = 1024 * 1024;
+
+/// Checks entries against the 0-core checkout and its stow dir
+#[derive(Debug, Clone)]
+pub struct HealthCheck {
+    core: PathBuf,
+    stow: PathBuf,
+}
+
+impl HealthCheck {
+    /// For ~/0-core/stow
+    pub fn new(home: &Path) -> Self {
+        Self::with_stow_dir(&home.join("0-core/stow"))
+    }
+    
+    pub fn with_stow_dir(stow: &Path) -> Self {
+        let stow = stow.canonicalize().unwrap_or_else(|_| stow.to_path_buf());
+        let core = stow.parent().map(Path::to_path_buf).unwrap_or_else(|| stow.clone());
+        HealthCheck { core, stow }
+    }
+    
+    /// The worst problem with `path` and why, or None when it's healthy
+    pub fn check(&self, path: &Path) -> Option<(HealthStatus, String)> {
+        let link = fs::symlink_metadata(path).ok()?;
+        let Ok(meta) = fs::metadata(path) else {
+            let target = fs::read_link(path).unwrap_or_default();
+            return Some((HealthStatus::Error, format!("Broken link to {}", target.display())));
+        };
+        
+        if let Some(error) = invalid_config(path, &meta) {
+            return Some((HealthStatus::Error, error));
+        }
+        if link.is_symlink() {
+            if let Some(target) = self.stray_stow_link(path) {
+                let reason = format!("Stow link to {}, outside {}", target.display(), self.stow.display());
+                return Some((HealthStatus::Warning, reason));
+            }
+        }
+        if meta.is_dir() && self.is_package(path) && !path.join(".dotmeta").exists() {
+            return Some((HealthStatus::Warning, "Stow package without a .dotmeta".to_string()));
+        }
+        if meta.is_file() && meta.permissions().mode() & 0o111 == 0 && has_shebang(path) {
+            return Some((HealthStatus::Warning, "Script is not executable (chmod +x)".to_string()));
+        }
+        None
+    }
+    
+    /// Where a link resolves, when it's a stow link that lands outside the
+    /// stow dir: one from outside 0-core into it, or one inside a package
+    fn stray_stow_link(&self, path: &Path) -> Option<PathBuf> {
+        let target = path.canonicalize().ok()?;
+        let dir = path.parent()?.canonicalize().ok()?;
+        let into_core = !dir.starts_with(&self.core) && target.starts_with(&self.core);
+        let stow_link = dir.starts_with(&self.stow) || into_core;
+        (stow_link && !target.starts_with(&self.stow)).then_some(target)
+    }
+    
+    /// A directory right below the stow dir
+    fn is_package(&self, path: &Path) -> bool {
+        let hidden = path.file_name().is_some_and(|n| n.to_string_lossy().starts_with('.'));
+        !hidden && path.parent().and_then(|p| p.canonicalize().ok()).as_deref() == Some(self.stow.as_path())
+    }
+}
+
+/// Why a .toml or .json file doesn't parse
+fn invalid_config(path: &Path, meta: &fs::Metadata) -> Option<String> {
+    let ext = path.extension()?.to_string_lossy().to_lowercase();
+    if !meta.is_file() || meta.len() > MAX_CONFIG_LEN || !matches!(ext.as_str(), "toml" | "json") {
+        return None;
+    }
+    
+    let kind = ext.to_uppercase();
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(_) => return Some(format!("Invalid {}: not UTF-8 text", kind)),
+    };
+    
+    if ext == "json" {
+        return serde_json::from_str::<serde_json::Value>(&content)
+            .err()
+            .map(|e| format!("Invalid JSON: {}", e));
+    }
+    
+    let error = toml::from_str::<toml::Table>(&content).err()?;
+    let message = error.message().trim().replace('\n', ", ");
+    let line = error.span().map(|span| content[..span.start].matches('\n').count() + 1);
+    Some(match line {
+        Some(line) => format!("Invalid TOML: {} at line {}", message, line),
+        None => format!("Invalid TOML: {}", message),
+    })
+}
+
+fn has_shebang(path: &Path) -> bool {
+    let mut head = [0u8; 2];
+    File::open(path).and_then(|mut f| f.read_exact(&mut head)).is_ok() && &head == b"#!"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::symlink;
+    
+    #[test]
+    fn test_health_checks() {
+        let root = std::env::temp_dir().join(format!("faelight-fm-health-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        let stow = root.join("0-core/stow");
+        fs::create_dir_all(stow.join("shell-zsh")).unwrap();
+        fs::create_dir_all(stow.join("term-foot")).unwrap();
+        fs::create_dir_all(root.join("0-core/scripts")).unwrap();
+        fs::create_dir_all(root.join("home")).unwrap();
+        fs::write(stow.join("shell-zsh/.dotmeta"), "").unwrap();
+        fs::write(stow.join("shell-zsh/.zshrc"), "").unwrap();
+        
+        let health = HealthCheck::with_stow_dir(&stow);
+        let status = |path: &Path| health.check(path).map(|(status, _)| status);
+        
+        // Broken, stray and good links
+        symlink(root.join("gone"), root.join("home/broken")).unwrap();
+        symlink(root.join("0-core/scripts"), root.join("home/scripts")).unwrap();
+        symlink(stow.join("shell-zsh/.zshrc"), root.join("home/.zshrc")).unwrap();
+        assert_eq!(status(&root.join("home/broken")), Some(HealthStatus::Error));
+        assert_eq!(status(&root.join("home/scripts")), Some(HealthStatus::Warning));
+        assert_eq!(status(&root.join("home/.zshrc")), None);
+        
+        assert_eq!(status(&stow.join("shell-zsh")), None);
+        let (_, reason) = health.check(&stow.join("term-foot")).unwrap();
+        assert!(reason.contains(".dotmeta"));
+        
+        let script = root.join("home/run");
+        fs::write(&script, "#!/bin/sh\necho hi\n").unwrap();
+        fs::set_permissions(&script, fs::Permissions::from_mode(0o644)).unwrap();
+        assert_eq!(status(&script), Some(HealthStatus::Warning));
+        fs::set_permissions(&script, fs::Permissions::from_mode(0o755)).unwrap();
+        assert_eq!(status(&script), None);
+        
+        fs::write(root.join("home/good.toml"), "[a]\nb = 1\n").unwrap();
+        fs::write(root.join("home/bad.toml"), "[a]\nb = \n").unwrap();
+        fs::write(root.join("home/bad.json"), "{\"a\": }").unwrap();
+        assert_eq!(status(&root.join("home/good.toml")), None);
+        let (status, reason) = health.check(&root.join("home/bad.toml")).unwrap();
+        assert_eq!(status, HealthStatus::Error);
+        assert!(reason.starts_with("Invalid TOML") && reason.ends_with("line 2"), "{}", reason);
+        assert!(health.check(&root.join("home/bad.json")).unwrap().1.starts_with("Invalid JSON"));
+        
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
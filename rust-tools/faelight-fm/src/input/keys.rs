@@ -1,13 +1,83 @@
 use crossterm::event::KeyCode;
-use crate::app::AppState;
+use crate::app::{AppState, Mode};
+use faelight_fm::config::Action;
 use faelight_fm::error::Result;
-use faelight_zone::Zone;
+use faelight_zone::Zones;
 
 pub fn handle_key<B: ratatui::backend::Backend>(
     key: KeyCode,
     app: &mut AppState,
     terminal: &mut ratatui::Terminal<B>,
 ) -> Result<()> {
+    // A (y/n) question takes the next key
+    if app.has_confirm() {
+        let yes = matches!(key, KeyCode::Char('y') | KeyCode::Char('Y'));
+        return app.answer_confirm(yes, terminal);
+    }
+    
+    // The : command line takes text
+    if app.mode == Mode::Command {
+        match key {
+            KeyCode::Char(c) => app.command_add_char(c),
+            KeyCode::Backspace => app.command_backspace(),
+            KeyCode::Tab => app.command_complete(),
+            KeyCode::Up => app.command_history(true),
+            KeyCode::Down => app.command_history(false),
+            KeyCode::Enter => app.run_command_line(terminal)?,
+            KeyCode::Esc => app.cancel_command(),
+            _ => {}
+        }
+        return Ok(());
+    }
+    
+    // Diff viewer scrolls, anything else closes it
+    if app.diff_view.is_some() {
+        match app.config.keymap.get(key) {
+            Some(Action::Down) => app.diff_scroll(true),
+            Some(Action::Up) => app.diff_scroll(false),
+            _ => app.close_diff(),
+        }
+        return Ok(());
+    }
+    
+    // :find results
+    if app.find_view.is_some() {
+        match app.config.keymap.get(key) {
+            Some(Action::Down) => app.find_select_next(),
+            Some(Action::Up) => app.find_select_prev(),
+            Some(Action::Enter) => app.find_go()?,
+            _ if key == KeyCode::Enter => app.find_go()?,
+            _ => app.close_find(),
+        }
+        return Ok(());
+    }
+    
+    // Trash browser
+    if app.trash_view.is_some() {
+        match (key, app.config.keymap.get(key)) {
+            (KeyCode::Char('r') | KeyCode::Enter, _) => app.restore_trash_item()?,
+            (_, Some(Action::Down)) => app.trash_select_next(),
+            (_, Some(Action::Up)) => app.trash_select_prev(),
+            (_, Some(Action::Delete)) => app.purge_trash_item()?,
+            (_, Some(Action::Undo)) => app.undo()?,
+            (KeyCode::Esc, _) | (_, Some(Action::Quit | Action::Trash)) => app.close_trash(),
+            _ => {}
+        }
+        return Ok(());
+    }
+    
+    // Open with chooser
+    if app.open_with.is_some() {
+        match app.config.keymap.get(key) {
+            Some(Action::Down) => app.open_with_next(),
+            Some(Action::Up) => app.open_with_prev(),
+            Some(Action::Enter) => app.open_with_run(terminal)?,
+            _ if key == KeyCode::Enter => app.open_with_run(terminal)?,
+            _ => app.close_open_with(),
+        }
+        return Ok(());
+    }
+    
     // If any overlay is visible, any key closes it
     if app.help_visible {
         app.toggle_help();
@@ -25,7 +95,7 @@ pub fn handle_key<B: ratatui::backend::Backend>(
     }
     
     // Search mode - handle text input
-    if app.search_mode {
+    if app.tab().search_mode {
         match key {
             KeyCode::Char(c) => app.search_add_char(c),
             KeyCode::Backspace => app.search_backspace(),
@@ -37,52 +107,77 @@ pub fn handle_key<B: ratatui::backend::Backend>(
         return Ok(());
     }
     
-    // Normal mode
-    match key {
+    // Esc drops a range being marked instead of quitting
+    if key == KeyCode::Esc && app.tab().visual_anchor.is_some() {
+        app.cancel_visual();
+        return Ok(());
+    }
+    
+    // Normal mode, keys from fm.toml
+    let Some(action) = app.config.keymap.get(key).cloned() else {
+        return Ok(());
+    };
+    
+    match action {
         // Navigation
-        KeyCode::Char('j') | KeyCode::Down => app.select_next(),
-        KeyCode::Char('k') | KeyCode::Up => app.select_prev(),
-        KeyCode::Char('l') | KeyCode::Right | KeyCode::Enter => app.enter_selected()?,
-        KeyCode::Char('h') | KeyCode::Left => app.go_parent()?,
+        Action::Down => app.select_next(),
+        Action::Up => app.select_prev(),
+        Action::Enter => app.open_selected(terminal)?,
+        Action::Parent => app.go_parent()?,
+        Action::Search => app.start_search(),
+        Action::ToggleHidden => app.toggle_hidden(),
+        Action::Unhealthy => app.toggle_unhealthy(),
         
         // Zone jumping
-        KeyCode::Char('0') => app.jump_to_zone(Zone::Core)?,
-        KeyCode::Char('1') => app.jump_to_zone(Zone::Workspace)?,
-        KeyCode::Char('2') => app.jump_to_zone(Zone::Src)?,
-        KeyCode::Char('3') => app.jump_to_zone(Zone::Project)?,
-        KeyCode::Char('4') => app.jump_to_zone(Zone::Archive)?,
-        KeyCode::Char('5') => app.jump_to_zone(Zone::Scratch)?,
-        
-        // Overlays
-        // Overlays
-        KeyCode::Char('?') => app.toggle_help(),
-        KeyCode::Char('i') => app.toggle_info(),
-        KeyCode::Char('p') => {
-            app.load_preview();
-            app.toggle_preview();
-        },
+        Action::JumpZone(id) => {
+            if let Some(zone) = Zones::global().get(&id) {
+                app.jump_to_zone(zone.clone())?;
+            }
+        }
         
-        // Edit file in nvim
-        KeyCode::Char('e') => app.edit_selected(terminal)?,
+        // Marking
+        Action::Mark => app.toggle_mark(),
+        Action::Visual => app.toggle_visual(),
+        Action::ClearMarks => app.clear_marks(),
         
         // File operations
-        KeyCode::Char('y') => {
-            app.yank_file(crate::app::YankMode::Copy);
-        },
-        KeyCode::Char('d') => {
-            app.yank_file(crate::app::YankMode::Cut);
-        },
-        KeyCode::Char('v') => {
-            app.paste_file()?;
-        },
+        Action::Yank => app.yank_file(crate::app::YankMode::Copy),
+        Action::Cut => app.yank_file(crate::app::YankMode::Cut),
+        Action::Paste => app.paste_file()?,
+        Action::Delete => app.delete_selected()?,
+        Action::Rename => app.bulk_rename(terminal)?,
+        Action::Undo => app.undo()?,
+        Action::Trash => app.open_trash(),
+        Action::CopyToPane => app.send_to_pane(crate::app::YankMode::Copy)?,
+        Action::MoveToPane => app.send_to_pane(crate::app::YankMode::Cut)?,
+        Action::Edit => app.edit_selected(terminal)?,
+        Action::OpenWith => app.open_with_selected(),
+        Action::Command => app.start_command(),
         
-        // Search
-        KeyCode::Char('/') => app.start_search(),
+        // Git
+        Action::Stage => app.git_stage()?,
+        Action::Unstage => app.git_unstage()?,
+        Action::Diff => app.git_diff(),
+        
+        // Overlays
+        Action::Preview => {
+            app.load_preview();
+            app.toggle_preview();
+        }
+        Action::Info => app.toggle_info(),
+        Action::Help => app.toggle_help(),
         
-        // Quit
-        KeyCode::Char('q') | KeyCode::Esc => app.quit(),
+        // Tabs and panes
+        Action::Miller => app.toggle_miller(),
+        Action::DualPane => app.toggle_dual()?,
+        Action::SwitchPane => app.switch_pane(),
+        Action::NewTab => app.new_tab()?,
+        Action::CloseTab => app.close_tab(),
+        Action::NextTab => app.next_tab(),
+        Action::PrevTab => app.prev_tab(),
+        Action::GoTab(n) => app.go_tab(n - 1),
         
-        _ => {}
+        Action::Quit => app.quit(),
     }
     
     Ok(())
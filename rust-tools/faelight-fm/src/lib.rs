@@ -4,4 +4,9 @@ pub mod zones;
 pub mod fs;
 pub mod error;
 pub mod git;
+pub mod health;
 pub mod daemon;
+pub mod config;
+pub mod actions;
+pub mod open;
+pub mod command;
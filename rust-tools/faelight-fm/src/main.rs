@@ -2,7 +2,7 @@ use std::io;
 use std::env;
 use std::path::PathBuf;
 use crossterm::{
-    event::{self, Event, KeyCode, EnableMouseCapture, DisableMouseCapture},
+    event::{self, Event, EnableMouseCapture, DisableMouseCapture},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
@@ -14,6 +14,7 @@ use faelight_fm::error::Result;
 // Import binary-only modules
 mod app;
 mod input;
+mod tab;
 mod ui;
 
 use app::AppState;
@@ -57,17 +58,13 @@ fn main() -> Result<()> {
 fn run_app<B: Backend>(terminal: &mut Terminal<B>, app: &mut AppState) -> Result<()> {
     loop {
         // Render
+        app.update_columns();
         terminal.draw(|f| ui::render(f, app))?;
         
         // Handle events
         if event::poll(std::time::Duration::from_millis(100))? {
             match event::read()? {
                 Event::Key(key) => {
-                    // Quick quit on ESC
-                    if key.code == KeyCode::Esc {
-                        app.quit();
-                    }
-                    
                     input::handle_key(key.code, app, terminal)?;
                 }
                 Event::Mouse(mouse) => {
@@ -77,6 +74,10 @@ fn run_app<B: Backend>(terminal: &mut Terminal<B>, app: &mut AppState) -> Result
             }
         }
         
+        // Pick up changes pushed by the daemon
+        app.refresh_if_changed()?;
+        app.poll_operation()?;
+        
         // Exit if quit
         if !app.running {
             break;
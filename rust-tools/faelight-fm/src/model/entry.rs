@@ -18,6 +18,8 @@ pub struct FaelightEntry {
     pub is_symlink: bool,  // NEW!
     pub zone: Zone,
     pub health: HealthStatus,
+    /// Why it isn't healthy
+    pub health_reason: Option<String>,
     pub intent_info: Option<IntentInfo>,
     pub git_status: GitStatus,
 }
@@ -25,9 +27,7 @@ pub struct FaelightEntry {
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum HealthStatus {
     Ok,
-    #[allow(dead_code)]
     Warning,
-    #[allow(dead_code)]
     Error,
 }
 
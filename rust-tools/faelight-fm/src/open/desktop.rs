@@ -0,0 +1,256 @@
+//! XDG defaults: mimeapps.list and .desktop entries
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// An application from a .desktop file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DesktopEntry {
+    /// File name, e.g. "org.pwmt.zathura.desktop"
+    pub id: String,
+    pub name: String,
+    pub exec: String,
+    /// Needs a terminal to run in
+    pub terminal: bool,
+    pub mime_types: Vec<String>,
+}
+
+impl DesktopEntry {
+    /// Program and arguments to open `path`
+    pub fn command(&self, path: &Path) -> Option<(String, Vec<String>)> {
+        let mut words = expand_exec(&self.exec, &self.name, path).into_iter();
+        Some((words.next()?, words.collect()))
+    }
+}
+
+/// Desktop ids for `mime` from mimeapps.list: defaults first, then
+/// added associations, in the spec's lookup order
+pub fn associations(mime: &str) -> Vec<String> {
+    let mut files: Vec<PathBuf> = config_dirs().into_iter().map(|d| d.join("mimeapps.list")).collect();
+    files.extend(data_dirs().into_iter().map(|d| d.join("applications/mimeapps.list")));
+    
+    let mut defaults = Vec::new();
+    let mut added = Vec::new();
+    let mut removed = Vec::new();
+    for file in files {
+        let Ok(content) = fs::read_to_string(&file) else {
+            continue;
+        };
+        for (section, key, value) in ini_entries(&content) {
+            if key != mime {
+                continue;
+            }
+            let list = match section {
+                "Default Applications" => &mut defaults,
+                "Added Associations" => &mut added,
+                "Removed Associations" => &mut removed,
+                _ => continue,
+            };
+            list.extend(value.split(';').filter(|id| !id.is_empty()).map(String::from));
+        }
+    }
+    
+    let mut ids: Vec<String> = Vec::new();
+    for id in defaults.into_iter().chain(added) {
+        if !ids.contains(&id) && !removed.contains(&id) {
+            ids.push(id);
+        }
+    }
+    ids
+}
+
+/// The installed entry with this id, earlier data dirs winning
+pub fn find_entry(id: &str) -> Option<DesktopEntry> {
+    data_dirs()
+        .into_iter()
+        .find_map(|d| fs::read_to_string(d.join("applications").join(id)).ok())
+        .and_then(|content| parse_entry(id, &content))
+}
+
+/// Every installed application that says it handles `mime`
+pub fn entries_for(mime: &str) -> Vec<DesktopEntry> {
+    let mut entries: Vec<DesktopEntry> = Vec::new();
+    
+    for dir in data_dirs() {
+        let Ok(files) = fs::read_dir(dir.join("applications")) else {
+            continue;
+        };
+        for file in files.filter_map(|f| f.ok()) {
+            let id = file.file_name().to_string_lossy().to_string();
+            if !id.ends_with(".desktop") || entries.iter().any(|e| e.id == id) {
+                continue;
+            }
+            let entry = fs::read_to_string(file.path()).ok().and_then(|c| parse_entry(&id, &c));
+            if let Some(entry) = entry.filter(|e| e.mime_types.iter().any(|m| m == mime)) {
+                entries.push(entry);
+            }
+        }
+    }
+    
+    entries.sort_by(|a, b| a.name.cmp(&b.name));
+    entries
+}
+
+/// The [Desktop Entry] group of a .desktop file. Hidden entries and ones
+/// without Exec are skipped.
+fn parse_entry(id: &str, content: &str) -> Option<DesktopEntry> {
+    let mut entry = DesktopEntry {
+        id: id.to_string(),
+        name: id.trim_end_matches(".desktop").to_string(),
+        exec: String::new(),
+        terminal: false,
+        mime_types: Vec::new(),
+    };
+    
+    for (section, key, value) in ini_entries(content) {
+        if section != "Desktop Entry" {
+            continue;
+        }
+        match key {
+            "Name" => entry.name = value.to_string(),
+            "Exec" => entry.exec = value.to_string(),
+            "Terminal" => entry.terminal = value == "true",
+            "MimeType" => entry.mime_types = value.split(';').filter(|m| !m.is_empty()).map(String::from).collect(),
+            "Hidden" if value == "true" => return None,
+            _ => {}
+        }
+    }
+    
+    (!entry.exec.is_empty()).then_some(entry)
+}
+
+/// (section, key, value) for every key of an ini-style file
+fn ini_entries(content: &str) -> Vec<(&str, &str, &str)> {
+    let mut section = "";
+    let mut entries = Vec::new();
+    
+    for line in content.lines().map(str::trim) {
+        if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            section = name;
+        } else if let Some((key, value)) = line.split_once('=') {
+            if !line.starts_with('#') {
+                entries.push((section, key.trim(), value.trim()));
+            }
+        }
+    }
+    entries
+}
+
+/// Split an Exec line into words and fill in the field codes. The file
+/// is appended when the line has no %f/%u code.
+fn expand_exec(exec: &str, name: &str, path: &Path) -> Vec<String> {
+    let file = path.to_string_lossy().to_string();
+    let mut words = Vec::new();
+    let mut has_file = false;
+    
+    for word in split_exec(exec) {
+        match word.as_str() {
+            "%f" | "%F" | "%u" | "%U" => {
+                words.push(file.clone());
+                has_file = true;
+            }
+            // Icon, name and location codes, and deprecated ones
+            "%i" | "%k" | "%d" | "%D" | "%n" | "%N" | "%v" | "%m" => {}
+            _ => words.push(word.replace("%c", name).replace("%%", "%")),
+        }
+    }
+    
+    if !has_file && !words.is_empty() {
+        words.push(file);
+    }
+    words
+}
+
+/// Words of an Exec line, honoring double quotes and backslash escapes
+fn split_exec(exec: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut quoted = false;
+    let mut chars = exec.chars();
+    
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                quoted = !quoted;
+                in_word = true;
+            }
+            '\\' if quoted => word.extend(chars.next()),
+            c if c.is_whitespace() && !quoted => {
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+            }
+            c => {
+                word.push(c);
+                in_word = true;
+            }
+        }
+    }
+    if in_word {
+        words.push(word);
+    }
+    words
+}
+
+fn home() -> PathBuf {
+    std::env::var("HOME").map(PathBuf::from).unwrap_or_else(|_| PathBuf::from("/"))
+}
+
+/// A user directory from `var`, or its default under $HOME
+fn user_dir(var: &str, default: &str) -> PathBuf {
+    std::env::var(var)
+        .ok()
+        .filter(|d| !d.is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(|| home().join(default))
+}
+
+/// Directories from a colon-separated `var`, or `default`
+fn system_dirs(var: &str, default: &str) -> Vec<PathBuf> {
+    let dirs = std::env::var(var).ok().filter(|d| !d.is_empty());
+    dirs.as_deref().unwrap_or(default).split(':').map(PathBuf::from).collect()
+}
+
+fn config_dirs() -> Vec<PathBuf> {
+    let mut dirs = vec![user_dir("XDG_CONFIG_HOME", ".config")];
+    dirs.extend(system_dirs("XDG_CONFIG_DIRS", "/etc/xdg"));
+    dirs
+}
+
+fn data_dirs() -> Vec<PathBuf> {
+    let mut dirs = vec![user_dir("XDG_DATA_HOME", ".local/share")];
+    dirs.extend(system_dirs("XDG_DATA_DIRS", "/usr/local/share:/usr/share"));
+    dirs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_desktop_entry_command() {
+        let entry = parse_entry("viewer.desktop", "\
+[Desktop Entry]
+Name=Image Viewer
+Exec=imv --title \"%c: file\" %i %F
+MimeType=image/png;image/jpeg;
+
+[Desktop Action new]
+Name=New Window
+Exec=imv --new
+").unwrap();
+
+        assert_eq!(entry.name, "Image Viewer");
+        assert!(!entry.terminal);
+        assert_eq!(entry.mime_types, vec!["image/png", "image/jpeg"]);
+        
+        let (program, args) = entry.command(Path::new("/tmp/my cat.png")).unwrap();
+        assert_eq!(program, "imv");
+        assert_eq!(args, vec!["--title", "Image Viewer: file", "/tmp/my cat.png"]);
+        
+        // No field code: the file goes last
+        assert_eq!(expand_exec("vim", "Vim", Path::new("/a b")), vec!["vim", "/a b"]);
+        assert!(parse_entry("gone.desktop", "[Desktop Entry]\nExec=gone\nHidden=true").is_none());
+    }
+}
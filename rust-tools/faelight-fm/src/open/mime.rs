@@ -0,0 +1,231 @@
+//! MIME type from a file's first bytes, refined by its extension
+use std::fs::{self, File};
+use std::io::Read;
+use std::os::unix::fs::FileTypeExt;
+use std::path::Path;
+
+/// Bytes read for sniffing, enough to reach the tar header
+const SNIFF_LEN: u64 = 512;
+
+/// Signatures at the start of the file
+const MAGIC: &[(&[u8], &str)] = &[
+    (b"\x89PNG\r\n\x1a\n", "image/png"),
+    (b"\xff\xd8\xff", "image/jpeg"),
+    (b"GIF87a", "image/gif"),
+    (b"GIF89a", "image/gif"),
+    (b"%PDF-", "application/pdf"),
+    (b"PK\x03\x04", "application/zip"),
+    (b"\x1f\x8b", "application/gzip"),
+    (b"BZh", "application/x-bzip2"),
+    (b"\xfd7zXZ\x00", "application/x-xz"),
+    (b"7z\xbc\xaf\x27\x1c", "application/x-7z-compressed"),
+    (b"\x28\xb5\x2f\xfd", "application/zstd"),
+    (b"\x7fELF", "application/x-executable"),
+    (b"ID3", "audio/mpeg"),
+    (b"OggS", "audio/ogg"),
+    (b"fLaC", "audio/flac"),
+    (b"\x1a\x45\xdf\xa3", "video/x-matroska"),
+    (b"SQLite format 3\x00", "application/vnd.sqlite3"),
+];
+
+/// Containers whose contents the extension tells apart
+const FAMILIES: &[(&str, &[&str])] = &[
+    ("application/zip", &[
+        "application/epub+zip",
+        "application/java-archive",
+        "application/vnd.oasis.opendocument.text",
+        "application/vnd.oasis.opendocument.spreadsheet",
+        "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+        "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+    ]),
+    ("video/x-matroska", &["video/webm"]),
+    ("video/mp4", &["audio/mp4", "video/quicktime"]),
+];
+
+/// Types that are text even though they aren't text/*
+const TEXT_TYPES: &[&str] = &[
+    "application/json",
+    "application/toml",
+    "application/xml",
+    "application/yaml",
+    "application/x-shellscript",
+    "image/svg+xml",
+];
+
+/// "inode/..." for directories and special files, otherwise sniffed from
+/// the content
+pub fn detect(path: &Path) -> String {
+    if let Some(inode) = fs::metadata(path).ok().and_then(|m| inode_type(m.file_type())) {
+        return inode.to_string();
+    }
+    
+    let mut head = Vec::new();
+    if let Ok(file) = File::open(path) {
+        let _ = file.take(SNIFF_LEN).read_to_end(&mut head);
+    }
+    let ext = path.extension().map(|e| e.to_string_lossy().to_lowercase()).unwrap_or_default();
+    detect_bytes(&head, &ext).to_string()
+}
+
+/// Anything that isn't a regular file; opening a FIFO would block
+fn inode_type(file_type: fs::FileType) -> Option<&'static str> {
+    let mime = if file_type.is_dir() {
+        "inode/directory"
+    } else if file_type.is_fifo() {
+        "inode/fifo"
+    } else if file_type.is_char_device() {
+        "inode/chardevice"
+    } else if file_type.is_block_device() {
+        "inode/blockdevice"
+    } else if file_type.is_socket() {
+        "inode/socket"
+    } else {
+        return None;
+    };
+    Some(mime)
+}
+
+pub fn detect_bytes(head: &[u8], ext: &str) -> &'static str {
+    let by_ext = from_extension(ext);
+    
+    match sniff(head) {
+        Some(mime) => FAMILIES
+            .iter()
+            .find(|(container, _)| *container == mime)
+            .and_then(|(_, members)| by_ext.filter(|m| members.contains(m)))
+            .unwrap_or(mime),
+        None if looks_like_text(head) => by_ext.filter(|m| is_text(m)).unwrap_or("text/plain"),
+        None => by_ext.unwrap_or("application/octet-stream"),
+    }
+}
+
+pub fn is_text(mime: &str) -> bool {
+    mime.starts_with("text/") || TEXT_TYPES.contains(&mime)
+}
+
+fn sniff(head: &[u8]) -> Option<&'static str> {
+    if let Some((_, mime)) = MAGIC.iter().find(|(magic, _)| head.starts_with(magic)) {
+        return Some(mime);
+    }
+    
+    match (head.get(..4), head.get(4..8), head.get(8..12)) {
+        (Some(b"RIFF"), _, Some(b"WEBP")) => return Some("image/webp"),
+        (Some(b"RIFF"), _, Some(b"WAVE")) => return Some("audio/wav"),
+        (_, Some(b"ftyp"), _) => return Some("video/mp4"),
+        _ => {}
+    }
+    if head.get(257..262) == Some(b"ustar") {
+        return Some("application/x-tar");
+    }
+    
+    // Scripts without an extension
+    let first_line = head.split(|b| *b == b'\n').next()?;
+    let shebang = std::str::from_utf8(first_line.strip_prefix(b"#!")?).ok()?;
+    if shebang.contains("python") {
+        Some("text/x-python")
+    } else {
+        Some("application/x-shellscript")
+    }
+}
+
+/// No NUL bytes and valid UTF-8, allowing a character cut off at the end
+fn looks_like_text(head: &[u8]) -> bool {
+    if head.contains(&0) {
+        return false;
+    }
+    match std::str::from_utf8(head) {
+        Ok(_) => true,
+        Err(e) => e.error_len().is_none(),
+    }
+}
+
+fn from_extension(ext: &str) -> Option<&'static str> {
+    let mime = match ext {
+        "txt" | "log" | "conf" | "cfg" | "ini" => "text/plain",
+        "md" | "markdown" => "text/markdown",
+        "rs" => "text/rust",
+        "py" => "text/x-python",
+        "c" => "text/x-csrc",
+        "h" => "text/x-chdr",
+        "lua" => "text/x-lua",
+        "js" | "mjs" => "text/javascript",
+        "html" | "htm" => "text/html",
+        "css" => "text/css",
+        "csv" => "text/csv",
+        "sh" | "bash" | "zsh" => "application/x-shellscript",
+        "toml" => "application/toml",
+        "json" => "application/json",
+        "yaml" | "yml" => "application/yaml",
+        "xml" => "application/xml",
+        "svg" => "image/svg+xml",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "pdf" => "application/pdf",
+        "epub" => "application/epub+zip",
+        "jar" => "application/java-archive",
+        "odt" => "application/vnd.oasis.opendocument.text",
+        "ods" => "application/vnd.oasis.opendocument.spreadsheet",
+        "docx" => "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+        "xlsx" => "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+        "zip" => "application/zip",
+        "tar" => "application/x-tar",
+        "gz" => "application/gzip",
+        "xz" => "application/x-xz",
+        "zst" => "application/zstd",
+        "7z" => "application/x-7z-compressed",
+        "mp3" => "audio/mpeg",
+        "ogg" | "opus" => "audio/ogg",
+        "flac" => "audio/flac",
+        "wav" => "audio/wav",
+        "m4a" => "audio/mp4",
+        "mp4" => "video/mp4",
+        "mov" => "video/quicktime",
+        "mkv" => "video/x-matroska",
+        "webm" => "video/webm",
+        _ => return None,
+    };
+    Some(mime)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_detect_bytes() {
+        // Content wins over a wrong extension
+        assert_eq!(detect_bytes(b"\x89PNG\r\n\x1a\n....", "txt"), "image/png");
+        assert_eq!(detect_bytes(b"%PDF-1.7", ""), "application/pdf");
+        // ...but tells zip-based formats apart
+        assert_eq!(detect_bytes(b"PK\x03\x04rest", "epub"), "application/epub+zip");
+        assert_eq!(detect_bytes(b"PK\x03\x04rest", "png"), "application/zip");
+        assert_eq!(detect_bytes(b"\x1a\x45\xdf\xa3", "webm"), "video/webm");
+        
+        assert_eq!(detect_bytes(b"fn main() {}\n", "rs"), "text/rust");
+        assert_eq!(detect_bytes(b"plain words", "png"), "text/plain");
+        assert_eq!(detect_bytes(b"#!/usr/bin/env bash\necho", ""), "application/x-shellscript");
+        assert_eq!(detect_bytes(b"", "md"), "text/markdown");
+        assert_eq!(detect_bytes(b"\x00\x01\x02", "mp3"), "audio/mpeg");
+        assert_eq!(detect_bytes(b"\x00\x01\x02", ""), "application/octet-stream");
+        // A multi-byte character cut off by the sniff limit
+        assert_eq!(detect_bytes("caf\u{e9}".as_bytes().split_last().unwrap().1, ""), "text/plain");
+    }
+    
+    #[test]
+    fn test_detect_special_files() {
+        let dir = std::env::temp_dir().join(format!("faelight-fm-mime-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let fifo = dir.join("pipe.txt");
+        assert!(std::process::Command::new("mkfifo").arg(&fifo).status().unwrap().success());
+        
+        // Returns instead of blocking on a reader-less FIFO
+        assert_eq!(detect(&fifo), "inode/fifo");
+        assert_eq!(detect(Path::new("/dev/null")), "inode/chardevice");
+        assert_eq!(detect(&dir), "inode/directory");
+        
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
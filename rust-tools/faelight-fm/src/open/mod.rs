@@ -0,0 +1,107 @@
+//! What opens a file: [[open]] rules from fm.toml, then the XDG defaults
+pub mod desktop;
+pub mod mime;
+
+use std::path::Path;
+use serde::Deserialize;
+
+/// An [[open]] rule from fm.toml
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct OpenRule {
+    /// MIME type, or a pattern such as "image/*"
+    #[serde(default)]
+    pub mime: Option<String>,
+    /// Extensions, without the dot
+    #[serde(default)]
+    pub ext: Vec<String>,
+    /// Command line; "%f" is replaced by the file, otherwise it's appended
+    pub run: String,
+    /// Run inside this terminal, suspending the TUI, rather than detached
+    #[serde(default)]
+    pub terminal: bool,
+}
+
+impl OpenRule {
+    pub fn matches(&self, mime: &str, path: &Path) -> bool {
+        let by_mime = self.mime.as_deref().is_some_and(|pattern| match pattern.strip_suffix("/*") {
+            Some(kind) => mime.split('/').next() == Some(kind),
+            None => pattern == "*" || pattern == mime,
+        });
+        let ext = path.extension().map(|e| e.to_string_lossy().to_lowercase());
+        by_mime || ext.is_some_and(|ext| self.ext.iter().any(|e| e.to_lowercase() == ext))
+    }
+    
+    fn handler(&self, path: &Path) -> Option<Handler> {
+        let file = path.to_string_lossy().to_string();
+        let mut words: Vec<String> = self.run.split_whitespace().map(String::from).collect();
+        if words.iter().any(|w| w.contains("%f")) {
+            words = words.into_iter().map(|w| w.replace("%f", &file)).collect();
+        } else {
+            words.push(file);
+        }
+        
+        let mut words = words.into_iter();
+        let program = words.next()?;
+        let name = Path::new(&program).file_name()?.to_string_lossy().to_string();
+        Some(Handler { name, program, args: words.collect(), terminal: self.terminal })
+    }
+}
+
+/// One way of opening a file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Handler {
+    /// Shown in "open with", e.g. "zathura" or "Image Viewer"
+    pub name: String,
+    pub program: String,
+    pub args: Vec<String>,
+    /// Runs in the terminal, so the TUI has to step aside
+    pub terminal: bool,
+}
+
+/// Everything that can open `path`, best first: matching rules, the
+/// mimeapps.list defaults, the editor for text, then other installed
+/// applications that list the type
+pub fn handlers(path: &Path, mime: &str, rules: &[OpenRule], editor: (String, Vec<String>)) -> Vec<Handler> {
+    let mut handlers: Vec<Handler> = rules
+        .iter()
+        .filter(|rule| rule.matches(mime, path))
+        .filter_map(|rule| rule.handler(path))
+        .collect();
+    
+    // Text subtypes fall back to what opens plain text
+    let mut types = vec![mime];
+    if mime::is_text(mime) && mime != "text/plain" {
+        types.push("text/plain");
+    }
+    
+    let from_entry = |entry: &desktop::DesktopEntry| {
+        let (program, args) = entry.command(path)?;
+        Some(Handler { name: entry.name.clone(), program, args, terminal: entry.terminal })
+    };
+    
+    for mime in &types {
+        let defaults = desktop::associations(mime).into_iter().filter_map(|id| desktop::find_entry(&id));
+        handlers.extend(defaults.filter_map(|e| from_entry(&e)));
+    }
+    
+    if mime::is_text(mime) {
+        let (program, mut args) = editor;
+        args.push(path.to_string_lossy().to_string());
+        handlers.push(Handler { name: program.clone(), program, args, terminal: true });
+    }
+    
+    for mime in &types {
+        handlers.extend(desktop::entries_for(mime).iter().filter_map(from_entry));
+    }
+    
+    // The same program can come from several places
+    let mut seen = Vec::new();
+    handlers.retain(|h| {
+        let key = (h.program.clone(), h.args.clone());
+        let new = !seen.contains(&key);
+        seen.push(key);
+        new
+    });
+    handlers
+}
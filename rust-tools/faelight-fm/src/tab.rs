@@ -0,0 +1,95 @@
+//! A tab: one directory, with its own selection and search
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+use faelight_fm::daemon::WatchSubscription;
+use faelight_fm::model::{FaelightEntry, HealthStatus, Zone};
+use faelight_fm::zones;
+
+pub struct Tab {
+    pub cwd: PathBuf,
+    pub zone: Zone,
+    pub entries: Vec<FaelightEntry>,
+    pub filtered_entries: Vec<FaelightEntry>,  // Hidden files, health and search applied
+    pub selected: usize,
+    pub search_mode: bool,
+    pub search_query: String,
+    pub visual_anchor: Option<usize>,  // Start of the range being marked
+    pub marked: BTreeSet<PathBuf>,  // Marked in this tab, for bulk operations
+    pub(crate) watch: Option<WatchSubscription>,  // Live updates for cwd
+}
+
+impl Tab {
+    /// A tab in `cwd`, empty until it's reloaded
+    pub fn new(cwd: PathBuf, watch: Option<WatchSubscription>) -> Self {
+        Self {
+            zone: zones::classify(&cwd),
+            cwd,
+            entries: Vec::new(),
+            filtered_entries: Vec::new(),
+            selected: 0,
+            search_mode: false,
+            search_query: String::new(),
+            visual_anchor: None,
+            marked: BTreeSet::new(),
+            watch,
+        }
+    }
+    
+    /// Directory name, for the tab bar
+    pub fn title(&self) -> String {
+        self.cwd
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| self.cwd.display().to_string())
+    }
+    
+    /// Move to `path`, dropping the search. Entries are loaded by the caller.
+    pub fn set_cwd(&mut self, path: PathBuf) {
+        self.zone = zones::classify(&path);
+        self.cwd = path;
+        self.search_mode = false;
+        self.search_query.clear();
+    }
+    
+    pub fn apply_filter(&mut self, show_hidden: bool, unhealthy_only: bool) {
+        let query = self.search_query.to_lowercase();
+        self.filtered_entries = self.entries
+            .iter()
+            .filter(|e| show_hidden || !e.name.starts_with('.'))
+            .filter(|e| !unhealthy_only || e.health != HealthStatus::Ok)
+            .filter(|e| query.is_empty() || e.name.to_lowercase().contains(&query))
+            .cloned()
+            .collect();
+        
+        // Keep selection valid
+        if self.selected >= self.filtered_entries.len() && !self.filtered_entries.is_empty() {
+            self.selected = self.filtered_entries.len() - 1;
+        }
+    }
+    
+    pub fn select_prev(&mut self) {
+        if self.selected > 0 {
+            self.selected -= 1;
+        }
+    }
+    
+    pub fn select_next(&mut self) {
+        if self.selected + 1 < self.filtered_entries.len() {
+            self.selected += 1;
+        }
+    }
+    
+    pub fn selected_entry(&self) -> Option<&FaelightEntry> {
+        self.filtered_entries.get(self.selected)
+    }
+    
+    /// Marked, or inside the range being marked
+    pub fn is_marked(&self, index: usize) -> bool {
+        if let Some(anchor) = self.visual_anchor {
+            if (anchor.min(self.selected)..=anchor.max(self.selected)).contains(&index) {
+                return true;
+            }
+        }
+        self.filtered_entries.get(index).is_some_and(|e| self.marked.contains(&e.path))
+    }
+}
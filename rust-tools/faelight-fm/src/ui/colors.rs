@@ -13,16 +13,15 @@ impl FaelightColors {
     // Faelight Forest accent colors
     pub const ACCENT_GREEN: Color = Color::Rgb(163, 227, 107);
     pub const ACCENT_BLUE: Color = Color::Rgb(107, 163, 227);
-    #[allow(dead_code)]
     pub const ACCENT_ORANGE: Color = Color::Rgb(227, 163, 107);
     
     // Text colors
     pub const TEXT_BRIGHT: Color = Color::Rgb(218, 224, 215);
     pub const TEXT_DIM: Color = Color::Rgb(119, 127, 111);
-    #[allow(dead_code)]
     pub const TEXT_DIMMER: Color = Color::Rgb(80, 85, 75);
     
     // Status colors
+    #[allow(dead_code)]
     pub const LOCKED: Color = Color::Rgb(200, 100, 100);
     #[allow(dead_code)]
     pub const MODIFIED: Color = Color::Rgb(227, 163, 107);
@@ -38,15 +37,11 @@ impl FaelightColors {
     // Symlink color
     pub const SYMLINK: Color = Color::Rgb(227, 200, 107);  // Yellow/gold for links
     
-    pub fn zone_color(zone: Zone) -> Color {
-        match zone {
-            Zone::Core => Self::LOCKED,
-            Zone::Workspace => Color::Rgb(227, 163, 107),
-            Zone::Src => Self::ACCENT_GREEN,
-            Zone::Project => Self::ACCENT_BLUE,
-            Zone::Archive => Color::Rgb(180, 150, 200),
-            Zone::Scratch => Color::Rgb(150, 150, 80),
-        }
+    /// Color from zones.toml, dim when the zone has none
+    pub fn zone_color(zone: &Zone) -> Color {
+        zone.rgb()
+            .map(|(r, g, b)| Color::Rgb(r, g, b))
+            .unwrap_or(Self::TEXT_DIM)
     }
     
     pub fn directory_style(selected: bool) -> Style {
@@ -0,0 +1,58 @@
+//! Parent and preview columns of the miller view
+use ratatui::prelude::*;
+use ratatui::widgets::{Block, Borders, List, ListItem, Paragraph, Widget};
+use crate::app::AppState;
+use super::colors::FaelightColors;
+use super::preview::highlight_line;
+
+/// The parent directory, with the current one highlighted
+pub fn render_parent(area: Rect, buf: &mut Buffer, app: &AppState) {
+    let cwd = &app.tab().cwd;
+    let items: Vec<ListItem> = app.columns.parent
+        .iter()
+        .map(|entry| {
+            let is_current = entry.path == *cwd;
+            let style = if entry.is_dir {
+                FaelightColors::directory_style(is_current)
+            } else {
+                FaelightColors::file_style(false).fg(FaelightColors::TEXT_DIM)
+            };
+            ListItem::new(format!("{} {}", entry.icon(), entry.name)).style(style)
+        })
+        .collect();
+    
+    let title = cwd.parent()
+        .and_then(|p| p.file_name())
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| "/".to_string());
+    
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .title(title)
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(FaelightColors::TEXT_DIM))
+        );
+    
+    Widget::render(list, area, buf);
+}
+
+/// The selected directory's entries, or the selected file's first lines
+pub fn render_preview(area: Rect, buf: &mut Buffer, app: &AppState) {
+    let lines: Vec<Line> = app.columns.preview
+        .iter()
+        .enumerate()
+        .map(|(i, line)| Line::from(highlight_line(line, i, &app.columns.preview_highlights)))
+        .collect();
+    
+    let title = app.selected_entry().map(|e| e.name.clone()).unwrap_or_default();
+    let paragraph = Paragraph::new(lines)
+        .block(
+            Block::default()
+                .title(title)
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(FaelightColors::TEXT_DIM))
+        );
+    
+    Widget::render(paragraph, area, buf);
+}
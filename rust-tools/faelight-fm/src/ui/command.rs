@@ -0,0 +1,34 @@
+//! The `:` command line, in the bottom row
+use ratatui::prelude::*;
+use ratatui::widgets::Paragraph;
+use crate::app::{AppState, Mode};
+use super::colors::FaelightColors;
+
+pub fn render(area: Rect, buf: &mut Buffer, app: &AppState) {
+    if app.mode != Mode::Command {
+        return;
+    }
+    let line = &app.command_line;
+    
+    let mut spans = vec![
+        Span::styled(line.prompt.as_str(), Style::default().fg(FaelightColors::ACCENT_BLUE).bold()),
+        Span::styled(line.input.as_str(), Style::default().fg(FaelightColors::TEXT_BRIGHT)),
+        Span::styled("█", Style::default().fg(FaelightColors::TEXT_DIM)),
+    ];
+    
+    // Names left after the last Tab
+    if line.completions.len() > 1 {
+        let names: Vec<&str> = line.completions
+            .iter()
+            .map(|c| c.rsplit(' ').next().unwrap_or(c))
+            .collect();
+        spans.push(Span::styled(
+            format!("   {}", names.join("  ")),
+            Style::default().fg(FaelightColors::TEXT_DIM).italic(),
+        ));
+    }
+    
+    Paragraph::new(Line::from(spans))
+        .style(Style::default().bg(FaelightColors::BG_DARK))
+        .render(area, buf);
+}
@@ -0,0 +1,54 @@
+//! Diff viewer overlay
+use ratatui::prelude::*;
+use ratatui::widgets::{Block, Borders, Clear, Paragraph};
+use crate::app::DiffView;
+use super::colors::FaelightColors;
+use super::help::centered_rect;
+
+pub fn render(area: Rect, buf: &mut Buffer, view: &DiffView) {
+    let popup_area = centered_rect(80, 80, area);
+    Clear.render(popup_area, buf);
+    
+    let block = Block::default()
+        .title(format!(" DIFF {} ", view.name))
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(FaelightColors::ACCENT_BLUE))
+        .style(Style::default().bg(FaelightColors::BG_DARK));
+    let inner = block.inner(popup_area);
+    block.render(popup_area, buf);
+    
+    let [text_area, footer_area] = Layout::vertical([
+        Constraint::Min(1),
+        Constraint::Length(1),
+    ]).areas(inner);
+    
+    let lines: Vec<Line> = view.lines
+        .iter()
+        .skip(view.scroll)
+        .take(text_area.height as usize)
+        .map(|line| Line::from(Span::styled(line.as_str(), line_style(line))))
+        .collect();
+    Paragraph::new(lines).render(text_area, buf);
+    
+    let shown = (view.scroll + text_area.height as usize).min(view.lines.len());
+    Paragraph::new(format!("{}/{} lines · j/k scroll · any other key closes", shown, view.lines.len()))
+        .style(Style::default().fg(FaelightColors::TEXT_DIM).italic())
+        .render(footer_area, buf);
+}
+
+fn line_style(line: &str) -> Style {
+    let style = Style::default();
+    if line.ends_with("changes:") && !line.starts_with([' ', '+', '-']) {
+        style.fg(FaelightColors::ACCENT_GREEN).bold()
+    } else if line.starts_with("+++") || line.starts_with("---") || line.starts_with("diff ") {
+        style.fg(FaelightColors::TEXT_BRIGHT).bold()
+    } else if line.starts_with("@@") {
+        style.fg(FaelightColors::ACCENT_BLUE)
+    } else if line.starts_with('+') {
+        style.fg(Color::Green)
+    } else if line.starts_with('-') {
+        style.fg(Color::Red)
+    } else {
+        style.fg(FaelightColors::TEXT_DIM)
+    }
+}
@@ -2,15 +2,20 @@ use ratatui::prelude::*;
 use ratatui::widgets::{Block, Borders, List, ListItem, Widget};
 use crate::app::AppState;
 use super::colors::FaelightColors;
-use faelight_fm::git::GitStatus;
+use faelight_fm::config::View;
+use faelight_fm::git::GitState;
+use faelight_fm::model::HealthStatus;
 
-pub fn render(area: Rect, buf: &mut Buffer, app: &AppState) {
-    let items: Vec<ListItem> = app
+/// The entries of tab `index`
+pub fn render(area: Rect, buf: &mut Buffer, app: &AppState, index: usize) {
+    let tab = &app.tabs[index];
+    let items: Vec<ListItem> = tab
         .filtered_entries
         .iter()
         .enumerate()
         .map(|(i, entry)| {
-            let is_selected = i == app.selected;
+            let is_selected = i == tab.selected;
+            let is_marked = tab.is_marked(i);
             
             // Symlinks get special color treatment
             let base_style = if entry.is_symlink {
@@ -33,21 +38,24 @@ pub fn render(area: Rect, buf: &mut Buffer, app: &AppState) {
             
             let zone_tag = format!("[Z:{}]", entry.zone.short_label());
             let mut spans = vec![
+                // Mark for bulk operations
+                Span::styled(
+                    if is_marked { "▌" } else { " " },
+                    Style::default().fg(FaelightColors::ACCENT_ORANGE)
+                ),
                 Span::raw(format!("{} ", entry.icon())),
-                // Git status marker
+                // Git status marker: staged, then unstaged
                 Span::styled(
-                    entry.git_status.marker(),
-                    Style::default().fg(match entry.git_status {
-                        GitStatus::Modified => Color::Yellow,
-                        GitStatus::Added => Color::Green,
-                        GitStatus::Deleted => Color::Red,
-                        GitStatus::Untracked => FaelightColors::TEXT_DIM,
-                        GitStatus::Clean => FaelightColors::TEXT_DIM,
-                    })
+                    entry.git_status.index.code().to_string(),
+                    git_style(entry.git_status.index, true)
+                ),
+                Span::styled(
+                    entry.git_status.worktree.code().to_string(),
+                    git_style(entry.git_status.worktree, false)
                 ),
                 Span::styled(
                     format!("{:<30} ", entry.name),
-                    base_style
+                    if is_marked { base_style.fg(FaelightColors::ACCENT_ORANGE) } else { base_style }
                 ),
                 Span::raw(format!("{:<12} ", zone_tag)),
             ];
@@ -68,19 +76,51 @@ pub fn render(area: Rect, buf: &mut Buffer, app: &AppState) {
                 spans.push(Span::raw(format!("{:<12} ", "")));
             }
             
-            spans.push(Span::raw(entry.health.badge()));
+            spans.push(Span::styled(entry.health.badge(), health_style(entry.health)));
             
             ListItem::new(Line::from(spans))
         })
         .collect();
     
+    // Dual panes show their path, the focused one in green
+    let (mut title, border) = if app.view == View::Dual {
+        let color = if index == app.active { FaelightColors::ACCENT_GREEN } else { FaelightColors::TEXT_DIM };
+        (format!(" {} ", tab.cwd.display()), color)
+    } else {
+        ("FILE LIST".to_string(), FaelightColors::TEXT_DIM)
+    };
+    if app.unhealthy_only {
+        title = format!("{} · unhealthy only ", title.trim_end());
+    }
+    
     let list = List::new(items)
         .block(
             Block::default()
-                .title("FILE LIST")
+                .title(title)
                 .borders(Borders::ALL)
-                .border_style(Style::default().fg(FaelightColors::TEXT_DIM))
+                .border_style(Style::default().fg(border))
         );
     
     Widget::render(list, area, buf);
 }
+
+fn health_style(health: HealthStatus) -> Style {
+    match health {
+        HealthStatus::Ok => Style::default(),
+        HealthStatus::Warning => Style::default().fg(Color::Yellow),
+        HealthStatus::Error => Style::default().fg(Color::Red).bold(),
+    }
+}
+
+/// Staged changes are green, unstaged ones by kind
+fn git_style(state: GitState, staged: bool) -> Style {
+    let style = Style::default();
+    match state {
+        GitState::Conflicted => style.fg(Color::Red).bold(),
+        GitState::Untracked => style.fg(FaelightColors::TEXT_DIM),
+        GitState::Ignored => style.fg(FaelightColors::TEXT_DIMMER),
+        _ if staged => style.fg(Color::Green),
+        GitState::Deleted => style.fg(Color::Red),
+        _ => style.fg(Color::Yellow),
+    }
+}
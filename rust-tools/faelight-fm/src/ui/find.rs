@@ -0,0 +1,49 @@
+//! `:find` results overlay
+use ratatui::prelude::*;
+use ratatui::widgets::{Block, Borders, Clear, List, ListItem, Paragraph};
+use crate::app::FindView;
+use super::colors::FaelightColors;
+use super::help::centered_rect;
+
+pub fn render(area: Rect, buf: &mut Buffer, view: &FindView) {
+    let popup_area = centered_rect(60, 60, area);
+    Clear.render(popup_area, buf);
+    
+    let block = Block::default()
+        .title(format!(" FIND '{}' ", view.query))
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(FaelightColors::ACCENT_BLUE))
+        .style(Style::default().bg(FaelightColors::BG_DARK));
+    let inner = block.inner(popup_area);
+    block.render(popup_area, buf);
+    
+    let [list_area, footer_area] = Layout::vertical([
+        Constraint::Min(1),
+        Constraint::Length(1),
+    ]).areas(inner);
+    
+    // Keep the selection on screen
+    let height = list_area.height as usize;
+    let skip = (view.selected + 1).saturating_sub(height);
+    
+    let items: Vec<ListItem> = view.results
+        .iter()
+        .enumerate()
+        .skip(skip)
+        .map(|(i, path)| {
+            let style = if i == view.selected {
+                Style::default().fg(FaelightColors::TEXT_BRIGHT).bg(FaelightColors::BG_SELECTED).bold()
+            } else {
+                Style::default().fg(FaelightColors::TEXT_BRIGHT)
+            };
+            let slash = if view.root.join(path).is_dir() { "/" } else { "" };
+            ListItem::new(Span::styled(format!("{}{}", path.display(), slash), style))
+        })
+        .collect();
+    
+    Widget::render(List::new(items), list_area, buf);
+    
+    Paragraph::new(format!("{} found · ⏎ go to · Esc close", view.results.len()))
+        .style(Style::default().fg(FaelightColors::TEXT_DIM).italic())
+        .render(footer_area, buf);
+}
@@ -1,64 +1,118 @@
 use ratatui::prelude::*;
 use ratatui::widgets::{Block, Borders, Paragraph, Clear};
+use faelight_zone::Zones;
+use faelight_fm::config::{Action, Keymap};
 use super::colors::FaelightColors;
 
-pub fn render(area: Rect, buf: &mut Buffer) {
+pub fn render(area: Rect, buf: &mut Buffer, keymap: &Keymap) {
     // Center the help box
-    let popup_area = centered_rect(60, 70, area);
+    let popup_area = centered_rect(80, 80, area);
     
     // Clear the background
     Clear.render(popup_area, buf);
     
-    let help_text = vec![
+    let block = Block::default()
+        .title(" HELP ")
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(FaelightColors::ACCENT_GREEN))
+        .style(Style::default().bg(FaelightColors::BG_DARK));
+    let inner = block.inner(popup_area);
+    block.render(popup_area, buf);
+    
+    let mut left = vec![
         Line::from(vec![
             Span::styled("KEYBOARD SHORTCUTS", Style::default().fg(FaelightColors::ACCENT_GREEN).bold()),
         ]),
+    ];
+    
+    left.push(Line::from(""));
+    left.push(heading("Navigation:"));
+    left.extend(action_lines(keymap, "Navigation:"));
+    
+    left.push(Line::from(""));
+    left.push(heading("Zone Jumping:"));
+    left.extend(zone_lines(keymap));
+    
+    left.push(Line::from(""));
+    left.push(heading("Tabs & Panes:"));
+    left.extend(action_lines(keymap, "Tabs & Panes:"));
+    left.extend(tab_line(keymap));
+    
+    // Too many shortcuts for one column
+    let mut right = vec![Line::from(""), Line::from("")];
+    right.push(heading("Files:"));
+    right.extend(action_lines(keymap, "Files:"));
+    
+    right.push(Line::from(""));
+    right.push(heading("Git:"));
+    right.extend(action_lines(keymap, "Git:"));
+    
+    right.push(Line::from(""));
+    right.push(heading("Other:"));
+    right.extend(action_lines(keymap, "Other:"));
+    
+    right.extend([
         Line::from(""),
         Line::from(vec![
-            Span::styled("Navigation:", Style::default().fg(FaelightColors::ACCENT_BLUE).bold()),
-        ]),
-        Line::from("  j / ↓      - Move down"),
-        Line::from("  k / ↑      - Move up"),
-        Line::from("  l / → / ⏎  - Enter directory"),
-        Line::from("  h / ←      - Go to parent"),
-        Line::from(""),
-        Line::from(vec![
-            Span::styled("Zone Jumping:", Style::default().fg(FaelightColors::ACCENT_BLUE).bold()),
-        ]),
-        Line::from("  0 - Core       (~/0-core)"),
-        Line::from("  1 - Workspace  (~/0-core/rust-tools)"),
-        Line::from("  2 - Src        (~/1-src)"),
-        Line::from("  3 - Project    (~/2-projects)"),
-        Line::from("  4 - Archive    (~/3-archive)"),
-        Line::from("  5 - Scratch    (temporary)"),
-        Line::from(""),
-        Line::from(vec![
-            Span::styled("Other:", Style::default().fg(FaelightColors::ACCENT_BLUE).bold()),
+            Span::styled("Keys are set in ~/.config/faelight/fm.toml", Style::default().fg(FaelightColors::TEXT_DIM).italic()),
         ]),
-        Line::from("  ?   - Toggle this help"),
-        Line::from("  i   - File information"),
-        Line::from("  /   - Search/filter files"),
-        Line::from("  q   - Quit"),
-        Line::from("  Esc - Quit"),
-        Line::from(""),
         Line::from(vec![
             Span::styled("Press any key to close", Style::default().fg(FaelightColors::TEXT_DIM).italic()),
         ]),
-    ];
+    ]);
     
-    let paragraph = Paragraph::new(help_text)
-        .block(
-            Block::default()
-                .title(" HELP ")
-                .borders(Borders::ALL)
-                .border_style(Style::default().fg(FaelightColors::ACCENT_GREEN))
-        )
-        .style(Style::default().bg(FaelightColors::BG_DARK));
-    
-    paragraph.render(popup_area, buf);
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(inner);
+    Paragraph::new(left).render(columns[0], buf);
+    Paragraph::new(right).render(columns[1], buf);
+}
+
+fn heading(title: &'static str) -> Line<'static> {
+    Line::from(vec![
+        Span::styled(title, Style::default().fg(FaelightColors::ACCENT_BLUE).bold()),
+    ])
+}
+
+/// One line per bound action in a group, e.g. "  j / ↓      - Move down"
+fn action_lines(keymap: &Keymap, group: &str) -> Vec<Line<'static>> {
+    Action::NAMED
+        .iter()
+        .filter(|action| action.group() == group)
+        .filter_map(|action| {
+            let keys = keymap.keys_for(action);
+            (!keys.is_empty()).then(|| Line::from(format!("  {:<10} - {}", keys, action.description())))
+        })
+        .collect()
+}
+
+/// One line per zone that has a jump key
+fn zone_lines(keymap: &Keymap) -> Vec<Line<'static>> {
+    Zones::global()
+        .all()
+        .iter()
+        .filter_map(|zone| {
+            let keys = keymap.keys_for(&Action::JumpZone(zone.id.clone()));
+            if keys.is_empty() {
+                return None;
+            }
+            let place = zone.root.as_deref().unwrap_or("everything else");
+            Some(Line::from(format!("  {:<10} - {} {:<6} ({})", keys, zone.icon(), zone.short_label(), place)))
+        })
+        .collect()
+}
+
+/// Digits that go to tabs, e.g. "  6 7 8 9    - Go to tab"
+fn tab_line(keymap: &Keymap) -> Option<Line<'static>> {
+    let keys: Vec<String> = (1..=9)
+        .map(|n| keymap.keys_for(&Action::GoTab(n)))
+        .filter(|k| !k.is_empty())
+        .collect();
+    (!keys.is_empty()).then(|| Line::from(format!("  {:<10} - {}", keys.join(" "), Action::GoTab(1).description())))
 }
 
-fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
+pub fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
     let popup_layout = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
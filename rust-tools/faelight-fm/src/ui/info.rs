@@ -1,5 +1,6 @@
 use ratatui::prelude::*;
-use ratatui::widgets::{Block, Borders, Paragraph, Clear};
+use ratatui::widgets::{Block, Borders, Paragraph, Clear, Wrap};
+use faelight_fm::model::HealthStatus;
 use crate::app::AppState;
 use super::colors::FaelightColors;
 use std::fs;
@@ -34,6 +35,23 @@ pub fn render(area: Rect, buf: &mut Buffer, app: &AppState) {
             "File"
         };
         
+        let git = entry.git_status;
+        let git_text = if git.is_clean() {
+            "Clean".to_string()
+        } else if git.index == git.worktree {
+            git.index.label().to_string()
+        } else {
+            format!("staged {} · unstaged {}", git.index.label(), git.worktree.label())
+        };
+        
+        let (health_text, health_color) = match &entry.health_reason {
+            Some(reason) => {
+                let color = if entry.health == HealthStatus::Error { Color::Red } else { Color::Yellow };
+                (format!("{} {}", entry.health.badge(), reason), color)
+            }
+            None => (format!("{} Healthy", entry.health.badge()), FaelightColors::ACCENT_GREEN),
+        };
+        
         let intent_text = if let Some(ref info) = entry.intent_info {
             let status = match info.status {
                 faelight_fm::intent::IntentStatus::Complete => "COMPLETE",
@@ -63,13 +81,21 @@ pub fn render(area: Rect, buf: &mut Buffer, app: &AppState) {
                 Span::styled("Zone:     ", Style::default().fg(FaelightColors::TEXT_DIM)),
                 Span::styled(
                     format!("{} ({})", entry.zone.short_label(), entry.zone.icon()),
-                    Style::default().fg(FaelightColors::zone_color(entry.zone))
+                    Style::default().fg(FaelightColors::zone_color(&entry.zone))
                 ),
             ]),
             Line::from(vec![
                 Span::styled("Intent:   ", Style::default().fg(FaelightColors::TEXT_DIM)),
                 Span::styled(intent_text.clone(), Style::default().fg(FaelightColors::TEXT_BRIGHT)),
             ]),
+            Line::from(vec![
+                Span::styled("Git:      ", Style::default().fg(FaelightColors::TEXT_DIM)),
+                Span::styled(git_text, Style::default().fg(FaelightColors::TEXT_BRIGHT)),
+            ]),
+            Line::from(vec![
+                Span::styled("Health:   ", Style::default().fg(FaelightColors::TEXT_DIM)),
+                Span::styled(health_text, Style::default().fg(health_color)),
+            ]),
             Line::from(vec![
                 Span::styled("Size:     ", Style::default().fg(FaelightColors::TEXT_DIM)),
                 Span::styled(size, Style::default().fg(FaelightColors::TEXT_BRIGHT)),
@@ -112,7 +138,8 @@ pub fn render(area: Rect, buf: &mut Buffer, app: &AppState) {
                 .borders(Borders::ALL)
                 .border_style(Style::default().fg(FaelightColors::ACCENT_BLUE))
         )
-        .style(Style::default().bg(FaelightColors::BG_DARK));
+        .style(Style::default().bg(FaelightColors::BG_DARK))
+        .wrap(Wrap { trim: false });
     
     paragraph.render(popup_area, buf);
 }
@@ -137,7 +164,7 @@ fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
         .split(popup_layout[1])[1]
 }
 
-fn format_size(bytes: u64) -> String {
+pub(crate) fn format_size(bytes: u64) -> String {
     const KB: u64 = 1024;
     const MB: u64 = KB * 1024;
     const GB: u64 = MB * 1024;
@@ -1,6 +1,6 @@
 use ratatui::prelude::*;
 
-pub fn create_layout(area: Rect) -> (Rect, Rect, Rect, Rect, Rect) {
+pub fn create_layout(area: Rect) -> (Rect, Rect, Rect, Rect, Rect, Rect) {
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
@@ -20,5 +20,29 @@ pub fn create_layout(area: Rect) -> (Rect, Rect, Rect, Rect, Rect) {
         ])
         .split(chunks[2]);
     
-    (chunks[0], chunks[1], main_chunks[0], main_chunks[1], chunks[3])
+    (chunks[0], chunks[1], main_chunks[0], main_chunks[1], chunks[3], chunks[4])
+}
+
+/// Left and right panes
+pub fn split_dual(area: Rect) -> (Rect, Rect) {
+    let panes = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(area);
+    
+    (panes[0], panes[1])
+}
+
+/// Parent, current and preview columns
+pub fn split_miller(area: Rect) -> (Rect, Rect, Rect) {
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage(20),
+            Constraint::Percentage(45),
+            Constraint::Percentage(35),
+        ])
+        .split(area);
+    
+    (columns[0], columns[1], columns[2])
 }
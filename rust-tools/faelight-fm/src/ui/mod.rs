@@ -7,43 +7,61 @@ pub mod help;
 pub mod info;
 pub mod search;
 pub mod colors;
+pub mod trash;
+pub mod tabs;
+pub mod columns;
+pub mod open_with;
+pub mod command;
+pub mod find;
+pub mod diff;
 
 use ratatui::prelude::*;
-use ratatui::widgets::Paragraph;
+use faelight_fm::config::View;
 use crate::app::AppState;
-use colors::FaelightColors;
 
 pub fn render(frame: &mut Frame, app: &AppState) {
-    let (topbar_area, path_area, zones_area, filelist_area, status_area) = 
+    let (topbar_area, path_area, zones_area, filelist_area, status_area, command_area) = 
         layout::create_layout(frame.area());
     
     // Top bar
     topbar::render(topbar_area, frame.buffer_mut(), app);
     
-    // Path bar (or search bar if searching)
-    if app.search_mode {
+    // Path bar with tabs (or search bar if searching)
+    if app.tab().search_mode {
         search::render(path_area, frame.buffer_mut(), app);
     } else {
-        let path_text = format!("PATH: {}", app.cwd.display());
-        let path_paragraph = Paragraph::new(path_text)
-            .style(Style::default()
-                .bg(FaelightColors::BG_DARK)
-                .fg(FaelightColors::TEXT_BRIGHT));
-        frame.render_widget(path_paragraph, path_area);
+        tabs::render(path_area, frame.buffer_mut(), app);
     }
     
     // Zones panel
-    zones::render(zones_area, frame.buffer_mut(), app.zone);
+    zones::render(zones_area, frame.buffer_mut(), &app.tab().zone);
     
-    // File list (filtered)
-    filelist::render(filelist_area, frame.buffer_mut(), app);
+    // File list (filtered), in the chosen view
+    match (app.view, app.other) {
+        (View::Dual, Some(other)) => {
+            let (left, right) = layout::split_dual(filelist_area);
+            let (left_tab, right_tab) = if app.active < other { (app.active, other) } else { (other, app.active) };
+            filelist::render(left, frame.buffer_mut(), app, left_tab);
+            filelist::render(right, frame.buffer_mut(), app, right_tab);
+        }
+        (View::Miller, _) => {
+            let (parent, current, preview) = layout::split_miller(filelist_area);
+            columns::render_parent(parent, frame.buffer_mut(), app);
+            filelist::render(current, frame.buffer_mut(), app, app.active);
+            columns::render_preview(preview, frame.buffer_mut(), app);
+        }
+        _ => filelist::render(filelist_area, frame.buffer_mut(), app, app.active),
+    }
     
     // Status bar
     status::render(status_area, frame.buffer_mut(), app);
     
+    // Command line
+    command::render(command_area, frame.buffer_mut(), app);
+    
     // Overlays (render on top)
     if app.help_visible {
-        help::render(frame.area(), frame.buffer_mut());
+        help::render(frame.area(), frame.buffer_mut(), &app.config.keymap);
     }
     
     if app.info_visible {
@@ -53,6 +71,22 @@ pub fn render(frame: &mut Frame, app: &AppState) {
     if app.preview_visible {
         preview::render(frame.area(), frame.buffer_mut(), app);
     }
+    
+    if let Some(ref view) = app.open_with {
+        open_with::render(frame.area(), frame.buffer_mut(), view);
+    }
+    
+    if let Some(ref view) = app.diff_view {
+        diff::render(frame.area(), frame.buffer_mut(), view);
+    }
+    
+    if let Some(ref view) = app.find_view {
+        find::render(frame.area(), frame.buffer_mut(), view);
+    }
+    
+    if let Some(ref view) = app.trash_view {
+        trash::render(frame.area(), frame.buffer_mut(), view, &app.config.keymap);
+    }
 }
 pub mod preview;
-    
+
@@ -0,0 +1,60 @@
+//! "Open with" chooser overlay
+use ratatui::prelude::*;
+use ratatui::widgets::{Block, Borders, Clear, List, ListItem, Paragraph};
+use crate::app::OpenWithView;
+use super::colors::FaelightColors;
+use super::help::centered_rect;
+
+pub fn render(area: Rect, buf: &mut Buffer, view: &OpenWithView) {
+    let popup_area = centered_rect(50, 50, area);
+    Clear.render(popup_area, buf);
+    
+    let name = view.path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    let block = Block::default()
+        .title(format!(" OPEN {} WITH ", name))
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(FaelightColors::ACCENT_BLUE))
+        .style(Style::default().bg(FaelightColors::BG_DARK));
+    let inner = block.inner(popup_area);
+    block.render(popup_area, buf);
+    
+    let [mime_area, list_area, footer_area] = Layout::vertical([
+        Constraint::Length(2),
+        Constraint::Min(1),
+        Constraint::Length(1),
+    ]).areas(inner);
+    
+    Paragraph::new(view.mime.as_str())
+        .style(Style::default().fg(FaelightColors::TEXT_DIM).italic())
+        .render(mime_area, buf);
+    
+    // Keep the selection on screen
+    let height = list_area.height as usize;
+    let skip = (view.selected + 1).saturating_sub(height);
+    
+    let items: Vec<ListItem> = view.handlers
+        .iter()
+        .enumerate()
+        .skip(skip)
+        .map(|(i, handler)| {
+            let style = if i == view.selected {
+                Style::default().fg(FaelightColors::TEXT_BRIGHT).bg(FaelightColors::BG_SELECTED).bold()
+            } else {
+                Style::default().fg(FaelightColors::TEXT_BRIGHT)
+            };
+            let kind = if handler.terminal { "terminal" } else { "" };
+            
+            ListItem::new(Line::from(vec![
+                Span::styled(format!("{:<24} ", handler.name), style),
+                Span::styled(format!("{:<16} ", handler.program), Style::default().fg(FaelightColors::TEXT_DIM)),
+                Span::styled(kind, Style::default().fg(FaelightColors::ACCENT_ORANGE)),
+            ]))
+        })
+        .collect();
+    
+    Widget::render(List::new(items), list_area, buf);
+    
+    Paragraph::new("⏎ open · Esc close")
+        .style(Style::default().fg(FaelightColors::TEXT_DIM).italic())
+        .render(footer_area, buf);
+}
@@ -1,6 +1,7 @@
 //! File preview overlay
 use ratatui::prelude::*;
 use ratatui::widgets::{Block, Borders, Paragraph, Wrap};
+use faelight_fm::daemon::client::{HighlightKind, HighlightSpan};
 use crate::app::AppState;
 use crate::ui::colors::FaelightColors;
 
@@ -21,17 +22,23 @@ pub fn render(area: Rect, buf: &mut Buffer, app: &AppState) {
             Span::styled("📄 ", Style::default().fg(FaelightColors::INTENT_FUTURE)),
             Span::styled(path, Style::default().fg(FaelightColors::TEXT_BRIGHT).bold()),
         ]));
+        if let Some(ref info) = app.preview_info {
+            lines.push(Line::from(
+                Span::styled(info, Style::default().fg(FaelightColors::TEXT_DIM).italic())
+            ));
+        }
         lines.push(Line::from(""));
     }
     
-    // Content (show up to 20 lines for smaller box)
+    // Content (preview.lines in fm.toml)
     if let Some(ref content) = app.preview_content {
-        for (i, line) in content.iter().take(20).enumerate() {
+        for (i, line) in content.iter().take(app.config.preview.lines).enumerate() {
             let line_num = format!("{:2} │ ", i + 1);
-            lines.push(Line::from(vec![
+            let mut spans = vec![
                 Span::styled(line_num, Style::default().fg(FaelightColors::INTENT_FUTURE)),
-                Span::raw(line),
-            ]));
+            ];
+            spans.extend(highlight_line(line, i, &app.preview_highlights));
+            lines.push(Line::from(spans));
         }
     }
     
@@ -55,6 +62,51 @@ pub fn render(area: Rect, buf: &mut Buffer, app: &AppState) {
     Widget::render(paragraph, preview_area, buf);
 }
 
+/// Split a line into styled spans using the daemon's highlight ranges
+pub fn highlight_line<'a>(line: &'a str, line_no: usize, highlights: &[HighlightSpan]) -> Vec<Span<'a>> {
+    let mut ranges: Vec<&HighlightSpan> = highlights.iter().filter(|h| h.line == line_no).collect();
+    if ranges.is_empty() {
+        return vec![Span::raw(line)];
+    }
+    ranges.sort_by_key(|h| h.start);
+    
+    // Spans are in chars; map them to byte offsets for slicing
+    let offsets: Vec<usize> = line.char_indices().map(|(i, _)| i).chain([line.len()]).collect();
+    let byte = |col: usize| offsets[col.min(offsets.len() - 1)];
+    
+    let mut spans = Vec::new();
+    let mut pos = 0;
+    for h in ranges {
+        let (start, end) = (byte(h.start), byte(h.end));
+        if start < pos || end <= start {
+            continue;
+        }
+        if start > pos {
+            spans.push(Span::raw(&line[pos..start]));
+        }
+        spans.push(Span::styled(&line[start..end], highlight_style(h.kind)));
+        pos = end;
+    }
+    if pos < line.len() {
+        spans.push(Span::raw(&line[pos..]));
+    }
+    spans
+}
+
+fn highlight_style(kind: HighlightKind) -> Style {
+    let style = Style::default();
+    match kind {
+        HighlightKind::Keyword => style.fg(FaelightColors::ACCENT_BLUE).bold(),
+        HighlightKind::Type => style.fg(FaelightColors::INTENT_DEFERRED),
+        HighlightKind::String => style.fg(FaelightColors::ACCENT_GREEN),
+        HighlightKind::Number => style.fg(FaelightColors::ACCENT_ORANGE),
+        HighlightKind::Comment => style.fg(FaelightColors::TEXT_DIM).italic(),
+        HighlightKind::Heading => style.fg(FaelightColors::ACCENT_GREEN).bold(),
+        HighlightKind::Key => style.fg(FaelightColors::ACCENT_BLUE),
+        HighlightKind::Variable => style.fg(FaelightColors::SYMLINK),
+    }
+}
+
 // Position in bottom portion (starts at 55% down)
 fn bottom_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
     let popup_layout = Layout::default()
@@ -65,7 +117,7 @@ fn bottom_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
             Constraint::Percentage(15),  // 15% bottom spacing
         ])
         .split(r);
-
+    
     Layout::default()
         .direction(Direction::Horizontal)
         .constraints([
@@ -4,17 +4,18 @@ use crate::app::AppState;
 use super::colors::FaelightColors;
 
 pub fn render(area: Rect, buf: &mut Buffer, app: &AppState) {
-    if !app.search_mode {
+    let tab = app.tab();
+    if !tab.search_mode {
         return;
     }
     
-    let status = if app.search_query.is_empty() { 
+    let status = if tab.search_query.is_empty() { 
         "type to filter...".to_string()
     } else { 
-        format!("{} matches", app.filtered_entries.len())
+        format!("{} matches", tab.filtered_entries.len())
     };
     
-    let search_text = format!("SEARCH: {} ({})", app.search_query, status);
+    let search_text = format!("SEARCH: {} ({})", tab.search_query, status);
     
     let paragraph = Paragraph::new(search_text)
         .style(Style::default()
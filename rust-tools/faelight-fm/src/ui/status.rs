@@ -55,6 +55,18 @@ pub fn render(area: Rect, buf: &mut Buffer, app: &AppState) {
                         Style::default().fg(FaelightColors::TEXT_BRIGHT)
                     ),
                 ])
+            } else if !app.tab().marked.is_empty() {
+                Line::from(vec![
+                    Span::styled(
+                        format!("{} marked", app.tab().marked.len()),
+                        Style::default().fg(FaelightColors::ACCENT_ORANGE).bold()
+                    ),
+                    Span::raw(" - Selected: "),
+                    Span::styled(
+                        &entry.name,
+                        Style::default().fg(FaelightColors::TEXT_BRIGHT)
+                    ),
+                ])
             } else {
                 Line::from(vec![
                     Span::raw("Selected: "),
@@ -0,0 +1,32 @@
+//! Path bar, led by the tabs when there's more than one
+use ratatui::prelude::*;
+use ratatui::widgets::Paragraph;
+use crate::app::AppState;
+use super::colors::FaelightColors;
+
+pub fn render(area: Rect, buf: &mut Buffer, app: &AppState) {
+    let mut spans = Vec::new();
+    
+    if app.tabs.len() > 1 {
+        for (i, tab) in app.tabs.iter().enumerate() {
+            let style = if i == app.active {
+                Style::default().bg(FaelightColors::ACCENT_GREEN).fg(FaelightColors::BG_DARK).bold()
+            } else if Some(i) == app.other {
+                Style::default().fg(FaelightColors::ACCENT_GREEN)
+            } else {
+                Style::default().fg(FaelightColors::TEXT_DIM)
+            };
+            spans.push(Span::styled(format!(" {} {} ", i + 1, tab.title()), style));
+        }
+        spans.push(Span::raw(" │ "));
+    }
+    
+    spans.push(Span::raw(format!("PATH: {}", app.tab().cwd.display())));
+    
+    let paragraph = Paragraph::new(Line::from(spans))
+        .style(Style::default()
+            .bg(FaelightColors::BG_DARK)
+            .fg(FaelightColors::TEXT_BRIGHT));
+    
+    paragraph.render(area, buf);
+}
@@ -4,16 +4,18 @@ use crate::app::AppState;
 use super::colors::FaelightColors;
 
 pub fn render(area: Rect, buf: &mut Buffer, app: &AppState) {
-    // Show critical status if in Core or Workspace
-    let lock_status = if app.zone.is_critical() {
-        "🔒 CRITICAL"
+    // Same lock state as the bar and faelight-git
+    let lock_status = if faelight_zone::is_core_locked() {
+        "🔒 LOCKED"
+    } else if app.tab().zone.is_critical() {
+        "⚠ CRITICAL"
     } else {
-        "UNL"
+        "🔓 UNL"
     };
     
     let text = format!(
         "🌲 Faelight FM │ Z: {} │ P: DEF │ {} │ 🏥 HEALTH: OK │ v0.2.0-beta",
-        app.zone.short_label(),
+        app.tab().zone.short_label(),
         lock_status
     );
     
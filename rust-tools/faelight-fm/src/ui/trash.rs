@@ -0,0 +1,65 @@
+//! Trash browser overlay
+use ratatui::prelude::*;
+use ratatui::widgets::{Block, Borders, Clear, List, ListItem, Paragraph};
+use faelight_fm::config::{Action, Keymap};
+use crate::app::TrashView;
+use super::colors::FaelightColors;
+use super::help::centered_rect;
+
+pub fn render(area: Rect, buf: &mut Buffer, view: &TrashView, keymap: &Keymap) {
+    let popup_area = centered_rect(70, 70, area);
+    Clear.render(popup_area, buf);
+    
+    let block = Block::default()
+        .title(format!(" TRASH ({}) ", view.items.len()))
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(FaelightColors::ACCENT_ORANGE))
+        .style(Style::default().bg(FaelightColors::BG_DARK));
+    let inner = block.inner(popup_area);
+    block.render(popup_area, buf);
+    
+    let [list_area, footer_area] = Layout::vertical([Constraint::Min(1), Constraint::Length(1)]).areas(inner);
+    
+    if view.items.is_empty() {
+        Paragraph::new("The trash is empty")
+            .style(Style::default().fg(FaelightColors::TEXT_DIM).italic())
+            .render(list_area, buf);
+    } else {
+        // Keep the selection on screen
+        let height = list_area.height as usize;
+        let skip = (view.selected + 1).saturating_sub(height);
+        
+        let items: Vec<ListItem> = view.items
+            .iter()
+            .enumerate()
+            .skip(skip)
+            .map(|(i, item)| {
+                let style = if i == view.selected {
+                    Style::default().fg(FaelightColors::TEXT_BRIGHT).bg(FaelightColors::BG_SELECTED).bold()
+                } else {
+                    Style::default().fg(FaelightColors::TEXT_BRIGHT)
+                };
+                let deleted = item.deleted
+                    .map(|d| d.format("%Y-%m-%d %H:%M").to_string())
+                    .unwrap_or_default();
+                
+                ListItem::new(Line::from(vec![
+                    Span::styled(format!("{:<16} ", deleted), Style::default().fg(FaelightColors::TEXT_DIM)),
+                    Span::styled(format!("{:<28} ", item.name), style),
+                    Span::styled(item.original.display().to_string(), Style::default().fg(FaelightColors::TEXT_DIM)),
+                ]))
+            })
+            .collect();
+        
+        Widget::render(List::new(items), list_area, buf);
+    }
+    
+    let footer = format!(
+        "r/⏎ restore · {} delete for good · {} undo · Esc close",
+        keymap.keys_for(&Action::Delete),
+        keymap.keys_for(&Action::Undo),
+    );
+    Paragraph::new(footer)
+        .style(Style::default().fg(FaelightColors::TEXT_DIM).italic())
+        .render(footer_area, buf);
+}
@@ -1,27 +1,19 @@
 use ratatui::prelude::*;
 use ratatui::widgets::{Block, Borders, List, ListItem, Widget};
-use faelight_zone::Zone;
+use faelight_zone::{Zone, Zones};
 use super::colors::FaelightColors;
 
-pub fn render(area: Rect, buf: &mut Buffer, current_zone: Zone) {
-    let zones = [
-        Zone::Core,
-        Zone::Workspace,
-        Zone::Src,
-        Zone::Project,
-        Zone::Archive,
-        Zone::Scratch,
-    ];
-    
-    let items: Vec<ListItem> = zones
+pub fn render(area: Rect, buf: &mut Buffer, current_zone: &Zone) {
+    let items: Vec<ListItem> = Zones::global()
+        .all()
         .iter()
         .map(|zone| {
-            let is_match = *zone == current_zone;
+            let is_match = zone.id == current_zone.id;
             
             let style = if is_match {
                 // Current zone: bright color + background highlight + bold
                 Style::default()
-                    .fg(FaelightColors::zone_color(*zone))
+                    .fg(FaelightColors::zone_color(zone))
                     .bg(FaelightColors::BG_SELECTED)
                     .bold()
             } else {
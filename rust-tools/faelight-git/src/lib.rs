@@ -7,11 +7,9 @@ pub mod commands;
 pub use git::repo::GitRepo;
 pub use risk::engine::RiskScore;
 
-/// Check if 0-core is locked
+/// Check if 0-core is locked (the shared lock state from faelight-zone)
 pub fn is_core_locked() -> bool {
-    std::path::PathBuf::from(std::env::var("HOME").unwrap_or_default())
-        .join(".0-core-locked")
-        .exists()
+    faelight_zone::is_core_locked()
 }
 
 /// Get 0-core directory
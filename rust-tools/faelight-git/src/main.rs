@@ -177,11 +177,6 @@ fn get_hooks_dir() -> PathBuf {
     get_core_dir().join(".git/hooks")
 }
 
-fn is_locked() -> bool {
-    let home = env::var("HOME").unwrap_or_default();
-    PathBuf::from(home).join(".0-core-locked").exists()
-}
-
 // ═══════════════════════════════════════════════════════════
 // 🔧 INSTALL/REMOVE HOOKS
 // ═══════════════════════════════════════════════════════════
@@ -281,7 +276,7 @@ fn verify() -> i32 {
     let mut issues = 0;
 
     // Check lock status
-    if is_locked() {
+    if faelight_git::is_core_locked() {
         println!("  {} Core is locked - commits blocked", "❌".red());
         issues += 1;
     } else {
@@ -357,12 +352,15 @@ fn verify() -> i32 {
 
 fn hook_pre_commit() -> i32 {
     // Check if core is locked
-    if is_locked() {
+    if faelight_git::is_core_locked() {
         eprintln!();
         eprintln!("{}", "═══════════════════════════════════════════".red());
         eprintln!("{}", "🔒 COMMIT BLOCKED - Core is locked!".red().bold());
         eprintln!("{}", "═══════════════════════════════════════════".red());
         eprintln!();
+        if let Some(info) = faelight_zone::lock_state().info {
+            eprintln!("Locked {}.", info);
+        }
         eprintln!("Run {} to unlock before committing.", "unlock-core".cyan());
         eprintln!();
         return 1;
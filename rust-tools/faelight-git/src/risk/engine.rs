@@ -1,7 +1,6 @@
 //! Git Risk Score engine
 
 use crate::git::GitRepo;
-use crate::is_core_locked;
 use anyhow::Result;
 
 #[derive(Debug, Clone)]
@@ -45,13 +44,17 @@ impl RiskScore {
         }
 
         // Factor 2: Core locked
-        if is_core_locked() {
+        let lock = faelight_zone::lock_state();
+        if lock.locked {
             let delta = 10;
             total += delta;
             breakdown.push(RiskFactor {
                 name: "Core locked".into(),
                 delta,
-                reason: "0-core is locked".into(),
+                reason: match lock.info {
+                    Some(info) => format!("0-core is locked {}", info),
+                    None => "0-core is locked".into(),
+                },
             });
         }
 
@@ -1,5 +1,6 @@
 //! File search - Find recent files across configured directories
 use super::SearchResult;
+use faelight_zone::Zones;
 use std::fs;
 use std::path::PathBuf;
 use std::time::SystemTime;
@@ -7,7 +8,7 @@ use walkdir::WalkDir;
 
 /// Configuration for file search
 pub struct FileSearchConfig {
-    /// Directories to search
+    /// Directories to search, by default every zone's that exists
     pub search_dirs: Vec<PathBuf>,
     /// Maximum age in days (0 = all files)
     pub max_age_days: u64,
@@ -19,11 +20,7 @@ impl Default for FileSearchConfig {
     fn default() -> Self {
         let home = std::env::var("HOME").unwrap_or_else(|_| "/home/christian".to_string());
         Self {
-            search_dirs: vec![
-                PathBuf::from(format!("{}/0-core", home)),
-                PathBuf::from(format!("{}/1-src", home)),
-                PathBuf::from(format!("{}/2-projects", home)),
-            ],
+            search_dirs: Zones::global().roots(&PathBuf::from(home)),
             max_age_days: 30, // Last 30 days
             max_results: 50,
         }
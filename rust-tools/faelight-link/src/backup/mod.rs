@@ -0,0 +1,359 @@
+use anyhow::{Context, Result};
+use colored::*;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use crate::conflict::move_path;
+use crate::manifest::Manifest;
+
+/// Metadata stored next to the backed-up tree
+const META_FILE: &str = ".faelight-backup.toml";
+
+/// A file, symlink or directory moved aside during a stow
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Backup {
+    #[serde(skip)]
+    pub id: String,
+    /// Where the backed-up path lived
+    pub original: PathBuf,
+    pub kind: String,
+    pub created_at: String,
+}
+
+impl Backup {
+    /// Location of the backed-up path, mirroring its layout below $HOME
+    fn item(&self, root: &Path, home: &Path) -> PathBuf {
+        root.join(&self.id).join(layout_path(&self.original, home))
+    }
+}
+
+fn home() -> Result<PathBuf> {
+    Ok(PathBuf::from(std::env::var("HOME").context("HOME not set")?))
+}
+
+fn backup_root(home: &Path) -> PathBuf {
+    home.join(".local/share/faelight-link/backups")
+}
+
+/// Path inside a backup: relative to $HOME, or the full path for anything outside it
+fn layout_path(original: &Path, home: &Path) -> PathBuf {
+    let relative = original
+        .strip_prefix(home)
+        .or_else(|_| original.strip_prefix("/"))
+        .unwrap_or(original);
+    relative.to_path_buf()
+}
+
+/// Move `target` into a new backup. It can be put back with `restore(&backup.id)`.
+pub fn create(target: &Path) -> Result<Backup> {
+    let home = home()?;
+    create_in(&backup_root(&home), &home, target)
+}
+
+fn create_in(root: &Path, home: &Path, target: &Path) -> Result<Backup> {
+    fs::create_dir_all(root)?;
+    
+    // Several paths can be backed up in the same second
+    let timestamp = chrono::Local::now().format("%Y%m%d-%H%M%S").to_string();
+    let mut id = timestamp.clone();
+    let mut n = 1;
+    while root.join(&id).exists() {
+        id = format!("{}-{}", timestamp, n);
+        n += 1;
+    }
+    
+    let kind = if target.is_symlink() {
+        "symlink"
+    } else if target.is_dir() {
+        "directory"
+    } else {
+        "file"
+    };
+    
+    let backup = Backup {
+        id,
+        original: target.to_path_buf(),
+        kind: kind.to_string(),
+        created_at: chrono::Local::now().to_rfc3339(),
+    };
+    
+    let dir = root.join(&backup.id);
+    let item = backup.item(root, home);
+    if let Some(parent) = item.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(dir.join(META_FILE), toml::to_string_pretty(&backup)?)?;
+    
+    if let Err(e) = move_path(target, &item) {
+        let _ = fs::remove_dir_all(&dir);
+        return Err(e);
+    }
+    Ok(backup)
+}
+
+/// All backups that record their original location, oldest first
+pub fn list() -> Result<Vec<Backup>> {
+    list_in(&backup_root(&home()?))
+}
+
+fn list_in(root: &Path) -> Result<Vec<Backup>> {
+    let mut backups = Vec::new();
+    
+    if !root.exists() {
+        return Ok(backups);
+    }
+    
+    for entry in fs::read_dir(root)? {
+        let entry = entry?;
+        let id = entry.file_name().to_string_lossy().to_string();
+        if let Ok(backup) = load_from(root, &id) {
+            backups.push(backup);
+        }
+    }
+    
+    // Ids only have seconds, and "-10" sorts before "-2"
+    backups.sort_by(|a, b| created(a).cmp(&created(b)).then_with(|| a.id.cmp(&b.id)));
+    Ok(backups)
+}
+
+fn created(backup: &Backup) -> Option<chrono::DateTime<chrono::FixedOffset>> {
+    chrono::DateTime::parse_from_rfc3339(&backup.created_at).ok()
+}
+
+fn load(id: &str) -> Result<Backup> {
+    load_from(&backup_root(&home()?), id)
+}
+
+fn load_from(root: &Path, id: &str) -> Result<Backup> {
+    let meta = root.join(id).join(META_FILE);
+    let content = fs::read_to_string(&meta)
+        .with_context(|| format!("Backup '{}' not found", id))?;
+    let mut backup: Backup = toml::from_str(&content)
+        .with_context(|| format!("Invalid backup metadata {}", meta.display()))?;
+    backup.id = id.to_string();
+    Ok(backup)
+}
+
+/// Move a backup back to its original location and delete it
+pub fn restore(id: &str) -> Result<Backup> {
+    let home = home()?;
+    restore_from(&backup_root(&home), &home, id)
+}
+
+fn restore_from(root: &Path, home: &Path, id: &str) -> Result<Backup> {
+    let backup = load_from(root, id)?;
+    
+    if backup.original.exists() || backup.original.is_symlink() {
+        anyhow::bail!("{} already exists", backup.original.display());
+    }
+    if let Some(parent) = backup.original.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    
+    move_path(&backup.item(root, home), &backup.original)?;
+    fs::remove_dir_all(root.join(id))?;
+    Ok(backup)
+}
+
+/// Put a backup back in place of the link stow left there. It's moved next
+/// to the link first, so a failed (cross-device) move leaves the link alone.
+fn restore_over_link(root: &Path, home: &Path, id: &str) -> Result<Backup> {
+    let backup = load_from(root, id)?;
+    let target = &backup.original;
+    if !target.is_symlink() {
+        anyhow::bail!("{} is not a link", target.display());
+    }
+    
+    let name = target.file_name().context("backup of /")?.to_string_lossy();
+    let temp = target.with_file_name(format!(".{}.faelight-restore", name));
+    if temp.exists() || temp.is_symlink() {
+        anyhow::bail!("{} is in the way", temp.display());
+    }
+    
+    let item = backup.item(root, home);
+    move_path(&item, &temp)?;
+    
+    // A file or link replaces the link in one rename; a directory can't
+    let swapped = if temp.is_dir() && !temp.is_symlink() {
+        let link = fs::read_link(target)?;
+        fs::remove_file(target)?;
+        fs::rename(&temp, target).inspect_err(|_| {
+            let _ = std::os::unix::fs::symlink(&link, target);
+        })
+    } else {
+        fs::rename(&temp, target)
+    };
+    if let Err(e) = swapped {
+        let _ = move_path(&temp, &item);
+        return Err(e.into());
+    }
+    
+    fs::remove_dir_all(root.join(id))?;
+    Ok(backup)
+}
+
+/// `faelight-link backups list`
+pub fn print_list() -> Result<()> {
+    let backups = list()?;
+    
+    if backups.is_empty() {
+        println!("  {}", "No backups found".bright_black());
+        return Ok(());
+    }
+    
+    for backup in &backups {
+        let name = if backup.kind == "directory" {
+            format!("{}/", backup.original.display())
+        } else {
+            backup.original.display().to_string()
+        };
+        println!(
+            "  {} {} {}",
+            backup.id.bright_green(),
+            name,
+            format!("({})", backup.kind).bright_black()
+        );
+    }
+    
+    println!("\n  Total: {} backups", backups.len());
+    println!("  {} Restore one with 'faelight-link backups restore <id>'", "💡".bright_blue());
+    Ok(())
+}
+
+/// `faelight-link backups restore <id>`
+pub fn restore_interactive(id: &str, force: bool) -> Result<()> {
+    let backup = load(id)?;
+    let target = &backup.original;
+    
+    println!("  {} → {}", backup.id.bright_green(), target.display());
+    
+    // The link stow put in its place is replaced; real files never are
+    if target.is_symlink() {
+        if !force {
+            use dialoguer::Confirm;
+            if !Confirm::new()
+                .with_prompt(format!("Replace the link at {}?", target.display()))
+                .default(true)
+                .interact()?
+            {
+                println!("  {}", "Cancelled".bright_yellow());
+                return Ok(());
+            }
+        }
+    
+        let home = home()?;
+        restore_over_link(&backup_root(&home), &home, id)?;
+        let mut manifest = Manifest::load()?;
+        manifest.remove_link(target);
+        manifest.save()?;
+    } else if target.exists() {
+        anyhow::bail!("{} already exists, move it away first", target.display());
+    } else {
+        restore(id)?;
+    }
+    
+    println!("\n  {} Restored {}", "✅".bright_green(), target.display());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_layout_path() {
+        let home = Path::new("/home/user");
+        assert_eq!(layout_path(&home.join(".config/nvim"), home), PathBuf::from(".config/nvim"));
+        assert_eq!(layout_path(Path::new("/etc/hosts"), home), PathBuf::from("etc/hosts"));
+    }
+    
+    #[test]
+    fn test_create_list_restore() {
+        let home = std::env::temp_dir().join(format!("faelight-link-backup-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&home);
+        let root = backup_root(&home);
+        let target = home.join(".config/nvim");
+        fs::create_dir_all(target.join("lua/plugins")).unwrap();
+        fs::write(target.join("init.lua"), "require('plugins')").unwrap();
+        fs::write(target.join("lua/plugins/lsp.lua"), "return {}").unwrap();
+        
+        let first = create_in(&root, &home, &target).unwrap();
+        assert!(!target.exists());
+        assert_eq!(first.kind, "directory");
+        assert!(root.join(&first.id).join(".config/nvim/lua/plugins/lsp.lua").exists());
+        
+        // A second backup in the same second gets its own id
+        fs::create_dir_all(&target).unwrap();
+        fs::write(target.join("init.lua"), "-- new").unwrap();
+        let second = create_in(&root, &home, &target).unwrap();
+        assert_ne!(first.id, second.id);
+        
+        let backups = list_in(&root).unwrap();
+        let ids: Vec<_> = backups.iter().map(|b| b.id.as_str()).collect();
+        assert_eq!(ids, [first.id.as_str(), second.id.as_str()]);
+        assert!(backups.iter().all(|b| b.original == target));
+        
+        let restored = restore_from(&root, &home, &first.id).unwrap();
+        assert_eq!(restored.original, target);
+        assert_eq!(fs::read_to_string(target.join("init.lua")).unwrap(), "require('plugins')");
+        assert_eq!(fs::read_to_string(target.join("lua/plugins/lsp.lua")).unwrap(), "return {}");
+        assert!(!root.join(&first.id).exists());
+        
+        // The original is back, so the other backup can't overwrite it
+        assert!(restore_from(&root, &home, &second.id).is_err());
+        assert_eq!(list_in(&root).unwrap().len(), 1);
+        
+        fs::remove_dir_all(&home).unwrap();
+    }
+    
+    #[test]
+    fn test_restore_over_link() {
+        let home = std::env::temp_dir().join(format!("faelight-link-relink-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&home);
+        let root = backup_root(&home);
+        let target = home.join(".config/app");
+        fs::create_dir_all(target.join("sub")).unwrap();
+        fs::write(target.join("sub/conf"), "mine").unwrap();
+        let file = home.join(".apprc");
+        fs::write(&file, "rc").unwrap();
+        
+        let dir_backup = create_in(&root, &home, &target).unwrap();
+        let file_backup = create_in(&root, &home, &file).unwrap();
+        std::os::unix::fs::symlink("/nowhere/app", &target).unwrap();
+        std::os::unix::fs::symlink("/nowhere/apprc", &file).unwrap();
+        
+        restore_over_link(&root, &home, &dir_backup.id).unwrap();
+        restore_over_link(&root, &home, &file_backup.id).unwrap();
+        assert!(!target.is_symlink() && !file.is_symlink());
+        assert_eq!(fs::read_to_string(target.join("sub/conf")).unwrap(), "mine");
+        assert_eq!(fs::read_to_string(&file).unwrap(), "rc");
+        assert!(list_in(&root).unwrap().is_empty());
+        
+        // Nothing to replace: refused, and the backup stays
+        let again = create_in(&root, &home, &file).unwrap();
+        assert!(restore_over_link(&root, &home, &again.id).is_err());
+        assert_eq!(list_in(&root).unwrap().len(), 1);
+        
+        fs::remove_dir_all(&home).unwrap();
+    }
+    
+    #[test]
+    fn test_list_oldest_first() {
+        let home = std::env::temp_dir().join(format!("faelight-link-order-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&home);
+        let root = backup_root(&home);
+        fs::create_dir_all(&home).unwrap();
+        
+        // Enough backups in one second to get a "-10" id
+        let mut created = Vec::new();
+        for i in 0..12 {
+            let file = home.join(format!("f{}", i));
+            fs::write(&file, "x").unwrap();
+            created.push(create_in(&root, &home, &file).unwrap().original);
+        }
+        let listed: Vec<PathBuf> = list_in(&root).unwrap().into_iter().map(|b| b.original).collect();
+        assert_eq!(listed, created);
+        
+        fs::remove_dir_all(&home).unwrap();
+    }
+}
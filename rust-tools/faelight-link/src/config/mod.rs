@@ -0,0 +1,158 @@
+use anyhow::{Context, Result};
+use glob::Pattern;
+use serde::Deserialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use crate::conflict::ConflictPolicy;
+
+/// Per-package settings file, never linked itself
+pub const CONFIG_FILE: &str = ".faelight-link.toml";
+
+/// Always ignored, like stow/.stow-local-ignore
+const DEFAULT_IGNORE: &[&str] = &[CONFIG_FILE, ".dotmeta", ".git", ".gitignore", "README*"];
+
+/// Links for targets outside $HOME are staged here, for a privileged step to install
+const STAGING_DIR: &str = ".local/state/faelight-link/staging";
+
+/// Settings from `<package>/.faelight-link.toml`
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PackageConfig {
+    /// Conflict policy used when `--on-conflict` isn't given
+    pub on_conflict: Option<ConflictPolicy>,
+    /// Directory the package is linked into (`~` by default)
+    pub target: Option<String>,
+    /// Globs for package files that are never linked. Patterns without a `/`
+    /// match any file name, others the path inside the package.
+    pub ignore: Vec<String>,
+    /// Only stow on these hosts (all when empty)
+    pub hosts: Vec<String>,
+    /// Only stow in these profiles (all when empty)
+    pub profiles: Vec<String>,
+    #[serde(skip)]
+    patterns: Vec<Pattern>,
+}
+
+/// Where a package's links go
+#[derive(Debug, Clone)]
+pub struct TargetRoot {
+    /// Directory the links are created in
+    pub path: PathBuf,
+    /// The real target when `path` is a staging directory
+    pub staged_for: Option<PathBuf>,
+}
+
+impl PackageConfig {
+    pub fn load(pkg_dir: &Path) -> Result<Self> {
+        let path = pkg_dir.join(CONFIG_FILE);
+    
+        let mut config: Self = if path.exists() {
+            let content = fs::read_to_string(&path)
+                .with_context(|| format!("Cannot read {}", path.display()))?;
+            toml::from_str(&content).with_context(|| format!("Invalid {}", path.display()))?
+        } else {
+            Self::default()
+        };
+    
+        config.patterns = DEFAULT_IGNORE
+            .iter()
+            .copied()
+            .chain(config.ignore.iter().map(String::as_str))
+            .map(|p| Pattern::new(p).with_context(|| format!("Invalid ignore pattern '{}' in {}", p, path.display())))
+            .collect::<Result<_>>()?;
+        Ok(config)
+    }
+    
+    /// Whether a path inside the package is excluded from linking
+    pub fn is_ignored(&self, relative: &Path) -> bool {
+        let name = relative.file_name().map(|n| n.to_string_lossy()).unwrap_or_default();
+    
+        self.patterns.iter().any(|pattern| {
+            if pattern.as_str().contains('/') {
+                pattern.matches_path(relative)
+            } else {
+                pattern.matches(&name)
+            }
+        })
+    }
+    
+    /// Resolve the target root. Roots outside `home` can't be written
+    /// without privileges, so their links are staged below `home` instead.
+    pub fn target_root(&self, home: &Path) -> TargetRoot {
+        let target = match self.target.as_deref() {
+            None | Some("~") => home.to_path_buf(),
+            Some(t) => match t.strip_prefix("~/") {
+                Some(rest) => home.join(rest),
+                None => home.join(t),
+            },
+        };
+    
+        if target.starts_with(home) {
+            return TargetRoot { path: target, staged_for: None };
+        }
+    
+        let inside = target.strip_prefix("/").unwrap_or(&target);
+        TargetRoot {
+            path: home.join(STAGING_DIR).join(inside),
+            staged_for: Some(target),
+        }
+    }
+    
+    /// Why the package must not be stowed on this machine, if it mustn't
+    pub fn inactive_reason(&self, home: &Path) -> Option<String> {
+        if !self.hosts.is_empty() {
+            let host = hostname();
+            if !self.hosts.contains(&host) {
+                return Some(format!("only for hosts {} (this is {})", self.hosts.join(", "), host));
+            }
+        }
+    
+        if !self.profiles.is_empty() {
+            let profile = current_profile(home);
+            if !self.profiles.contains(&profile) {
+                return Some(format!("only for profiles {} (active: {})", self.profiles.join(", "), profile));
+            }
+        }
+    
+        None
+    }
+}
+
+pub(crate) fn hostname() -> String {
+    fs::read_to_string("/proc/sys/kernel/hostname")
+        .or_else(|_| fs::read_to_string("/etc/hostname"))
+        .map(|h| h.trim().to_string())
+        .unwrap_or_else(|_| "unknown".to_string())
+}
+
+/// Profile set by the `profile` tool
+fn current_profile(home: &Path) -> String {
+    fs::read_to_string(home.join(".local/state/0-core/current-profile"))
+        .map(|s| s.trim().to_string())
+        .unwrap_or_else(|_| "default".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_ignore_and_target() {
+        let dir = std::env::temp_dir().join(format!("faelight-link-config-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join(CONFIG_FILE), "target = \"/etc\"\nignore = [\"*.bak\", \".config/app/cache\"]\n").unwrap();
+    
+        let config = PackageConfig::load(&dir).unwrap();
+        assert!(config.is_ignored(Path::new("README.md")));
+        assert!(config.is_ignored(Path::new(".config/app/init.bak")));
+        assert!(config.is_ignored(Path::new(".config/app/cache")));
+        assert!(!config.is_ignored(Path::new(".config/app/init.lua")));
+    
+        let home = Path::new("/home/test");
+        let root = config.target_root(home);
+        assert_eq!(root.path, home.join(STAGING_DIR).join("etc"));
+        assert_eq!(root.staged_for, Some(PathBuf::from("/etc")));
+    
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -2,8 +2,23 @@ use anyhow::{Context, Result};
 use colored::*;
 use dialoguer::Select;
 use std::fs;
+use std::os::unix::fs::symlink;
 use std::path::{Path, PathBuf};
 
+/// How to resolve conflicts without asking
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConflictPolicy {
+    /// Move the existing file to the backup area
+    Backup,
+    /// Leave the existing file alone
+    Skip,
+    /// Replace the existing file
+    Overwrite,
+    /// Stop without changing anything
+    Fail,
+}
+
 #[derive(Debug, Clone)]
 pub enum ConflictAction {
     Backup,
@@ -28,6 +43,7 @@ impl Conflict {
 pub fn resolve_conflict(conflict: &Conflict) -> Result<ConflictAction> {
     println!("\n  {} Conflict detected!", "⚠️".bright_yellow());
     println!("    Target: {}", conflict.target.display().to_string().bright_white());
+    println!("    Source: {}", conflict.source.display().to_string().bright_black());
     
     // Check what exists
     if conflict.target.is_symlink() {
@@ -56,33 +72,46 @@ pub fn resolve_conflict(conflict: &Conflict) -> Result<ConflictAction> {
     }
 }
 
-/// Create backup of existing file
-pub fn backup_file(target: &Path) -> Result<PathBuf> {
-    let home = std::env::var("HOME").context("HOME not set")?;
-    let backup_root = PathBuf::from(home).join(".local/share/faelight-link/backups");
-    
-    // Create backup directory
-    fs::create_dir_all(&backup_root)?;
-    
-    // Generate backup path with timestamp
-    let timestamp = chrono::Local::now().format("%Y%m%d-%H%M%S");
-    let filename = target.file_name()
-        .and_then(|n| n.to_str())
-        .unwrap_or("unknown");
-    let backup_path = backup_root.join(format!("{}.{}", filename, timestamp));
+/// Rename a file, symlink or directory, copying when crossing filesystems
+pub fn move_path(from: &Path, to: &Path) -> Result<()> {
+    match fs::rename(from, to) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::CrossesDevices => {
+            if let Err(e) = copy_tree(from, to) {
+                let _ = remove_tree(to);
+                return Err(e).with_context(|| format!("Cannot move {}", from.display()));
+            }
+            remove_tree(from)?;
+            Ok(())
+        }
+        Err(e) => Err(e).with_context(|| format!("Cannot move {}", from.display())),
+    }
+}
+
+/// Copy a tree, keeping symlinks as links and permissions on directories
+fn copy_tree(from: &Path, to: &Path) -> Result<()> {
+    let metadata = fs::symlink_metadata(from)?;
     
-    // Copy or move
-    if target.is_symlink() {
-        // For symlinks, just remove (we'll create new one)
-        fs::remove_file(target)?;
-    } else if target.is_file() {
-        // For regular files, copy to backup
-        fs::copy(target, &backup_path)?;
-        fs::remove_file(target)?;
-    } else if target.is_dir() {
-        // For directories, this is more complex - skip for now
-        anyhow::bail!("Cannot backup directories yet");
+    if metadata.is_symlink() {
+        symlink(fs::read_link(from)?, to)?;
+    } else if metadata.is_dir() {
+        fs::create_dir(to)?;
+        for entry in fs::read_dir(from)? {
+            let entry = entry?;
+            copy_tree(&entry.path(), &to.join(entry.file_name()))?;
+        }
+        fs::set_permissions(to, metadata.permissions())?;
+    } else {
+        fs::copy(from, to)?;
     }
     
-    Ok(backup_path)
+    Ok(())
+}
+
+fn remove_tree(path: &Path) -> std::io::Result<()> {
+    if path.is_dir() && !path.is_symlink() {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
+    }
 }
@@ -1,308 +1,230 @@
 use anyhow::{Context, Result};
 use colored::*;
+use serde::Serialize;
 use std::fs;
-use std::os::unix::fs::symlink;
 use std::path::{Path, PathBuf};
-use crate::conflict::{Conflict, ConflictAction, resolve_conflict, backup_file};
+use crate::config::PackageConfig;
+use crate::manifest::{self, Drift, Manifest};
+use crate::template;
 
-/// Create symlinks for package files
-pub fn create_links(pkg_dir: &Path, files: &[PathBuf]) -> Result<()> {
-    let home = std::env::var("HOME").context("HOME not set")?;
-    let home_path = PathBuf::from(&home);
-    
-    let mut created = 0;
-    let mut skipped = 0;
-    let mut backed_up = 0;
-    let mut errors = 0;
-    
-    for file in files {
-        // Calculate target path (where symlink should be)
-        let relative = file.strip_prefix(pkg_dir)?;
-        let target = home_path.join(relative);
-        
-        // Create parent directories if needed
-        if let Some(parent) = target.parent() {
-            fs::create_dir_all(parent)?;
-        }
-        
-        // Check if target already exists (CONFLICT!)
-        if target.exists() || target.is_symlink() {
-            // Create conflict
-            let conflict = Conflict::new(target.clone(), file.clone());
-            
-            // Ask user what to do
-            match resolve_conflict(&conflict)? {
-                ConflictAction::Backup => {
-                    // Backup existing file
-                    match backup_file(&target) {
-                        Ok(backup_path) => {
-                            println!("    {} {} (backed up to {})", 
-                                "💾".bright_blue(), 
-                                relative.display(),
-                                backup_path.file_name().unwrap().to_string_lossy().bright_black()
-                            );
-                            backed_up += 1;
-                            
-                            // Now create the symlink
-                            if let Err(e) = symlink(file, &target) {
-                                println!("    {} {} ({})", "✗".bright_red(), relative.display(), e);
-                                errors += 1;
-                            } else {
-                                created += 1;
-                            }
-                        }
-                        Err(e) => {
-                            println!("    {} {} (backup failed: {})", "✗".bright_red(), relative.display(), e);
-                            errors += 1;
-                        }
-                    }
-                }
-                ConflictAction::Skip => {
-                    println!("    {} {}", "⊘".bright_yellow(), relative.display());
-                    skipped += 1;
-                }
-                ConflictAction::Overwrite => {
-                    // Remove existing
-                    if target.is_symlink() || target.is_file() {
-                        fs::remove_file(&target)?;
-                    }
-                    
-                    // Create symlink
-                    match symlink(file, &target) {
-                        Ok(_) => {
-                            println!("    {} {} (overwritten)", "✓".bright_green(), relative.display());
-                            created += 1;
-                        }
-                        Err(e) => {
-                            println!("    {} {} ({})", "✗".bright_red(), relative.display(), e);
-                            errors += 1;
-                        }
-                    }
-                }
-                ConflictAction::Quit => {
-                    println!("\n  {} Operation cancelled by user", "⚠️".bright_yellow());
-                    break;
-                }
-            }
-            
-            continue;
-        }
-        
-        // No conflict - create symlink
-        match symlink(file, &target) {
-            Ok(_) => {
-                println!("    {} {}", "✓".bright_green(), relative.display());
-                created += 1;
-            }
-            Err(e) => {
-                println!("    {} {} ({})", "✗".bright_red(), relative.display(), e);
-                errors += 1;
-            }
-        }
-    }
-    
-    // Summary
-    println!();
-    if created > 0 {
-        println!("  {} Created: {}", "✅".bright_green(), created);
-    }
-    if backed_up > 0 {
-        println!("  {} Backed up: {}", "💾".bright_blue(), backed_up);
-    }
-    if skipped > 0 {
-        println!("  {} Skipped: {}", "⊘".bright_yellow(), skipped);
-    }
-    if errors > 0 {
-        println!("  {} Errors: {}", "✗".bright_red(), errors);
-    }
-    
-    Ok(())
+/// Link counts of one package
+#[derive(Serialize)]
+struct PackageStatus {
+    package: String,
+    links: usize,
+    drifted: usize,
 }
 
 /// Show status of all links
-pub fn status() -> Result<()> {
+pub fn status(json: bool) -> Result<()> {
     let home = std::env::var("HOME").context("HOME not set")?;
     let stow_dir = PathBuf::from(&home).join("0-core/stow");
+    let packages = crate::package::discover_packages(&stow_dir)?;
+    let manifest = Manifest::load()?;
+    
+    let statuses: Vec<PackageStatus> = packages
+        .into_iter()
+        .map(|package| {
+            let records = manifest.links(&package);
+            PackageStatus {
+                links: records.len(),
+                drifted: records.iter().filter(|r| r.check().is_some_and(|d| d.is_drift())).count(),
+                package,
+            }
+        })
+        .collect();
+    let total_links: usize = statuses.iter().map(|s| s.links).sum();
+    let total_drifted: usize = statuses.iter().map(|s| s.drifted).sum();
+    
+    if json {
+        let report = serde_json::json!({
+            "packages": statuses,
+            "total_links": total_links,
+            "total_drifted": total_drifted,
+        });
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
     
     if !stow_dir.exists() {
         println!("  {} Stow directory not found", "⚠️".bright_yellow());
         return Ok(());
     }
     
-    let packages = crate::package::discover_packages(&stow_dir)?;
-    
-    if packages.is_empty() {
+    if statuses.is_empty() {
         println!("  {} No packages found", "⚠️".bright_yellow());
         return Ok(());
     }
     
-    let mut total_links = 0;
-    let mut total_broken = 0;
-    
-    for package in &packages {
-        let links = count_package_links(&home, package)?;
-        let broken = count_broken_links(&home, package)?;
-        
-        total_links += links;
-        total_broken += broken;
-        
-        if broken > 0 {
+    for status in &statuses {
+        if status.drifted > 0 {
             println!(
-                "  {} {} ({} links, {} broken)",
+                "  {} {} ({} links, {} drifted)",
                 "⚠️".bright_yellow(),
-                package.bright_green(),
-                links,
-                broken.to_string().bright_red()
+                status.package.bright_green(),
+                status.links,
+                status.drifted.to_string().bright_red()
             );
-        } else if links > 0 {
+        } else if status.links > 0 {
             println!(
                 "  {} {} ({} links)",
                 "✓".bright_green(),
-                package.bright_green(),
-                links
+                status.package.bright_green(),
+                status.links
             );
         } else {
             println!(
                 "  {} {} (not stowed)",
                 "○".bright_black(),
-                package.bright_black()
+                status.package.bright_black()
             );
         }
     }
     
     println!();
     println!("  Total links: {}", total_links);
-    if total_broken > 0 {
-        println!("  Broken links: {}", total_broken.to_string().bright_red());
+    if total_drifted > 0 {
+        println!("  Drifted links: {}", total_drifted.to_string().bright_red());
+        println!("\n  {} Run 'faelight-link verify' for details", "💡".bright_blue());
     }
     
     Ok(())
 }
 
-/// Count symlinks for a package
-fn count_package_links(home: &str, package: &str) -> Result<usize> {
-    let home_path = PathBuf::from(home);
-    let mut count = 0;
+/// Health of one package's recorded links
+#[derive(Serialize)]
+struct PackageAudit<'a> {
+    package: &'a str,
+    valid: usize,
+    broken: usize,
+    folded: usize,
+}
+
+#[derive(Serialize)]
+struct BrokenLink<'a> {
+    package: &'a str,
+    target: PathBuf,
+    problem: String,
+}
+
+/// Audit all links - comprehensive health check
+pub fn audit(json: bool) -> Result<()> {
+    let home = std::env::var("HOME").context("HOME not set")?;
+    let stow_dir = PathBuf::from(&home).join("0-core/stow");
     
-    let search_paths = vec![
-        home_path.clone(),
-        home_path.join(".config"),
-    ];
+    if !stow_dir.exists() && !json {
+        println!("  {} Stow directory not found", "⚠️".bright_yellow());
+        return Ok(());
+    }
     
-    for search_path in search_paths {
-        if !search_path.exists() {
-            continue;
-        }
-        
-        if let Ok(entries) = fs::read_dir(&search_path) {
-            for entry in entries.flatten() {
-                let path = entry.path();
-                
-                if path.is_symlink() {
-                    if let Ok(target) = fs::read_link(&path) {
-                        let target_str = target.to_string_lossy();
-                        if target_str.contains(&format!("0-core/stow/{}", package)) {
-                            count += 1;
-                        }
+    let manifest = Manifest::load()?;
+    let vars = template::variables(Path::new(&home))?;
+    let mut audits = Vec::new();
+    let mut broken_links = Vec::new();
+    
+    for (package, record) in &manifest.packages {
+        let mut audit = PackageAudit { package, valid: 0, broken: 0, folded: 0 };
+        let pkg_dir = stow_dir.join(package);
+        let config = PackageConfig::load(&pkg_dir)?;
+        let inactive = config.inactive_reason(Path::new(&home));
+    
+        for link in &record.links {
+            // Links the package rules no longer allow
+            let rule = if let Some(reason) = &inactive {
+                Some(format!("package is {}", reason))
+            } else if link.source.strip_prefix(&pkg_dir).is_ok_and(|rel| config.is_ignored(rel)) {
+                Some("ignored by package rules".to_string())
+            } else {
+                None
+            };
+    
+            match (rule, link.check()) {
+                (Some(problem), _) => {
+                    audit.broken += 1;
+                    broken_links.push(BrokenLink { package, target: link.target.clone(), problem });
+                }
+                (None, Some(drift)) if drift.is_drift() => {
+                    audit.broken += 1;
+                    broken_links.push(BrokenLink { package, target: link.target.clone(), problem: drift_label(&drift) });
+                }
+                (None, _) => {
+                    audit.valid += 1;
+                    // Folded links cover a whole directory
+                    if link.folded {
+                        audit.folded += 1;
                     }
                 }
             }
         }
-    }
-    
-    Ok(count)
-}
-
-/// Count broken symlinks for a package
-fn count_broken_links(home: &str, package: &str) -> Result<usize> {
-    let home_path = PathBuf::from(home);
-    let mut count = 0;
-    
-    let search_paths = vec![
-        home_path.clone(),
-        home_path.join(".config"),
-    ];
     
-    for search_path in search_paths {
-        if !search_path.exists() {
-            continue;
-        }
-        
-        if let Ok(entries) = fs::read_dir(&search_path) {
-            for entry in entries.flatten() {
-                let path = entry.path();
-                
-                if path.is_symlink() {
-                    if let Ok(target) = fs::read_link(&path) {
-                        let target_str = target.to_string_lossy();
-                        if target_str.contains(&format!("0-core/stow/{}", package)) {
-                            if !path.exists() {
-                                count += 1;
-                            }
-                        }
-                    }
+        // Rendered files must match their template and the current variables
+        match template::render_package(&pkg_dir, Path::new(&home), &config, &vars) {
+            Ok(rendered) => {
+                for r in rendered.into_iter().filter(|r| r.is_stale()) {
+                    audit.broken += 1;
+                    let template = r.template.strip_prefix(&stow_dir).unwrap_or(&r.template).display().to_string();
+                    broken_links.push(BrokenLink { package, target: r.output, problem: format!("stale render of {}", template) });
                 }
             }
+            Err(e) => {
+                audit.broken += 1;
+                broken_links.push(BrokenLink { package, target: pkg_dir.clone(), problem: format!("{:#}", e) });
+            }
         }
-    }
     
-    Ok(count)
-}
-
-/// Audit all links - comprehensive health check
-pub fn audit() -> Result<()> {
-    let home = std::env::var("HOME").context("HOME not set")?;
-    let stow_dir = PathBuf::from(&home).join("0-core/stow");
+        audits.push(audit);
+    }
     
-    if !stow_dir.exists() {
-        println!("  {} Stow directory not found", "⚠️".bright_yellow());
+    let total_valid: usize = audits.iter().map(|a| a.valid).sum();
+    let total_broken: usize = audits.iter().map(|a| a.broken).sum();
+    let total_folded: usize = audits.iter().map(|a| a.folded).sum();
+    let health_pct = (total_valid * 100)
+        .checked_div(total_valid + total_broken)
+        .unwrap_or(100);
+    
+    if json {
+        let report = serde_json::json!({
+            "packages": audits,
+            "broken": broken_links,
+            "total_valid": total_valid,
+            "total_broken": total_broken,
+            "total_folded": total_folded,
+            "health": health_pct,
+        });
+        println!("{}", serde_json::to_string_pretty(&report)?);
         return Ok(());
     }
     
-    let packages = crate::package::discover_packages(&stow_dir)?;
-    
-    if packages.is_empty() {
-        println!("  {} No packages found", "⚠️".bright_yellow());
+    if audits.is_empty() {
+        println!("  {} No stowed packages recorded", "⚠️".bright_yellow());
         return Ok(());
     }
     
-    println!("\n  Scanning all symlinks...\n");
+    println!("\n  Checking recorded links...\n");
     
-    let mut total_valid = 0;
-    let mut total_broken = 0;
-    let mut broken_links: Vec<(String, PathBuf)> = Vec::new();
+    for audit in &audits {
+        let folded_note = if audit.folded > 0 {
+            format!(", {} folded", audit.folded)
+        } else {
+            String::new()
+        };
     
-    for package in &packages {
-        let links = find_all_package_links(&home, package)?;
-        let mut valid = 0;
-        let mut broken = 0;
-        
-        for link in links {
-            if link.exists() {
-                valid += 1;
-            } else {
-                broken += 1;
-                broken_links.push((package.clone(), link));
-            }
-        }
-        
-        total_valid += valid;
-        total_broken += broken;
-        
-        if broken > 0 {
+        if audit.broken > 0 {
             println!(
-                "  {} {} ({} valid, {} broken)",
+                "  {} {} ({} valid, {} broken{})",
                 "⚠️".bright_yellow(),
-                package.bright_green(),
-                valid.to_string().bright_green(),
-                broken.to_string().bright_red()
+                audit.package.bright_green(),
+                audit.valid.to_string().bright_green(),
+                audit.broken.to_string().bright_red(),
+                folded_note
             );
-        } else if valid > 0 {
+        } else if audit.valid > 0 {
             println!(
-                "  {} {} ({} links)",
+                "  {} {} ({} links{})",
                 "✓".bright_green(),
-                package.bright_green(),
-                valid
+                audit.package.bright_green(),
+                audit.valid,
+                folded_note
             );
         }
     }
@@ -311,29 +233,27 @@ pub fn audit() -> Result<()> {
     println!("  {} Summary:", "📊".bright_blue());
     println!("  ━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
     println!("    Valid links:   {}", total_valid.to_string().bright_green());
+    if total_folded > 0 {
+        println!("    Folded dirs:   {}", total_folded.to_string().bright_blue());
+    }
     
     if total_broken > 0 {
         println!("    Broken links:  {}", total_broken.to_string().bright_red());
         println!("\n  {} Broken links found:", "⚠️".bright_yellow());
-        for (package, link) in &broken_links {
-            println!("    {} {} → {}", 
+        for link in &broken_links {
+            println!("    {} {} → {} {}", 
                 "✗".bright_red(),
-                package.bright_black(),
-                link.display().to_string().bright_red()
+                link.package.bright_black(),
+                link.target.display().to_string().bright_red(),
+                format!("({})", link.problem).bright_black()
             );
         }
-        println!("\n  {} Run 'faelight-link clean' to remove broken links", "💡".bright_blue());
+        println!("\n  {} Re-stow the package to repair links and stale renders, or run 'faelight-link clean' to remove broken ones", "💡".bright_blue());
     } else {
         println!("    Broken links:  {}", "0".bright_green());
         println!("\n  {} All links are healthy!", "✅".bright_green());
     }
     
-    let health_pct = if total_valid + total_broken > 0 {
-        (total_valid * 100) / (total_valid + total_broken)
-    } else {
-        100
-    };
-    
     println!("\n  Overall health: {}%", 
         if health_pct == 100 {
             health_pct.to_string().bright_green()
@@ -347,6 +267,65 @@ pub fn audit() -> Result<()> {
     Ok(())
 }
 
+/// Compare every recorded link with the filesystem and report drift
+pub fn verify() -> Result<()> {
+    let manifest = Manifest::load()?;
+    
+    if manifest.packages.is_empty() {
+        println!("  {} No stowed packages recorded", "⚠️".bright_yellow());
+        return Ok(());
+    }
+    
+    let mut drifted = 0;
+    let mut changed = 0;
+    
+    for (package, record) in &manifest.packages {
+        let issues: Vec<_> = record.links
+            .iter()
+            .filter_map(|link| link.check().map(|drift| (link, drift)))
+            .collect();
+    
+        if issues.is_empty() {
+            println!("  {} {} ({} links)", "✓".bright_green(), package.bright_green(), record.links.len());
+            continue;
+        }
+    
+        println!("  {} {} {}", "⚠️".bright_yellow(), package.bright_green(), format!("(stowed {})", record.stowed_at).bright_black());
+        for (link, drift) in issues {
+            if drift.is_drift() {
+                drifted += 1;
+                println!("    {} {} {}", "✗".bright_red(), link.target.display(), drift_label(&drift).bright_red());
+            } else {
+                changed += 1;
+                println!("    {} {} {}", "~".bright_blue(), link.target.display(), drift_label(&drift).bright_black());
+            }
+        }
+    }
+    
+    println!();
+    if drifted > 0 {
+        println!("  {} Drifted links: {}", "✗".bright_red(), drifted);
+        println!("  {} Re-stow the package to repair them", "💡".bright_blue());
+    } else {
+        println!("  {} No drift, every link matches the manifest", "✅".bright_green());
+    }
+    if changed > 0 {
+        println!("  {} Sources edited since stow: {}", "~".bright_blue(), changed);
+    }
+    
+    Ok(())
+}
+
+fn drift_label(drift: &Drift) -> String {
+    match drift {
+        Drift::Missing => "missing".to_string(),
+        Drift::Retargeted(dest) => format!("now points to {}", dest.display()),
+        Drift::Replaced => "replaced by a real file".to_string(),
+        Drift::Broken => "source is gone".to_string(),
+        Drift::SourceChanged => "source changed".to_string(),
+    }
+}
+
 /// Clean up broken and orphaned links
 pub fn clean(force: bool) -> Result<()> {
     let home = std::env::var("HOME").context("HOME not set")?;
@@ -364,7 +343,7 @@ pub fn clean(force: bool) -> Result<()> {
     
     for package in &packages {
         let links = find_all_package_links(&home, package)?;
-        
+    
         for link in links {
             if !link.exists() {
                 broken_links.push(link);
@@ -397,6 +376,7 @@ pub fn clean(force: bool) -> Result<()> {
     
     println!("\n  {} Removing broken links...", "🗑️".bright_blue());
     
+    let mut manifest = Manifest::load()?;
     let mut removed = 0;
     let mut errors = 0;
     
@@ -404,6 +384,7 @@ pub fn clean(force: bool) -> Result<()> {
         match fs::remove_file(link) {
             Ok(_) => {
                 println!("    {} {}", "✓".bright_green(), link.display());
+                manifest.remove_link(link);
                 removed += 1;
             }
             Err(e) => {
@@ -413,6 +394,8 @@ pub fn clean(force: bool) -> Result<()> {
         }
     }
     
+    manifest.save()?;
+    
     println!();
     if removed > 0 {
         println!("  {} Removed: {}", "✅".bright_green(), removed);
@@ -426,8 +409,9 @@ pub fn clean(force: bool) -> Result<()> {
 }
 
 /// Find all symlinks for a package (recursive)
-fn find_all_package_links(home: &str, package: &str) -> Result<Vec<PathBuf>> {
+pub(crate) fn find_all_package_links(home: &str, package: &str) -> Result<Vec<PathBuf>> {
     let home_path = PathBuf::from(home);
+    let package_dir = home_path.join("0-core/stow").join(package);
     let mut links = Vec::new();
     
     let search_paths = vec![
@@ -440,15 +424,19 @@ fn find_all_package_links(home: &str, package: &str) -> Result<Vec<PathBuf>> {
         if !search_path.exists() {
             continue;
         }
-        
-        find_links_in_dir(&search_path, package, &mut links)?;
+    
+        find_links_in_dir(&search_path, &package_dir, &mut links)?;
     }
     
+    // $HOME's walk already covers .config and .local
+    links.sort();
+    links.dedup();
+    
     Ok(links)
 }
 
-/// Helper to find links in a directory
-fn find_links_in_dir(dir: &Path, package: &str, links: &mut Vec<PathBuf>) -> Result<()> {
+/// Helper to find links in a directory into `package_dir`
+fn find_links_in_dir(dir: &Path, package_dir: &Path, links: &mut Vec<PathBuf>) -> Result<()> {
     if !dir.is_dir() {
         return Ok(());
     }
@@ -456,13 +444,11 @@ fn find_links_in_dir(dir: &Path, package: &str, links: &mut Vec<PathBuf>) -> Res
     for entry in fs::read_dir(dir)? {
         let entry = entry?;
         let path = entry.path();
-        
+    
         if path.is_symlink() {
-            if let Ok(target) = fs::read_link(&path) {
-                let target_str = target.to_string_lossy();
-                if target_str.contains(&format!("0-core/stow/{}", package)) {
-                    links.push(path);
-                }
+            // By component, so `nvim` doesn't claim `nvim-extra`'s links
+            if manifest::link_dest(&path).is_some_and(|dest| dest.starts_with(package_dir)) {
+                links.push(path);
             }
         } else if path.is_dir() {
             // Skip certain directories
@@ -470,9 +456,35 @@ fn find_links_in_dir(dir: &Path, package: &str, links: &mut Vec<PathBuf>) -> Res
             if name.starts_with('.') && name != ".config" && name != ".local" {
                 continue;
             }
-            find_links_in_dir(&path, package, links)?;
+            find_links_in_dir(&path, package_dir, links)?;
         }
     }
     
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::symlink;
+    
+    #[test]
+    fn test_find_package_links() {
+        let home = std::env::temp_dir().join(format!("faelight-link-find-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&home);
+        let stow = home.join("0-core/stow");
+        fs::create_dir_all(stow.join("nvim/.config/nvim")).unwrap();
+        fs::create_dir_all(stow.join("nvim-extra/.config/extra")).unwrap();
+        fs::create_dir_all(home.join(".config")).unwrap();
+        
+        symlink(stow.join("nvim/.config/nvim"), home.join(".config/nvim")).unwrap();
+        symlink(stow.join("nvim-extra/.config/extra"), home.join(".config/extra")).unwrap();
+        // Relative, as GNU stow makes them
+        symlink("0-core/stow/nvim/.vimrc", home.join(".vimrc")).unwrap();
+        
+        let links = find_all_package_links(&home.to_string_lossy(), "nvim").unwrap();
+        assert_eq!(links, vec![home.join(".config/nvim"), home.join(".vimrc")]);
+        
+        fs::remove_dir_all(&home).unwrap();
+    }
+}
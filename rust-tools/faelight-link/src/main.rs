@@ -1,10 +1,17 @@
 use anyhow::Result;
 use clap::{Parser, Subcommand};
 use colored::*;
+use std::path::PathBuf;
+use conflict::ConflictPolicy;
 
 mod package;
 mod link;
 mod conflict;
+mod plan;
+mod manifest;
+mod backup;
+mod config;
+mod template;
 
 #[derive(Parser)]
 #[command(name = "faelight-link")]
@@ -25,6 +32,18 @@ enum Commands {
         /// Skip verification prompts
         #[arg(long)]
         force: bool,
+        
+        /// Show the plan without changing anything
+        #[arg(long)]
+        dry_run: bool,
+        
+        /// Resolve conflicts without asking
+        #[arg(long, value_enum)]
+        on_conflict: Option<ConflictPolicy>,
+        
+        /// Print the result as JSON (never prompts)
+        #[arg(long)]
+        json: bool,
     },
     
     /// Unstow a package (remove symlinks)
@@ -33,14 +52,59 @@ enum Commands {
         package: String,
     },
     
+    /// Move existing files into a package and link them back
+    Adopt {
+        /// Package name (created if missing)
+        package: String,
+        
+        /// Files or directories to adopt
+        #[arg(required = true)]
+        paths: Vec<PathBuf>,
+        
+        /// Skip the confirmation prompt
+        #[arg(long)]
+        force: bool,
+        
+        /// Blast radius for a new package's .dotmeta (asked when omitted)
+        #[arg(long, value_parser = ["low", "medium", "high", "critical"])]
+        blast_radius: Option<String>,
+    },
+    
     /// List all packages
     List,
     
     /// Show status of links
-    Status,
+    Status {
+        /// Print the result as JSON
+        #[arg(long)]
+        json: bool,
+    },
     
     /// Audit link health (check for broken/orphaned links)
-    Audit,
+    Audit {
+        /// Print the result as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    
+    /// Report links that drifted from the manifest
+    Verify,
+    
+    /// Render templated package files (*.tmpl)
+    Render {
+        /// Package name (all packages when omitted)
+        package: Option<String>,
+        
+        /// Show what would change without writing
+        #[arg(long)]
+        diff: bool,
+    },
+    
+    /// List or restore backups made during stow
+    Backups {
+        #[command(subcommand)]
+        command: BackupsCommand,
+    },
     
     /// Clean up broken and orphaned links
     Clean {
@@ -50,31 +114,80 @@ enum Commands {
     },
 }
 
+#[derive(Subcommand)]
+enum BackupsCommand {
+    /// List all backups
+    List,
+    
+    /// Put a backup back where it was
+    Restore {
+        /// Backup id (see `backups list`)
+        id: String,
+        
+        /// Replace a link at the original location without asking
+        #[arg(long)]
+        force: bool,
+    },
+}
+
 fn main() -> Result<()> {
     let cli = Cli::parse();
     
-    println!("{}", "🔗 faelight-link v0.1.0".bright_blue().bold());
+    // Keep stdout parseable
+    let json = matches!(
+        cli.command,
+        Commands::Stow { json: true, .. } | Commands::Status { json: true } | Commands::Audit { json: true }
+    );
+    if !json {
+        println!("{}", "🔗 faelight-link v0.1.0".bright_blue().bold());
+    }
     
     match cli.command {
-        Commands::Stow { package, force } => {
-            println!("📦 Stowing package: {}", package.bright_green());
-            package::stow(&package, force)?;
+        Commands::Stow { package, force, dry_run, on_conflict, json } => {
+            if !json {
+                println!("📦 Stowing package: {}", package.bright_green());
+            }
+            package::stow(&package, force, dry_run, on_conflict, json)?;
         }
         Commands::Unstow { package } => {
             println!("📦 Unstowing package: {}", package.bright_yellow());
             package::unstow(&package)?;
         }
+        Commands::Adopt { package, paths, force, blast_radius } => {
+            println!("📥 Adopting into package: {}", package.bright_green());
+            package::adopt(&package, &paths, force, blast_radius.as_deref())?;
+        }
         Commands::List => {
             println!("📋 Available packages:");
             package::list()?;
         }
-        Commands::Status => {
-            println!("📊 Link status:");
-            link::status()?;
+        Commands::Status { json } => {
+            if !json {
+                println!("📊 Link status:");
+            }
+            link::status(json)?;
+        }
+        Commands::Audit { json } => {
+            if !json {
+                println!("📊 Auditing link health:");
+            }
+            link::audit(json)?;
+        }
+        Commands::Verify => {
+            println!("🔍 Verifying links against the manifest:");
+            link::verify()?;
+        }
+        Commands::Render { package, diff } => {
+            println!("📝 Rendering templates:");
+            template::render_cmd(package.as_deref(), diff)?;
+        }
+        Commands::Backups { command: BackupsCommand::List } => {
+            println!("💾 Backups:");
+            backup::print_list()?;
         }
-        Commands::Audit => {
-            println!("📊 Auditing link health:");
-            link::audit()?;
+        Commands::Backups { command: BackupsCommand::Restore { id, force } } => {
+            println!("💾 Restoring backup:");
+            backup::restore_interactive(&id, force)?;
         }
         Commands::Clean { force } => {
             println!("🧹 Cleaning up broken links:");
@@ -0,0 +1,346 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use crate::plan::{Action, Plan, Unfold};
+
+/// Every link faelight-link created, grouped by package
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Manifest {
+    #[serde(default)]
+    pub packages: BTreeMap<String, PackageRecord>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct PackageRecord {
+    pub stowed_at: String,
+    #[serde(default)]
+    pub links: Vec<LinkRecord>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LinkRecord {
+    pub target: PathBuf,
+    pub source: PathBuf,
+    /// Links a whole directory
+    #[serde(default)]
+    pub folded: bool,
+    pub created_at: String,
+    /// Source content hash at stow time (files only)
+    pub hash: Option<String>,
+}
+
+/// How a recorded link differs from the filesystem
+#[derive(Debug, PartialEq)]
+pub enum Drift {
+    /// The link was deleted
+    Missing,
+    /// The link now points somewhere else
+    Retargeted(PathBuf),
+    /// A real file or directory took the link's place
+    Replaced,
+    /// The link exists but its package file is gone
+    Broken,
+    /// The package file was edited since it was stowed
+    SourceChanged,
+}
+
+impl Drift {
+    /// Whether the link itself is no longer what stow created
+    pub fn is_drift(&self) -> bool {
+        !matches!(self, Drift::SourceChanged)
+    }
+}
+
+impl LinkRecord {
+    pub fn new(source: &Path, target: &Path, folded: bool) -> Self {
+        Self {
+            target: target.to_path_buf(),
+            source: source.to_path_buf(),
+            folded,
+            created_at: now(),
+            hash: if folded { None } else { hash_file(source) },
+        }
+    }
+    
+    /// Whether the target is still the link stow created
+    pub fn is_linked(&self) -> bool {
+        link_dest(&self.target).is_some_and(|dest| dest == self.source)
+    }
+    
+    /// Compare the record with the filesystem
+    pub fn check(&self) -> Option<Drift> {
+        let metadata = match fs::symlink_metadata(&self.target) {
+            Ok(m) => m,
+            Err(_) => return Some(Drift::Missing),
+        };
+    
+        if !metadata.is_symlink() {
+            return Some(Drift::Replaced);
+        }
+    
+        match link_dest(&self.target) {
+            Some(dest) if dest != self.source => return Some(Drift::Retargeted(dest)),
+            None => return Some(Drift::Missing),
+            _ => {}
+        }
+    
+        if !self.source.exists() {
+            return Some(Drift::Broken);
+        }
+    
+        match &self.hash {
+            Some(hash) if hash_file(&self.source).as_ref() != Some(hash) => Some(Drift::SourceChanged),
+            _ => None,
+        }
+    }
+}
+
+impl Manifest {
+    /// Location of the manifest file
+    pub fn path() -> Result<PathBuf> {
+        let home = std::env::var("HOME").context("HOME not set")?;
+        Ok(PathBuf::from(home).join(".local/state/faelight-link/manifest.toml"))
+    }
+    
+    /// Load the manifest. Without one, existing links are adopted by scanning $HOME.
+    pub fn load() -> Result<Self> {
+        let path = Self::path()?;
+    
+        if !path.exists() {
+            let manifest = Self::scan()?;
+            manifest.save()?;
+            return Ok(manifest);
+        }
+    
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("Cannot read {}", path.display()))?;
+        toml::from_str(&content).with_context(|| format!("Invalid manifest {}", path.display()))
+    }
+    
+    /// Write the manifest atomically
+    pub fn save(&self) -> Result<()> {
+        let path = Self::path()?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+    
+        let tmp = path.with_extension("toml.tmp");
+        fs::write(&tmp, toml::to_string_pretty(self)?)?;
+        fs::rename(&tmp, &path)?;
+        Ok(())
+    }
+    
+    /// Build a manifest from the links currently in $HOME
+    fn scan() -> Result<Self> {
+        let home = std::env::var("HOME").context("HOME not set")?;
+        let stow_dir = crate::package::get_stow_dir()?;
+        let mut manifest = Self::default();
+    
+        for package in crate::package::discover_packages(&stow_dir)? {
+            let records: Vec<LinkRecord> = crate::link::find_all_package_links(&home, &package)?
+                .into_iter()
+                .filter_map(|link| {
+                    let source = link_dest(&link)?;
+                    Some(LinkRecord::new(&source, &link, source.is_dir()))
+                })
+                .collect();
+    
+            if !records.is_empty() {
+                manifest.record(&package, records);
+            }
+        }
+    
+        Ok(manifest)
+    }
+    
+    pub fn links(&self, package: &str) -> &[LinkRecord] {
+        self.packages.get(package).map(|p| p.links.as_slice()).unwrap_or(&[])
+    }
+    
+    /// Add or replace link records of a package. A target has one owner,
+    /// so a link taken over from another package leaves that one's records.
+    pub fn record(&mut self, package: &str, links: Vec<LinkRecord>) {
+        for (name, record) in self.packages.iter_mut() {
+            if name != package {
+                record.links.retain(|l| !links.iter().any(|link| link.target == l.target));
+            }
+        }
+        self.packages.retain(|name, record| name == package || !record.links.is_empty());
+    
+        let entry = self.packages.entry(package.to_string()).or_default();
+        entry.stowed_at = now();
+    
+        for link in links {
+            match entry.links.iter_mut().find(|l| l.target == link.target) {
+                // Keep the original creation time of links that were already there
+                Some(existing) if existing.source == link.source => existing.hash = link.hash,
+                Some(existing) => *existing = link,
+                None => entry.links.push(link),
+            }
+        }
+        entry.links.sort_by(|a, b| a.target.cmp(&b.target));
+    }
+    
+    /// Record the links an applied plan created
+    pub fn record_plan(&mut self, package: &str, plan: &Plan, stow_dir: &Path) {
+        for unfold in &plan.unfolds {
+            self.record_unfold(unfold, stow_dir);
+        }
+    
+        let links = plan.links
+            .iter()
+            .filter(|l| matches!(l.action, Action::Create | Action::AlreadyLinked | Action::Backup | Action::Overwrite | Action::Adopt))
+            .map(|l| LinkRecord::new(&l.source, &l.target, l.folded))
+            .collect();
+        self.record(package, links);
+    }
+    
+    /// The owner of an unfolded link now has one link per entry
+    fn record_unfold(&mut self, unfold: &Unfold, stow_dir: &Path) {
+        let Some(owner) = package_of(&unfold.source, stow_dir) else {
+            return;
+        };
+    
+        self.remove_link(&unfold.target);
+        let links = fs::read_dir(&unfold.source)
+            .map(|entries| {
+                entries
+                    .flatten()
+                    .map(|e| {
+                        let source = e.path();
+                        let folded = source.is_dir();
+                        LinkRecord::new(&source, &unfold.target.join(e.file_name()), folded)
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        self.record(&owner, links);
+    }
+    
+    /// Replace per-entry records below `dir` with one folded link to `source`
+    pub fn record_refold(&mut self, dir: &Path, source: &Path, stow_dir: &Path) {
+        let Some(owner) = package_of(source, stow_dir) else {
+            return;
+        };
+    
+        if let Some(record) = self.packages.get_mut(&owner) {
+            record.links.retain(|l| l.target.parent() != Some(dir));
+        }
+        self.record(&owner, vec![LinkRecord::new(source, dir, true)]);
+    }
+    
+    /// Forget a single link, whichever package owns it
+    pub fn remove_link(&mut self, target: &Path) {
+        for record in self.packages.values_mut() {
+            record.links.retain(|l| l.target != target);
+        }
+        self.packages.retain(|_, record| !record.links.is_empty());
+    }
+    
+    /// Forget a link of one package, leaving other packages' records alone
+    pub fn remove_package_link(&mut self, package: &str, target: &Path) {
+        if let Some(record) = self.packages.get_mut(package) {
+            record.links.retain(|l| l.target != target);
+            if record.links.is_empty() {
+                self.packages.remove(package);
+            }
+        }
+    }
+}
+
+/// Package owning a path inside the stow directory
+fn package_of(source: &Path, stow_dir: &Path) -> Option<String> {
+    source
+        .strip_prefix(stow_dir)
+        .ok()?
+        .components()
+        .next()
+        .map(|c| c.as_os_str().to_string_lossy().to_string())
+}
+
+/// Where a link points, with relative links (e.g. from GNU stow) resolved
+pub(crate) fn link_dest(link: &Path) -> Option<PathBuf> {
+    let dest = fs::read_link(link).ok()?;
+    let dest = match link.parent() {
+        Some(parent) if dest.is_relative() => parent.join(dest),
+        _ => dest,
+    };
+    
+    // Collapse the `..` components so the path compares equal to the source
+    let mut resolved = PathBuf::new();
+    for component in dest.components() {
+        match component {
+            Component::ParentDir => {
+                resolved.pop();
+            }
+            Component::CurDir => {}
+            other => resolved.push(other),
+        }
+    }
+    Some(resolved)
+}
+
+fn now() -> String {
+    chrono::Local::now().to_rfc3339()
+}
+
+fn hash_file(path: &Path) -> Option<String> {
+    let content = fs::read(path).ok()?;
+    let mut hasher = Sha256::new();
+    hasher.update(&content);
+    Some(format!("sha256:{:x}", hasher.finalize()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::symlink;
+    
+    #[test]
+    fn test_check_drift() {
+        let root = std::env::temp_dir().join(format!("faelight-link-manifest-{}", std::process::id()));
+        fs::create_dir_all(&root).unwrap();
+        let source = root.join("source");
+        let target = root.join("target");
+        fs::write(&source, "v1").unwrap();
+    
+        let record = LinkRecord::new(&source, &target, false);
+        assert_eq!(record.check(), Some(Drift::Missing));
+    
+        symlink(&source, &target).unwrap();
+        assert_eq!(record.check(), None);
+    
+        fs::write(&source, "v2").unwrap();
+        assert_eq!(record.check(), Some(Drift::SourceChanged));
+    
+        fs::remove_file(&target).unwrap();
+        symlink(&root, &target).unwrap();
+        assert_eq!(record.check(), Some(Drift::Retargeted(root.clone())));
+    
+        fs::remove_file(&target).unwrap();
+        fs::write(&target, "real").unwrap();
+        assert_eq!(record.check(), Some(Drift::Replaced));
+    
+        fs::remove_dir_all(&root).unwrap();
+    }
+    
+    #[test]
+    fn test_link_has_one_owner() {
+        let target = PathBuf::from("/home/user/.config/app/config");
+        let mut manifest = Manifest::default();
+        manifest.record("a", vec![LinkRecord::new(Path::new("/stow/a/config"), &target, false)]);
+        manifest.record("b", vec![LinkRecord::new(Path::new("/stow/b/config"), &target, false)]);
+        assert!(manifest.links("a").is_empty());
+        assert!(!manifest.packages.contains_key("a"));
+        assert_eq!(manifest.links("b")[0].source, PathBuf::from("/stow/b/config"));
+    
+        // Unstowing a stale package leaves the new owner's record
+        manifest.remove_package_link("a", &target);
+        assert_eq!(manifest.links("b").len(), 1);
+        manifest.remove_package_link("b", &target);
+        assert!(manifest.packages.is_empty());
+    }
+}
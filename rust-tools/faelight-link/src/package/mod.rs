@@ -1,8 +1,14 @@
 use anyhow::{Context, Result};
 use colored::*;
+use serde::Serialize;
 use std::fs;
 use std::path::{Path, PathBuf};
 use walkdir::WalkDir;
+use crate::config::{PackageConfig, TargetRoot};
+use crate::conflict::ConflictPolicy;
+use crate::manifest::Manifest;
+use crate::plan::{self, Plan, PlannedLink, Summary};
+use crate::template::{self, Rendered};
 
 /// Discover all packages in the stow directory
 pub fn discover_packages(stow_dir: &Path) -> Result<Vec<String>> {
@@ -15,7 +21,7 @@ pub fn discover_packages(stow_dir: &Path) -> Result<Vec<String>> {
     for entry in fs::read_dir(stow_dir)? {
         let entry = entry?;
         let path = entry.path();
-        
+    
         if path.is_dir() {
             if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
                 // Skip hidden directories and .dotmeta
@@ -46,14 +52,21 @@ pub fn list() -> Result<()> {
         return Ok(());
     }
     
+    let home = std::env::var("HOME").context("HOME not set")?;
+    
     for (i, package) in packages.iter().enumerate() {
         let pkg_path = stow_dir.join(package);
-        let file_count = count_files(&pkg_path)?;
+        let config = PackageConfig::load(&pkg_path)?;
+        let file_count = count_files(&pkg_path, &config)?;
+        let note = match config.inactive_reason(Path::new(&home)) {
+            Some(reason) => format!(" - inactive, {}", reason),
+            None => String::new(),
+        };
         println!(
-            "  {} {} {} files",
+            "  {} {} {}",
             format!("{:2}.", i + 1).bright_black(),
             package.bright_green(),
-            format!("({} files)", file_count).bright_black()
+            format!("({} files{})", file_count, note).bright_black()
         );
     }
     
@@ -61,18 +74,22 @@ pub fn list() -> Result<()> {
     Ok(())
 }
 
-/// Count files in a package directory
-fn count_files(dir: &Path) -> Result<usize> {
+/// Count the files of a package that get linked
+fn count_files(dir: &Path, config: &PackageConfig) -> Result<usize> {
     let count = WalkDir::new(dir)
+        .min_depth(1)
         .into_iter()
+        .filter_entry(|e| e.path().strip_prefix(dir).is_ok_and(|rel| !config.is_ignored(rel)))
         .filter_map(|e| e.ok())
         .filter(|e| e.file_type().is_file())
         .count();
     Ok(count)
 }
 
-/// Stow a package
-pub fn stow(package: &str, force: bool) -> Result<()> {
+/// Stow a package: plan every link first, then apply the plan atomically.
+/// Conflicts use `on_conflict`, then the package default, and are only
+/// prompted for when neither is set and `force` isn't given.
+pub fn stow(package: &str, force: bool, dry_run: bool, on_conflict: Option<ConflictPolicy>, json: bool) -> Result<()> {
     let stow_dir = get_stow_dir()?;
     let pkg_path = stow_dir.join(package);
     
@@ -80,23 +97,74 @@ pub fn stow(package: &str, force: bool) -> Result<()> {
         anyhow::bail!("Package '{}' not found in {}", package, stow_dir.display());
     }
     
+    let config = PackageConfig::load(&pkg_path)?;
+    let policy = on_conflict.or(config.on_conflict);
+    let home = PathBuf::from(std::env::var("HOME").context("HOME not set")?);
+    let root = config.target_root(&home);
+    let inactive = config.inactive_reason(&home);
+    
+    if json {
+        let mut report = StowReport::new(package, dry_run, &root);
+        report.inactive = inactive;
+        if report.inactive.is_none() {
+            let rendered = template::render_package(&pkg_path, &home, &config, &template::variables(&home)?)?;
+            let plan = Plan::build(&pkg_path, &root.path, &home, &stow_dir, &config)?;
+            stow_json(&mut report, plan, &rendered, policy, &stow_dir);
+        }
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        if !report.success {
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+    
     println!("  Package: {}", pkg_path.display().to_string().bright_black());
     
-    // Discover files to link
-    let files = discover_package_files(&pkg_path)?;
-    println!("  Files to link: {}", files.len());
+    if let Some(reason) = inactive {
+        println!("  {} Skipping, package is {}", "⊘".bright_yellow(), reason);
+        return Ok(());
+    }
+    
+    println!("  Target:  {}", root.path.display().to_string().bright_black());
+    if let Some(real) = &root.staged_for {
+        println!("  {} Staging links for {} (needs a privileged install)", "📦".bright_blue(), real.display());
+    }
+    
+    let file_count = count_files(&pkg_path, &config)?;
+    println!("  Files to link: {}", file_count);
     
-    if files.is_empty() {
+    if file_count == 0 {
         println!("  {}", "No files to link".bright_yellow());
         return Ok(());
     }
     
-    // Show first few files
-    for (_i, file) in files.iter().take(5).enumerate() {
-        println!("    {} {}", "→".bright_black(), file.display().to_string().bright_black());
+    let rendered = template::render_package(&pkg_path, &home, &config, &template::variables(&home)?)?;
+    let mut plan = Plan::build(&pkg_path, &root.path, &home, &stow_dir, &config)?;
+    
+    println!();
+    plan.print();
+    
+    let stale = rendered.iter().filter(|r| r.is_stale()).count();
+    if stale > 0 {
+        println!("  {} {} templates to render", "📝".bright_blue(), stale);
+    }
+    
+    if dry_run {
+        println!("\n  {} Dry run, nothing was changed", "💡".bright_blue());
+        return Ok(());
     }
-    if files.len() > 5 {
-        println!("    {} {} more...", "→".bright_black(), (files.len() - 5).to_string().bright_black());
+    
+    // Generated files only, so they are written before any prompt
+    template::write(&rendered)?;
+    
+    if plan.is_noop() {
+        // Links stowed before the manifest existed get recorded here
+        let mut manifest = Manifest::load()?;
+        manifest.record_plan(package, &plan, &stow_dir);
+        manifest.save()?;
+    
+        println!("\n  {} Package already stowed", "✅".bright_green());
+        return Ok(());
     }
     
     // Ask for confirmation
@@ -112,28 +180,103 @@ pub fn stow(package: &str, force: bool) -> Result<()> {
         }
     }
     
+    // Nobody to ask: keep whatever is in the way
+    let policy = policy.or(force.then_some(ConflictPolicy::Backup));
+    
+    // Resolve conflicts before anything is touched, so Quit leaves no trace
+    if !plan.resolve_conflicts(policy)? {
+        println!("\n  {} Operation cancelled by user", "⚠️".bright_yellow());
+        return Ok(());
+    }
+    
     println!("\n  {} Creating links...", "🔗".bright_blue());
     
-    // Create links (next step)
-    crate::link::create_links(&pkg_path, &files)?;
+    let summary = plan.apply()?;
+    summary.print();
+    
+    let mut manifest = Manifest::load()?;
+    manifest.record_plan(package, &plan, &stow_dir);
+    manifest.save()?;
+    
+    if let Some(real) = &root.staged_for {
+        println!("\n  {} Install the staged links with:", "💡".bright_blue());
+        println!("    sudo cp -a {}/. {}/", root.path.display(), real.display());
+    }
     
     println!("\n  {} Package stowed successfully!", "✅".bright_green());
     Ok(())
 }
 
-/// Discover all files in a package
-fn discover_package_files(pkg_dir: &Path) -> Result<Vec<PathBuf>> {
-    let mut files = Vec::new();
-    
-    for entry in WalkDir::new(pkg_dir)
-        .into_iter()
-        .filter_map(|e| e.ok())
-        .filter(|e| e.file_type().is_file())
-    {
-        files.push(entry.path().to_path_buf());
+/// Result of a `stow --json` run
+#[derive(Serialize)]
+struct StowReport<'a> {
+    package: &'a str,
+    dry_run: bool,
+    success: bool,
+    /// Directory the links were created in
+    target: PathBuf,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    staged_for: Option<PathBuf>,
+    /// Why the package was skipped on this machine
+    #[serde(skip_serializing_if = "Option::is_none")]
+    inactive: Option<String>,
+    /// Planned links (dry run only)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    plan: Vec<PlannedLink>,
+    /// Template outputs (re)rendered, or to be rendered on a dry run
+    rendered: Vec<PathBuf>,
+    #[serde(flatten)]
+    summary: Summary,
+    errors: Vec<String>,
+}
+
+impl<'a> StowReport<'a> {
+    fn new(package: &'a str, dry_run: bool, root: &TargetRoot) -> Self {
+        Self {
+            package,
+            dry_run,
+            success: true,
+            target: root.path.clone(),
+            staged_for: root.staged_for.clone(),
+            inactive: None,
+            plan: Vec::new(),
+            rendered: Vec::new(),
+            summary: Summary::default(),
+            errors: Vec::new(),
+        }
+    }
+}
+
+/// Non-interactive stow filling in `report`
+fn stow_json(report: &mut StowReport, mut plan: Plan, rendered: &[Rendered], policy: Option<ConflictPolicy>, stow_dir: &Path) {
+    let stale: Vec<PathBuf> = rendered.iter().filter(|r| r.is_stale()).map(|r| r.output.clone()).collect();
+    
+    if report.dry_run {
+        report.plan = plan.links;
+        report.rendered = stale;
+        return;
     }
     
-    Ok(files)
+    let result = template::write(rendered)
+        .and_then(|_| plan.resolve_conflicts(Some(policy.unwrap_or(ConflictPolicy::Backup))))
+        .and_then(|_| plan.apply())
+        .and_then(|summary| {
+            let mut manifest = Manifest::load()?;
+            manifest.record_plan(report.package, &plan, stow_dir);
+            manifest.save()?;
+            Ok(summary)
+        });
+    
+    match result {
+        Ok(summary) => {
+            report.summary = summary;
+            report.rendered = stale;
+        }
+        Err(e) => {
+            report.success = false;
+            report.errors.push(format!("{:#}", e));
+        }
+    }
 }
 
 /// Unstow a package (remove symlinks)
@@ -147,18 +290,44 @@ pub fn unstow(package: &str) -> Result<()> {
     
     println!("  Package: {}", pkg_path.display().to_string().bright_black());
     
-    // Find all symlinks for this package
-    let home = std::env::var("HOME").context("HOME not set")?;
-    let links = find_package_links(&home, package)?;
+    let home = PathBuf::from(std::env::var("HOME").context("HOME not set")?);
+    let root = PackageConfig::load(&pkg_path)?.target_root(&home);
+    let mut manifest = Manifest::load()?;
+    let records = manifest.links(package).to_vec();
+    
+    if records.is_empty() {
+        println!("  {} No symlinks recorded for this package", "⚠️".bright_yellow());
+        return Ok(());
+    }
+    
+    // Only remove links that still point where stow put them
+    let (links, drifted): (Vec<_>, Vec<_>) = records
+        .into_iter()
+        .partition(|r| r.is_linked());
+    
+    for record in &drifted {
+        println!(
+            "    {} {} {}",
+            "⚠️".bright_yellow(),
+            record.target.display(),
+            "(changed since stow, left alone)".bright_black()
+        );
+        manifest.remove_package_link(package, &record.target);
+    }
     
     if links.is_empty() {
-        println!("  {} No symlinks found for this package", "⚠️".bright_yellow());
+        manifest.save()?;
+        println!("  {} No symlinks left to remove", "⚠️".bright_yellow());
         return Ok(());
     }
     
     println!("  Found {} symlinks to remove:", links.len());
     for link in &links {
-        println!("    {} {}", "→".bright_black(), link.display().to_string().bright_black());
+        if link.folded {
+            println!("    {} {}/ {}", "→".bright_black(), link.target.display().to_string().bright_black(), "(folded)".bright_black());
+        } else {
+            println!("    {} {}", "→".bright_black(), link.target.display().to_string().bright_black());
+        }
     }
     
     // Ask for confirmation
@@ -178,22 +347,46 @@ pub fn unstow(package: &str) -> Result<()> {
     let mut errors = 0;
     
     for link in &links {
-        match fs::remove_file(link) {
+        match fs::remove_file(&link.target) {
             Ok(_) => {
-                println!("    {} {}", "✓".bright_green(), link.display());
+                println!("    {} {}", "✓".bright_green(), link.target.display());
+                manifest.remove_package_link(package, &link.target);
                 removed += 1;
             }
             Err(e) => {
-                println!("    {} {} ({})", "✗".bright_red(), link.display(), e);
+                println!("    {} {} ({})", "✗".bright_red(), link.target.display(), e);
                 errors += 1;
             }
         }
     }
     
+    // Directories we shared with another package may be foldable again
+    let mut parents: Vec<PathBuf> = links.iter().filter_map(|l| l.target.parent().map(Path::to_path_buf)).collect();
+    parents.sort_by_key(|p| std::cmp::Reverse(p.components().count()));
+    parents.dedup();
+    
+    let mut refolded = 0;
+    for dir in parents {
+        match plan::refold(&dir, &root.path, &home, &stow_dir) {
+            Ok(Some(source)) => {
+                println!("    {} {}/", "⇱".bright_blue(), dir.display());
+                manifest.record_refold(&dir, &source, &stow_dir);
+                refolded += 1;
+            }
+            Ok(None) => {}
+            Err(e) => println!("    {} {} (refold failed: {})", "⚠️".bright_yellow(), dir.display(), e),
+        }
+    }
+    
+    manifest.save()?;
+    
     println!();
     if removed > 0 {
         println!("  {} Removed: {}", "✅".bright_green(), removed);
     }
+    if refolded > 0 {
+        println!("  {} Refolded: {}", "⇱".bright_blue(), refolded);
+    }
     if errors > 0 {
         println!("  {} Errors: {}", "✗".bright_red(), errors);
     }
@@ -202,55 +395,125 @@ pub fn unstow(package: &str) -> Result<()> {
     Ok(())
 }
 
-/// Find all symlinks for a package
-fn find_package_links(home: &str, package: &str) -> Result<Vec<PathBuf>> {
-    let home_path = PathBuf::from(home);
-    let mut links = Vec::new();
-    
-    let search_paths = vec![
-        home_path.clone(),
-        home_path.join(".config"),
-        home_path.join(".local"),
-    ];
-    
-    for search_path in search_paths {
-        if !search_path.exists() {
-            continue;
-        }
-        
-        find_links_recursive(&search_path, package, &mut links)?;
+/// Blast radius levels understood by core-protect
+const BLAST_RADII: &[(&str, &str)] = &[
+    ("low", "cosmetic, easy to fix"),
+    ("medium", "important but not essential"),
+    ("high", "breaks a core workflow"),
+    ("critical", "system unusable if broken"),
+];
+
+/// Move existing files into a package, leaving links in their place. New
+/// packages get a starter .dotmeta.
+pub fn adopt(package: &str, paths: &[PathBuf], force: bool, blast_radius: Option<&str>) -> Result<()> {
+    let lock = faelight_zone::lock_state();
+    if lock.locked {
+        let by = lock.info.map(|info| format!(" {}", info)).unwrap_or_default();
+        anyhow::bail!("Core is locked{}, run 'core-protect unlock' first", by);
     }
     
-    Ok(links)
-}
-
-/// Recursively find symlinks
-fn find_links_recursive(dir: &Path, package: &str, links: &mut Vec<PathBuf>) -> Result<()> {
-    if !dir.is_dir() {
-        return Ok(());
+    let stow_dir = get_stow_dir()?;
+    let pkg_path = stow_dir.join(package);
+    let home = PathBuf::from(std::env::var("HOME").context("HOME not set")?);
+    let config = PackageConfig::load(&pkg_path)?;
+    let root = config.target_root(&home);
+    
+    if let Some(real) = &root.staged_for {
+        anyhow::bail!("Package '{}' targets {}, only files below $HOME can be adopted", package, real.display());
     }
     
-    for entry in fs::read_dir(dir)? {
-        let entry = entry?;
-        let path = entry.path();
-        
-        if path.is_symlink() {
-            if let Ok(target) = fs::read_link(&path) {
-                let target_str = target.to_string_lossy();
-                if target_str.contains(&format!("0-core/stow/{}", package)) {
-                    links.push(path);
-                }
-            }
-        } else if path.is_dir() {
-            // Skip certain directories
-            let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
-            if name.starts_with('.') && name != ".config" && name != ".local" {
-                continue;
-            }
-            find_links_recursive(&path, package, links)?;
+    println!("  Package: {}", pkg_path.display().to_string().bright_black());
+    if !pkg_path.exists() {
+        println!("  {} New package", "✨".bright_blue());
+    }
+    
+    let paths = paths.iter().map(|p| absolute(p)).collect::<Result<Vec<_>>>()?;
+    let plan = Plan::adopt(&paths, &pkg_path, &root.path, &stow_dir, &config)?;
+    
+    println!();
+    plan.print();
+    
+    if !force {
+        use dialoguer::Confirm;
+        if !Confirm::new()
+            .with_prompt("Move these into the package?")
+            .default(true)
+            .interact()?
+        {
+            println!("  {}", "Cancelled".bright_yellow());
+            return Ok(());
         }
     }
     
+    // Asked up front, so a cancelled prompt leaves nothing behind
+    let dotmeta = pkg_path.join(".dotmeta");
+    let radius = if dotmeta.exists() {
+        None
+    } else {
+        Some(match blast_radius {
+            Some(radius) => radius.to_string(),
+            None => prompt_blast_radius()?,
+        })
+    };
+    
+    println!("\n  {} Adopting...", "📥".bright_blue());
+    
+    let summary = plan.apply()?;
+    summary.print();
+    
+    let mut manifest = Manifest::load()?;
+    manifest.record_plan(package, &plan, &stow_dir);
+    manifest.save()?;
+    
+    if let Some(radius) = radius {
+        fs::write(&dotmeta, starter_dotmeta(package, &radius))
+            .with_context(|| format!("Cannot write {}", dotmeta.display()))?;
+        println!("  {} Created {} (blast_radius = {})", "📝".bright_blue(), dotmeta.display(), radius);
+    }
+    
+    println!("\n  {} Adopted into '{}', commit it in ~/0-core", "✅".bright_green(), package);
     Ok(())
 }
 
+/// Absolute path with symlinked parents resolved, the last component kept as is
+fn absolute(path: &Path) -> Result<PathBuf> {
+    let path = std::path::absolute(path)?;
+    let name = path.file_name().with_context(|| format!("Cannot adopt {}", path.display()))?;
+    let parent = path.parent().unwrap_or(Path::new("/"));
+    let parent = fs::canonicalize(parent).with_context(|| format!("{} does not exist", path.display()))?;
+    Ok(parent.join(name))
+}
+
+fn prompt_blast_radius() -> Result<String> {
+    use dialoguer::Select;
+    let items: Vec<String> = BLAST_RADII.iter().map(|(level, what)| format!("{:<8} ({})", level, what)).collect();
+    
+    let choice = Select::new()
+        .with_prompt("Blast radius of this package?")
+        .items(&items)
+        .default(0)
+        .interact()?;
+    Ok(BLAST_RADII[choice].0.to_string())
+}
+
+fn starter_dotmeta(package: &str, blast_radius: &str) -> String {
+    // Values go through toml so quotes and backslashes in a name stay a string
+    let quote = |value: &str| toml::Value::String(value.to_string()).to_string();
+    format!(
+        "[package]\nname = {}\ndescription = \"\"\nversion = \"1.0.0\"\nblast_radius = {}\n\n[blast_impact]\nfailure_modes = []\n",
+        quote(package), quote(blast_radius)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_starter_dotmeta_escapes_name() {
+        let name = "odd\"name\\ = [x]";
+        let meta: toml::Table = toml::from_str(&starter_dotmeta(name, "low")).unwrap();
+        assert_eq!(meta["package"]["name"].as_str(), Some(name));
+        assert_eq!(meta["package"]["blast_radius"].as_str(), Some("low"));
+    }
+}
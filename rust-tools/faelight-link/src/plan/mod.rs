@@ -0,0 +1,751 @@
+use anyhow::{Context, Result};
+use colored::*;
+use serde::Serialize;
+use std::fs;
+use std::os::unix::fs::symlink;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+use crate::backup;
+use crate::config::PackageConfig;
+use crate::manifest;
+use crate::template;
+use crate::conflict::{Conflict, ConflictAction, ConflictPolicy, resolve_conflict, move_path};
+
+/// What stow will do for a single package file
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    /// Create a new symlink
+    Create,
+    /// Target already links to this package file
+    AlreadyLinked,
+    /// Something else exists at the target (not resolved yet)
+    Conflict,
+    /// Move the existing target to the backup area, then link
+    Backup,
+    /// Replace the existing target
+    Overwrite,
+    /// Leave the existing target alone
+    Skip,
+    /// Move the existing target into the package, then link it back
+    Adopt,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PlannedLink {
+    pub source: PathBuf,
+    pub target: PathBuf,
+    #[serde(skip)]
+    pub relative: PathBuf,
+    pub action: Action,
+    /// Links a whole directory
+    pub folded: bool,
+}
+
+/// A folded directory link of another package that has to become a real
+/// directory of per-entry links before this package can share it
+#[derive(Debug)]
+pub struct Unfold {
+    pub target: PathBuf,
+    /// Package directory the folded link points to
+    pub source: PathBuf,
+}
+
+/// Every change a stow will make, computed before touching the filesystem
+#[derive(Debug)]
+pub struct Plan {
+    pub links: Vec<PlannedLink>,
+    /// Missing directories that are never folded, shallowest first
+    pub dirs: Vec<PathBuf>,
+    /// Folded links to split, shallowest first
+    pub unfolds: Vec<Unfold>,
+}
+
+/// What an applied plan changed
+#[derive(Debug, Default, Serialize)]
+pub struct Summary {
+    pub created: Vec<PathBuf>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub adopted: Vec<PathBuf>,
+    pub backed_up: Vec<BackedUp>,
+    pub overwritten: Vec<PathBuf>,
+    pub skipped: Vec<PathBuf>,
+    pub unchanged: usize,
+    pub folded: usize,
+    pub unfolded: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BackedUp {
+    pub target: PathBuf,
+    /// Id for `faelight-link backups restore`
+    pub backup: String,
+}
+
+impl Plan {
+    /// Plan links for the package at `pkg_dir` into `root`, skipping files
+    /// the package config ignores. Directories missing from `root` are
+    /// linked whole (folded), templates link to their rendered output.
+    pub fn build(pkg_dir: &Path, root: &Path, home: &Path, stow_dir: &Path, config: &PackageConfig) -> Result<Self> {
+        let mut planner = Planner {
+            home,
+            root,
+            pkg_dir,
+            package: pkg_dir.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default(),
+            stow_dir,
+            config,
+            plan: Plan {
+                links: Vec::new(),
+                dirs: Vec::new(),
+                unfolds: Vec::new(),
+            },
+        };
+    
+        // A root like ~/.local/share may not exist yet
+        let mut missing: Vec<PathBuf> = root.ancestors().take_while(|d| !d.exists()).map(Path::to_path_buf).collect();
+        missing.reverse();
+        planner.plan.dirs.extend(missing);
+    
+        planner.plan_dir(pkg_dir, root, None)?;
+        Ok(planner.plan)
+    }
+    
+    /// Plan moving existing `paths` below `root` into the package at
+    /// `pkg_dir`, each replaced by a link to its new place
+    pub fn adopt(paths: &[PathBuf], pkg_dir: &Path, root: &Path, stow_dir: &Path, config: &PackageConfig) -> Result<Self> {
+        let mut plan = Plan {
+            links: Vec::new(),
+            dirs: Vec::new(),
+            unfolds: Vec::new(),
+        };
+    
+        for path in paths {
+            if path.is_symlink() {
+                anyhow::bail!("{} is already a symlink", path.display());
+            }
+            if !path.exists() {
+                anyhow::bail!("{} does not exist", path.display());
+            }
+            if path.starts_with(stow_dir) {
+                anyhow::bail!("{} is already inside {}", path.display(), stow_dir.display());
+            }
+    
+            let relative = path
+                .strip_prefix(root)
+                .ok()
+                .filter(|r| !r.as_os_str().is_empty())
+                .with_context(|| format!("{} is not inside the package target {}", path.display(), root.display()))?;
+            if relative.ancestors().any(|a| !a.as_os_str().is_empty() && config.is_ignored(a)) {
+                anyhow::bail!("{} is ignored by the package rules", relative.display());
+            }
+            if let Some(other) = plan.links.iter().find(|l| l.target.starts_with(path) || path.starts_with(&l.target)) {
+                anyhow::bail!("{} overlaps {}", path.display(), other.target.display());
+            }
+    
+            let source = pkg_dir.join(relative);
+            if source.exists() || source.is_symlink() {
+                anyhow::bail!("{} already exists in the package", relative.display());
+            }
+    
+            if let Some(parent) = source.parent() {
+                let mut missing: Vec<PathBuf> = parent
+                    .ancestors()
+                    .take_while(|d| !d.exists())
+                    .filter(|d| !plan.dirs.iter().any(|p| p == d))
+                    .map(Path::to_path_buf)
+                    .collect();
+                missing.reverse();
+                plan.dirs.extend(missing);
+            }
+    
+            plan.links.push(PlannedLink {
+                source,
+                target: path.clone(),
+                relative: relative.to_path_buf(),
+                action: Action::Adopt,
+                folded: path.is_dir(),
+            });
+        }
+    
+        Ok(plan)
+    }
+    
+    pub fn conflicts(&self) -> usize {
+        self.links.iter().filter(|l| l.action == Action::Conflict).count()
+    }
+    
+    /// True when applying would change nothing
+    pub fn is_noop(&self) -> bool {
+        self.unfolds.is_empty() && self.links.iter().all(|l| l.action == Action::AlreadyLinked)
+    }
+    
+    /// Resolve every conflict with `policy`, or ask for each one when there
+    /// is none. Returns false if the user quit.
+    pub fn resolve_conflicts(&mut self, policy: Option<ConflictPolicy>) -> Result<bool> {
+        let conflicts = self.links.iter_mut().filter(|l| l.action == Action::Conflict);
+    
+        if let Some(policy) = policy {
+            let mut failed = Vec::new();
+            for link in conflicts {
+                match policy {
+                    ConflictPolicy::Backup => link.action = Action::Backup,
+                    ConflictPolicy::Skip => link.action = Action::Skip,
+                    ConflictPolicy::Overwrite => link.action = Action::Overwrite,
+                    ConflictPolicy::Fail => failed.push(link.target.display().to_string()),
+                }
+            }
+            if !failed.is_empty() {
+                anyhow::bail!("{} conflicts: {}", failed.len(), failed.join(", "));
+            }
+            return Ok(true);
+        }
+    
+        for link in conflicts {
+            let conflict = Conflict::new(link.target.clone(), link.source.clone());
+    
+            link.action = match resolve_conflict(&conflict)? {
+                ConflictAction::Backup => Action::Backup,
+                ConflictAction::Skip => Action::Skip,
+                ConflictAction::Overwrite => Action::Overwrite,
+                ConflictAction::Quit => return Ok(false),
+            };
+        }
+    
+        Ok(true)
+    }
+    
+    /// Print the plan
+    pub fn print(&self) {
+        for dir in &self.dirs {
+            println!("    {} {}/", "+".bright_green(), dir.display().to_string().bright_black());
+        }
+        for unfold in &self.unfolds {
+            println!(
+                "    {} {}/ {}",
+                "⇲".bright_blue(),
+                unfold.target.display(),
+                format!("(unfold, shared with {})", unfold.source.display()).bright_black()
+            );
+        }
+    
+        for link in &self.links {
+            let path = if link.folded {
+                format!("{}/", link.relative.display())
+            } else {
+                link.relative.display().to_string()
+            };
+            match link.action {
+                Action::Create => println!("    {} {}", "→".bright_green(), path),
+                Action::AlreadyLinked => println!("    {} {} {}", "=".bright_black(), path, "(already linked)".bright_black()),
+                Action::Conflict => println!("    {} {} {}", "⚠️".bright_yellow(), path, "(conflict)".bright_yellow()),
+                Action::Backup => println!("    {} {} {}", "💾".bright_blue(), path, "(backup, then link)".bright_black()),
+                Action::Overwrite => println!("    {} {} {}", "✗".bright_red(), path, "(overwrite)".bright_red()),
+                Action::Skip => println!("    {} {}", "⊘".bright_yellow(), path),
+                Action::Adopt => println!("    {} {} {}", "📥".bright_blue(), path, "(move into package, then link)".bright_black()),
+            }
+        }
+    
+        let count = |action| self.links.iter().filter(|l| l.action == action).count();
+        println!();
+        println!(
+            "  Plan: {} to link, {} already linked, {} conflicts",
+            (count(Action::Create) + count(Action::Adopt)).to_string().bright_green(),
+            count(Action::AlreadyLinked),
+            if self.conflicts() > 0 {
+                self.conflicts().to_string().bright_yellow()
+            } else {
+                "0".normal()
+            }
+        );
+    }
+    
+    /// Apply every step, or none: on failure all changes made so far are undone
+    pub fn apply(&self) -> Result<Summary> {
+        if self.conflicts() > 0 {
+            anyhow::bail!("{} unresolved conflicts", self.conflicts());
+        }
+    
+        let mut tx = Transaction::default();
+        match self.apply_steps(&mut tx) {
+            Ok(summary) => {
+                tx.commit();
+                Ok(summary)
+            }
+            Err(e) => {
+                println!("  {} {}", "✗".bright_red(), e);
+                println!("  {} Rolling back...", "↩".bright_yellow());
+                tx.rollback();
+                Err(e.context("Stow failed, all changes were rolled back"))
+            }
+        }
+    }
+    
+    fn apply_steps(&self, tx: &mut Transaction) -> Result<Summary> {
+        let mut summary = Summary::default();
+    
+        for dir in &self.dirs {
+            fs::create_dir(dir).with_context(|| format!("Cannot create {}", dir.display()))?;
+            tx.undo.push(Undo::RemoveDir(dir.clone()));
+        }
+    
+        for unfold in &self.unfolds {
+            unfold_link(unfold, tx)
+                .with_context(|| format!("Cannot unfold {}", unfold.target.display()))?;
+            summary.unfolded += 1;
+        }
+    
+        for link in &self.links {
+            match link.action {
+                Action::AlreadyLinked => {
+                    summary.unchanged += 1;
+                    continue;
+                }
+                Action::Skip | Action::Conflict => {
+                    summary.skipped.push(link.target.clone());
+                    continue;
+                }
+                Action::Backup => {
+                    let backup = backup::create(&link.target)
+                        .with_context(|| format!("Cannot back up {}", link.target.display()))?;
+                    tx.undo.push(Undo::Unbackup { id: backup.id.clone(), target: link.target.clone() });
+                    summary.backed_up.push(BackedUp { target: link.target.clone(), backup: backup.id });
+                }
+                Action::Overwrite => {
+                    // Keep the original aside until the whole stow succeeded
+                    let aside = aside_path(&link.target);
+                    move_path(&link.target, &aside)
+                        .with_context(|| format!("Cannot replace {}", link.target.display()))?;
+                    tx.undo.push(Undo::Restore { from: aside.clone(), to: link.target.clone() });
+                    tx.discard.push(aside);
+                    summary.overwritten.push(link.target.clone());
+                }
+                Action::Adopt => {
+                    move_path(&link.target, &link.source)
+                        .with_context(|| format!("Cannot move {} into the package", link.target.display()))?;
+                    tx.undo.push(Undo::Restore { from: link.source.clone(), to: link.target.clone() });
+                }
+                Action::Create => {}
+            }
+    
+            symlink(&link.source, &link.target)
+                .with_context(|| format!("Cannot link {}", link.target.display()))?;
+            tx.undo.push(Undo::RemoveLink(link.target.clone()));
+            if link.action == Action::Adopt {
+                summary.adopted.push(link.target.clone());
+            } else {
+                summary.created.push(link.target.clone());
+            }
+            if link.folded {
+                summary.folded += 1;
+            }
+        }
+    
+        Ok(summary)
+    }
+}
+
+impl Summary {
+    pub fn print(&self) {
+        println!();
+        if !self.created.is_empty() {
+            println!("  {} Created: {}", "✅".bright_green(), self.created.len());
+        }
+        if !self.adopted.is_empty() {
+            println!("  {} Adopted: {}", "📥".bright_blue(), self.adopted.len());
+        }
+        if self.folded > 0 {
+            println!("  {} Folded directories: {}", "📁".bright_blue(), self.folded);
+        }
+        if self.unfolded > 0 {
+            println!("  {} Unfolded: {}", "⇲".bright_blue(), self.unfolded);
+        }
+        if !self.backed_up.is_empty() {
+            println!("  {} Backed up: {}", "💾".bright_blue(), self.backed_up.len());
+        }
+        if !self.overwritten.is_empty() {
+            println!("  {} Overwritten: {}", "✗".bright_red(), self.overwritten.len());
+        }
+        if !self.skipped.is_empty() {
+            println!("  {} Skipped: {}", "⊘".bright_yellow(), self.skipped.len());
+        }
+        if self.unchanged > 0 {
+            println!("  {} Already linked: {}", "=".bright_black(), self.unchanged);
+        }
+    }
+}
+
+/// Directories that are shared by too many programs to ever be a package symlink
+const NO_FOLD: &[&str] = &[".config", ".local", ".local/share", ".local/state", ".local/bin", ".cache"];
+
+/// What currently occupies a target path
+enum Existing {
+    Missing,
+    /// Symlink, with its destination resolved to an absolute path
+    Link(PathBuf),
+    Dir,
+    Other,
+}
+
+fn existing(target: &Path) -> Existing {
+    // Resolved the same way as the manifest, so GNU stow's relative links match
+    if let Some(dest) = manifest::link_dest(target) {
+        return Existing::Link(dest);
+    }
+    
+    match fs::metadata(target) {
+        Ok(m) if m.is_dir() => Existing::Dir,
+        Ok(_) => Existing::Other,
+        Err(_) => Existing::Missing,
+    }
+}
+
+struct Planner<'a> {
+    home: &'a Path,
+    root: &'a Path,
+    pkg_dir: &'a Path,
+    package: String,
+    stow_dir: &'a Path,
+    config: &'a PackageConfig,
+    plan: Plan,
+}
+
+impl Planner<'_> {
+    /// Plan every entry of `src_dir` into `target_dir`. `unfolded_from` is set
+    /// when `target_dir` is a folded link that will be unfolded first, so its
+    /// future contents mirror that directory.
+    fn plan_dir(&mut self, src_dir: &Path, target_dir: &Path, unfolded_from: Option<&Path>) -> Result<()> {
+        let mut entries: Vec<PathBuf> = fs::read_dir(src_dir)?
+            .map(|e| e.map(|e| e.path()))
+            .collect::<std::io::Result<_>>()?;
+        entries.sort();
+    
+        for source in entries {
+            let Some(name) = source.file_name() else {
+                continue;
+            };
+            if self.config.is_ignored(source.strip_prefix(self.pkg_dir)?) {
+                continue;
+            }
+            let is_dir = source.is_dir() && !source.is_symlink();
+    
+            // Templates are linked to their rendered output, without the extension
+            let (source, target) = if !is_dir && template::is_template(&source) {
+                let rendered = template::output_path(self.home, &self.package, source.strip_prefix(self.pkg_dir)?);
+                (rendered, target_dir.join(Path::new(name).with_extension("")))
+            } else {
+                (source.clone(), target_dir.join(name))
+            };
+            let relative = target.strip_prefix(self.root)?.to_path_buf();
+            // A folded link would expose ignored files and raw templates, so such directories are created instead
+            let no_fold = is_no_fold(&target, self.home) || (is_dir && self.needs_real_dir(&source));
+    
+            let state = match unfolded_from {
+                Some(other) => {
+                    let other = other.join(name);
+                    if other.exists() || other.is_symlink() {
+                        Existing::Link(other)
+                    } else {
+                        Existing::Missing
+                    }
+                }
+                None => existing(&target),
+            };
+    
+            match state {
+                Existing::Missing if is_dir && no_fold => {
+                    self.plan.dirs.push(target.clone());
+                    self.plan_dir(&source, &target, None)?;
+                }
+                Existing::Missing => self.push(source, target, relative, Action::Create, is_dir),
+                Existing::Link(dest) if dest == source => {
+                    self.push(source, target, relative, Action::AlreadyLinked, is_dir);
+                }
+                // Another package's folded directory: split it and share
+                Existing::Link(dest) if is_dir && dest.is_dir() && dest.starts_with(self.stow_dir) => {
+                    self.plan.unfolds.push(Unfold { target: target.clone(), source: dest.clone() });
+                    self.plan_dir(&source, &target, Some(&dest))?;
+                }
+                Existing::Dir if is_dir => self.plan_dir(&source, &target, None)?,
+                _ => self.push(source, target, relative, Action::Conflict, false),
+            }
+        }
+    
+        Ok(())
+    }
+    
+    fn needs_real_dir(&self, dir: &Path) -> bool {
+        WalkDir::new(dir)
+            .min_depth(1)
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .any(|e| {
+                template::is_template(e.path())
+                    || e.path().strip_prefix(self.pkg_dir).is_ok_and(|rel| self.config.is_ignored(rel))
+            })
+    }
+    
+    fn push(&mut self, source: PathBuf, target: PathBuf, relative: PathBuf, action: Action, folded: bool) {
+        self.plan.links.push(PlannedLink {
+            source,
+            target,
+            relative,
+            action,
+            folded,
+        });
+    }
+}
+
+fn is_no_fold(target: &Path, home: &Path) -> bool {
+    target
+        .strip_prefix(home)
+        .is_ok_and(|relative| NO_FOLD.iter().any(|dir| relative == Path::new(dir)))
+}
+
+/// Replace a folded directory link with a directory of per-entry links
+fn unfold_link(unfold: &Unfold, tx: &mut Transaction) -> Result<()> {
+    fs::remove_file(&unfold.target)?;
+    tx.undo.push(Undo::Relink { link: unfold.target.clone(), dest: unfold.source.clone() });
+    
+    fs::create_dir(&unfold.target)?;
+    tx.undo.push(Undo::RemoveDir(unfold.target.clone()));
+    
+    for entry in fs::read_dir(&unfold.source)? {
+        let entry = entry?;
+        let link = unfold.target.join(entry.file_name());
+        symlink(entry.path(), &link)?;
+        tx.undo.push(Undo::RemoveLink(link));
+    }
+    
+    Ok(())
+}
+
+/// Turn a directory that only holds links into one package directory back
+/// into a single folded link. Returns the package directory it now links to.
+pub fn refold(dir: &Path, root: &Path, home: &Path, stow_dir: &Path) -> Result<Option<PathBuf>> {
+    let Ok(relative) = dir.strip_prefix(root) else {
+        return Ok(None);
+    };
+    if relative.as_os_str().is_empty() || is_no_fold(dir, home) || dir.is_symlink() || !dir.is_dir() {
+        return Ok(None);
+    }
+    
+    let mut source_dir: Option<PathBuf> = None;
+    let mut links = Vec::new();
+    
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let Existing::Link(dest) = existing(&entry.path()) else {
+            return Ok(None);
+        };
+        let (Some(parent), Some(name)) = (dest.parent(), dest.file_name()) else {
+            return Ok(None);
+        };
+        if name != entry.file_name() || !parent.starts_with(stow_dir) {
+            return Ok(None);
+        }
+    
+        match &source_dir {
+            Some(current) if current != parent => return Ok(None),
+            Some(_) => {}
+            None => source_dir = Some(parent.to_path_buf()),
+        }
+        links.push(entry.path());
+    }
+    
+    // Only fold when the package directory holds exactly these entries
+    let Some(source_dir) = source_dir else {
+        return Ok(None);
+    };
+    if fs::read_dir(&source_dir)?.count() != links.len() {
+        return Ok(None);
+    }
+    
+    for link in &links {
+        fs::remove_file(link)?;
+    }
+    fs::remove_dir(dir)?;
+    symlink(&source_dir, dir)?;
+    Ok(Some(source_dir))
+}
+
+/// Hidden sibling holding an overwritten file until the stow commits
+fn aside_path(target: &Path) -> PathBuf {
+    let name = target.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    target.with_file_name(format!(".{}.faelight-link-old", name))
+}
+
+/// Reverse of a completed step
+enum Undo {
+    RemoveDir(PathBuf),
+    RemoveLink(PathBuf),
+    Relink { link: PathBuf, dest: PathBuf },
+    Restore { from: PathBuf, to: PathBuf },
+    Unbackup { id: String, target: PathBuf },
+}
+
+#[derive(Default)]
+struct Transaction {
+    undo: Vec<Undo>,
+    /// Overwritten originals, deleted once everything succeeded
+    discard: Vec<PathBuf>,
+}
+
+impl Transaction {
+    fn commit(self) {
+        for path in self.discard {
+            let result = if path.is_dir() && !path.is_symlink() {
+                fs::remove_dir_all(&path)
+            } else {
+                fs::remove_file(&path)
+            };
+            if let Err(e) = result {
+                eprintln!("    {} Could not remove {} ({})", "⚠️".bright_yellow(), path.display(), e);
+            }
+        }
+    }
+    
+    fn rollback(self) {
+        for step in self.undo.into_iter().rev() {
+            let result = match &step {
+                Undo::RemoveLink(link) => fs::remove_file(link).map_err(anyhow::Error::from),
+                Undo::Relink { link, dest } => symlink(dest, link).map_err(anyhow::Error::from),
+                Undo::Restore { from, to } => move_path(from, to),
+                Undo::Unbackup { id, .. } => backup::restore(id).map(|_| ()),
+                // Only removes directories we created and left empty
+                Undo::RemoveDir(dir) => fs::remove_dir(dir).map_err(anyhow::Error::from),
+            };
+    
+            if let Err(e) = result {
+                let path = match &step {
+                    Undo::RemoveLink(p) | Undo::RemoveDir(p) => p,
+                    Undo::Relink { link: p, .. } | Undo::Restore { to: p, .. } => p,
+                    Undo::Unbackup { target: p, .. } => p,
+                };
+                eprintln!("    {} Rollback failed for {} ({})", "✗".bright_red(), path.display(), e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn setup(name: &str) -> (PathBuf, PathBuf, PathBuf) {
+        let root = std::env::temp_dir().join(format!("faelight-link-{}-{}", name, std::process::id()));
+        let stow = root.join("stow");
+        let home = root.join("home");
+        fs::create_dir_all(stow.join("a/.config/app")).unwrap();
+        fs::create_dir_all(&home).unwrap();
+        fs::write(stow.join("a/.config/app/a.conf"), "a").unwrap();
+        (root, stow, home)
+    }
+    
+    #[test]
+    fn test_failed_apply_rolls_back() {
+        let (root, stow, home) = setup("rollback");
+        fs::write(stow.join("a/.config/app/b.conf"), "b").unwrap();
+    
+        let mut plan = Plan::build(&stow.join("a"), &home, &home, &stow, &PackageConfig::default()).unwrap();
+        assert_eq!(plan.dirs, vec![home.join(".config")]);
+        assert!(plan.links[0].folded);
+    
+        // Make the second step fail halfway through
+        plan.links.push(PlannedLink {
+            source: stow.join("a/.config/app"),
+            target: home.join(".config/app"),
+            relative: PathBuf::from(".config/app"),
+            action: Action::Create,
+            folded: true,
+        });
+    
+        assert!(plan.apply().is_err());
+        assert!(!home.join(".config").exists());
+    
+        fs::remove_dir_all(&root).unwrap();
+    }
+    
+    #[test]
+    fn test_fold_and_unfold() {
+        let (root, stow, home) = setup("fold");
+        fs::create_dir_all(stow.join("b/.config/app")).unwrap();
+        fs::write(stow.join("b/.config/app/b.conf"), "b").unwrap();
+    
+        Plan::build(&stow.join("a"), &home, &home, &stow, &PackageConfig::default()).unwrap().apply().unwrap();
+        assert_eq!(fs::read_link(home.join(".config/app")).unwrap(), stow.join("a/.config/app"));
+    
+        // Package b shares .config/app, so a's folded link is split up
+        let plan = Plan::build(&stow.join("b"), &home, &home, &stow, &PackageConfig::default()).unwrap();
+        assert_eq!(plan.unfolds.len(), 1);
+        plan.apply().unwrap();
+    
+        assert!(!home.join(".config/app").is_symlink());
+        assert_eq!(fs::read_link(home.join(".config/app/a.conf")).unwrap(), stow.join("a/.config/app/a.conf"));
+        assert_eq!(fs::read_link(home.join(".config/app/b.conf")).unwrap(), stow.join("b/.config/app/b.conf"));
+    
+        // Once b is gone the directory folds back into a's link
+        fs::remove_file(home.join(".config/app/b.conf")).unwrap();
+        assert!(refold(&home.join(".config/app"), &home, &home, &stow).unwrap().is_some());
+        assert_eq!(fs::read_link(home.join(".config/app")).unwrap(), stow.join("a/.config/app"));
+    
+        fs::remove_dir_all(&root).unwrap();
+    }
+    
+    #[test]
+    fn test_relative_links() {
+        let (root, stow, home) = setup("relative");
+        fs::create_dir_all(home.join(".config/app")).unwrap();
+        symlink("../../../stow/a/.config/app/a.conf", home.join(".config/app/a.conf")).unwrap();
+    
+        // A link GNU stow made is already linked, not a conflict
+        let plan = Plan::build(&stow.join("a"), &home, &home, &stow, &PackageConfig::default()).unwrap();
+        assert_eq!(plan.conflicts(), 0);
+        assert_eq!(plan.links[0].action, Action::AlreadyLinked);
+    
+        assert_eq!(refold(&home.join(".config/app"), &home, &home, &stow).unwrap(), Some(stow.join("a/.config/app")));
+        assert_eq!(fs::read_link(home.join(".config/app")).unwrap(), stow.join("a/.config/app"));
+    
+        fs::remove_dir_all(&root).unwrap();
+    }
+    
+    #[test]
+    fn test_conflict_policy() {
+        let (root, stow, home) = setup("policy");
+        fs::create_dir_all(home.join(".config/app")).unwrap();
+        fs::write(home.join(".config/app/a.conf"), "mine").unwrap();
+    
+        let mut plan = Plan::build(&stow.join("a"), &home, &home, &stow, &PackageConfig::default()).unwrap();
+        assert_eq!(plan.conflicts(), 1);
+        assert!(plan.resolve_conflicts(Some(ConflictPolicy::Fail)).is_err());
+    
+        assert!(plan.resolve_conflicts(Some(ConflictPolicy::Skip)).unwrap());
+        assert_eq!(plan.links[0].action, Action::Skip);
+        let summary = plan.apply().unwrap();
+        assert_eq!(summary.skipped, vec![home.join(".config/app/a.conf")]);
+        assert_eq!(fs::read_to_string(home.join(".config/app/a.conf")).unwrap(), "mine");
+    
+        fs::remove_dir_all(&root).unwrap();
+    }
+    
+    #[test]
+    fn test_adopt() {
+        let (root, stow, home) = setup("adopt");
+        fs::create_dir_all(home.join(".config/new")).unwrap();
+        fs::write(home.join(".config/new/new.conf"), "new").unwrap();
+    
+        let target = home.join(".config/new");
+        let plan = Plan::adopt(std::slice::from_ref(&target), &stow.join("b"), &home, &stow, &PackageConfig::default()).unwrap();
+        assert_eq!(plan.dirs, vec![stow.join("b"), stow.join("b/.config")]);
+        assert!(Plan::adopt(&[target.clone(), target.join("new.conf")], &stow.join("b"), &home, &stow, &PackageConfig::default()).is_err());
+    
+        let summary = plan.apply().unwrap();
+        assert_eq!(summary.adopted, vec![target.clone()]);
+        assert_eq!(fs::read_link(&target).unwrap(), stow.join("b/.config/new"));
+        assert_eq!(fs::read_to_string(stow.join("b/.config/new/new.conf")).unwrap(), "new");
+    
+        // Adopting a link stow made is refused
+        assert!(Plan::adopt(&[target], &stow.join("c"), &home, &stow, &PackageConfig::default()).is_err());
+    
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
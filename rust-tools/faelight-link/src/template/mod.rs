@@ -0,0 +1,248 @@
+use anyhow::{Context, Result};
+use colored::*;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+use crate::config::{hostname, PackageConfig};
+
+/// Package files with this extension are rendered, then linked without it
+const TEMPLATE_EXT: &str = "tmpl";
+
+/// Rendered templates live here, one directory per package
+const RENDER_DIR: &str = ".local/state/faelight-link/rendered";
+
+const DEFAULT_THEME: &str = "faelight-forest";
+
+pub type Vars = BTreeMap<String, String>;
+
+/// A template and its rendered output
+#[derive(Debug)]
+pub struct Rendered {
+    pub template: PathBuf,
+    pub output: PathBuf,
+    pub content: String,
+    /// What is currently on disk
+    pub current: Option<String>,
+}
+
+impl Rendered {
+    /// The output on disk doesn't match the template and variables
+    pub fn is_stale(&self) -> bool {
+        self.current.as_ref() != Some(&self.content)
+    }
+}
+
+pub fn is_template(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == TEMPLATE_EXT)
+}
+
+/// Where the template at `relative` inside `package` is rendered to
+pub fn output_path(home: &Path, package: &str, relative: &Path) -> PathBuf {
+    home.join(RENDER_DIR).join(package).join(relative.with_extension(""))
+}
+
+/// Template variables: `host`, the `[system]` table of
+/// ~/.config/faelight/config.toml as `system.*` (plus `theme` and `profile`),
+/// and the active theme from themes.toml (`colors.accent`, `bar.bg`, ...)
+pub fn variables(home: &Path) -> Result<Vars> {
+    let dir = home.join(".config/faelight");
+    let mut vars = Vars::new();
+    vars.insert("host".to_string(), hostname());
+    
+    let config = read_table(&dir.join("config.toml"))?;
+    if let Some(toml::Value::Table(system)) = config.get("system") {
+        flatten("system", system, &mut vars);
+    }
+    
+    let theme = vars.get("system.theme").cloned().unwrap_or_else(|| DEFAULT_THEME.to_string());
+    let profile = vars.get("system.default_profile").cloned().unwrap_or_else(|| "default".to_string());
+    
+    let themes = read_table(&dir.join("themes.toml"))?;
+    if let Some(toml::Value::Table(table)) = themes.get(&theme) {
+        flatten("", table, &mut vars);
+    }
+    
+    vars.insert("theme".to_string(), theme);
+    vars.insert("profile".to_string(), profile);
+    Ok(vars)
+}
+
+fn read_table(path: &Path) -> Result<toml::Table> {
+    if !path.exists() {
+        return Ok(toml::Table::new());
+    }
+    let content = fs::read_to_string(path).with_context(|| format!("Cannot read {}", path.display()))?;
+    content.parse().with_context(|| format!("Invalid {}", path.display()))
+}
+
+fn flatten(prefix: &str, table: &toml::Table, vars: &mut Vars) {
+    for (key, value) in table {
+        let name = if prefix.is_empty() { key.clone() } else { format!("{}.{}", prefix, key) };
+        match value {
+            toml::Value::Table(inner) => flatten(&name, inner, vars),
+            toml::Value::String(s) => {
+                vars.insert(name, s.clone());
+            }
+            other => {
+                vars.insert(name, other.to_string());
+            }
+        }
+    }
+}
+
+/// Replace every `{{ name }}`. Unknown names are an error.
+pub fn render(template: &str, vars: &Vars) -> Result<String> {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    
+    while let Some(start) = rest.find("{{") {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let end = after.find("}}").context("Unterminated '{{'")?;
+    
+        let name = after[..end].trim();
+        let value = vars.get(name).with_context(|| format!("Unknown variable '{}'", name))?;
+        out.push_str(value);
+        rest = &after[end + 2..];
+    }
+    
+    out.push_str(rest);
+    Ok(out)
+}
+
+/// Render every template of a package in memory
+pub fn render_package(pkg_dir: &Path, home: &Path, config: &PackageConfig, vars: &Vars) -> Result<Vec<Rendered>> {
+    let package = pkg_dir.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    let mut rendered = Vec::new();
+    
+    let entries = WalkDir::new(pkg_dir)
+        .min_depth(1)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|e| e.path().strip_prefix(pkg_dir).is_ok_and(|rel| !config.is_ignored(rel)))
+        .filter_map(|e| e.ok());
+    
+    for entry in entries {
+        if !entry.file_type().is_file() || !is_template(entry.path()) {
+            continue;
+        }
+    
+        let relative = entry.path().strip_prefix(pkg_dir)?;
+        let template = fs::read_to_string(entry.path())
+            .with_context(|| format!("Cannot read {}", entry.path().display()))?;
+        let content = render(&template, vars)
+            .with_context(|| format!("Cannot render {}", entry.path().display()))?;
+        let output = output_path(home, &package, relative);
+    
+        rendered.push(Rendered {
+            template: entry.path().to_path_buf(),
+            current: fs::read_to_string(&output).ok(),
+            output,
+            content,
+        });
+    }
+    
+    Ok(rendered)
+}
+
+/// Write stale outputs. Returns how many were written.
+pub fn write(rendered: &[Rendered]) -> Result<usize> {
+    let mut written = 0;
+    
+    for r in rendered.iter().filter(|r| r.is_stale()) {
+        if let Some(parent) = r.output.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&r.output, &r.content).with_context(|| format!("Cannot write {}", r.output.display()))?;
+        written += 1;
+    }
+    
+    Ok(written)
+}
+
+/// `faelight-link render [package] [--diff]`
+pub fn render_cmd(package: Option<&str>, diff: bool) -> Result<()> {
+    let home = PathBuf::from(std::env::var("HOME").context("HOME not set")?);
+    let stow_dir = crate::package::get_stow_dir()?;
+    let vars = variables(&home)?;
+    
+    let packages = match package {
+        Some(p) => vec![p.to_string()],
+        None => crate::package::discover_packages(&stow_dir)?,
+    };
+    
+    let mut changed = 0;
+    let mut total = 0;
+    
+    for package in &packages {
+        let pkg_dir = stow_dir.join(package);
+        if !pkg_dir.exists() {
+            anyhow::bail!("Package '{}' not found in {}", package, stow_dir.display());
+        }
+    
+        let config = PackageConfig::load(&pkg_dir)?;
+        let rendered = render_package(&pkg_dir, &home, &config, &vars)?;
+        total += rendered.len();
+    
+        for r in rendered.iter().filter(|r| r.is_stale()) {
+            changed += 1;
+            let relative = r.template.strip_prefix(&stow_dir).unwrap_or(&r.template);
+            println!("  {} {}", "~".bright_blue(), relative.display());
+    
+            if diff {
+                print_diff(r.current.as_deref().unwrap_or(""), &r.content, &r.output);
+            }
+        }
+    
+        if !diff {
+            write(&rendered)?;
+        }
+    }
+    
+    println!();
+    if diff {
+        println!("  {} {} of {} templates would change", "📝".bright_blue(), changed, total);
+    } else {
+        println!("  {} Rendered {} of {} templates", "✅".bright_green(), changed, total);
+    }
+    Ok(())
+}
+
+fn print_diff(old: &str, new: &str, output: &Path) {
+    let diff = similar::TextDiff::from_lines(old, new);
+    let path = output.display().to_string();
+    
+    for line in diff.unified_diff().context_radius(2).header(&path, &path).to_string().lines() {
+        let line = if line.starts_with("+++") || line.starts_with("---") {
+            line.bold()
+        } else if line.starts_with('+') {
+            line.bright_green()
+        } else if line.starts_with('-') {
+            line.bright_red()
+        } else if line.starts_with("@@") {
+            line.bright_blue()
+        } else {
+            line.normal()
+        };
+        println!("    {}", line);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_render() {
+        let mut vars = Vars::new();
+        vars.insert("host".to_string(), "forest".to_string());
+        vars.insert("colors.accent".to_string(), "#a3e36b".to_string());
+    
+        let out = render("output {{host}} accent={{ colors.accent }}\n", &vars).unwrap();
+        assert_eq!(out, "output forest accent=#a3e36b\n");
+    
+        assert!(render("{{ missing }}", &vars).is_err());
+        assert!(render("{{ host", &vars).is_err());
+    }
+}
@@ -0,0 +1,203 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use serde::Deserialize;
+use crate::error::{Result, ZoneError};
+use crate::model::Zone;
+
+/// User zone definitions, relative to $HOME
+pub const CONFIG_PATH: &str = ".config/faelight/zones.toml";
+
+/// Used when the user has no zones.toml
+const DEFAULT_ZONES: &str = include_str!("../zones.toml");
+
+/// All zone definitions, in file order
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Zones {
+    #[serde(rename = "zone")]
+    zones: Vec<Zone>,
+}
+
+impl Default for Zones {
+    fn default() -> Self {
+        Self::parse(DEFAULT_ZONES, Path::new("zones.toml")).expect("built-in zones.toml is valid")
+    }
+}
+
+impl Zones {
+    /// Load ~/.config/faelight/zones.toml, or the built-in zones if it doesn't exist
+    pub fn load(home: &Path) -> Result<Self> {
+        let path = home.join(CONFIG_PATH);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let content = fs::read_to_string(&path).map_err(|source| ZoneError::Read { path: path.clone(), source })?;
+        Self::parse(&content, &path)
+    }
+
+    /// Zones of this process, loaded once. Falls back to the built-in zones
+    /// when zones.toml is invalid (`faelight-zone` reports why).
+    pub fn global() -> &'static Zones {
+        static ZONES: OnceLock<Zones> = OnceLock::new();
+        ZONES.get_or_init(|| {
+            let home = std::env::var("HOME").map(PathBuf::from).unwrap_or_else(|_| PathBuf::from("/"));
+            Self::load(&home).unwrap_or_default()
+        })
+    }
+
+    pub fn parse(content: &str, path: &Path) -> Result<Self> {
+        let mut zones: Self = toml::from_str(content)
+            .map_err(|source| ZoneError::Parse { path: path.to_path_buf(), source })?;
+
+        let mut ids = HashSet::new();
+        for zone in &zones.zones {
+            if zone.id.is_empty() {
+                return Err(ZoneError::Invalid("zone without an id".to_string()));
+            }
+            if !ids.insert(zone.id.as_str()) {
+                return Err(ZoneError::Invalid(format!("zone '{}' is defined twice", zone.id)));
+            }
+        }
+
+        // Everything has to land somewhere
+        if !zones.zones.iter().any(Zone::is_fallback) {
+            let scratch = Self::default().fallback().clone();
+            if ids.contains(scratch.id.as_str()) {
+                return Err(ZoneError::Invalid(format!("zone '{}' needs no paths, it is the fallback", scratch.id)));
+            }
+            zones.zones.push(scratch);
+        }
+
+        Ok(zones)
+    }
+
+    pub fn all(&self) -> &[Zone] {
+        &self.zones
+    }
+
+    pub fn get(&self, id: &str) -> Option<&Zone> {
+        self.zones.iter().find(|z| z.id == id)
+    }
+
+    /// Directories of every zone that exist on disk. Nested ones are
+    /// covered by their parent and left out.
+    pub fn roots(&self, home: &Path) -> Vec<PathBuf> {
+        let mut dirs: Vec<PathBuf> = self
+            .zones
+            .iter()
+            .flat_map(|zone| zone.prefixes(home))
+            .filter(|dir| dir.is_dir())
+            .collect();
+        dirs.sort();
+        dirs.dedup();
+
+        dirs.iter()
+            .filter(|dir| !dirs.iter().any(|other| other != *dir && dir.starts_with(other)))
+            .cloned()
+            .collect()
+    }
+
+    /// Zone for paths outside every other zone
+    pub fn fallback(&self) -> &Zone {
+        self.zones.iter().find(|z| z.is_fallback()).expect("zones always have a fallback")
+    }
+
+    /// The zone whose root or extra path is the longest prefix of `path`,
+    /// and how to display the path
+    pub fn detect(&self, path: &Path, home: &Path) -> (&Zone, String) {
+        let best = self
+            .zones
+            .iter()
+            .flat_map(|zone| zone.prefixes(home).into_iter().map(move |prefix| (zone, prefix)))
+            .filter(|(_, prefix)| path.starts_with(prefix))
+            // On equal length the first definition wins
+            .rev()
+            .max_by_key(|(_, prefix)| prefix.components().count());
+
+        match best {
+            Some((zone, prefix)) => (zone, display_path(path, &prefix, zone.is_critical(), home)),
+            None => (self.fallback(), path.display().to_string()),
+        }
+    }
+}
+
+/// Path from the zone directory's name down, e.g. `1-src/alacritty`.
+/// Critical zones are UPPERCASE, and one directly in home shows just its
+/// name wherever inside it the path is, e.g. `0-CORE`.
+fn display_path(path: &Path, prefix: &Path, critical: bool, home: &Path) -> String {
+    let base = prefix.parent().unwrap_or(prefix);
+    let shown = if critical && base == home { prefix } else { path };
+    let rel = shown.strip_prefix(base).unwrap_or(shown).display().to_string();
+    if critical {
+        rel.to_uppercase()
+    } else {
+        rel
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_longest_prefix() {
+        let home = Path::new("/home/test");
+        let zones = Zones::default();
+
+        let (zone, display) = zones.detect(&home.join("0-core/rust-tools/faelight-zone"), home);
+        assert_eq!(zone.id, "workspace");
+        assert_eq!(display, "RUST-TOOLS/FAELIGHT-ZONE");
+
+        let (zone, display) = zones.detect(&home.join("0-core"), home);
+        assert_eq!(zone.id, "core");
+        assert_eq!(display, "0-CORE");
+        let (zone, display) = zones.detect(&home.join("0-core/stow/shell-zsh"), home);
+        assert_eq!(zone.id, "core");
+        assert_eq!(display, "0-CORE");
+
+        let (zone, display) = zones.detect(&home.join("1-src/alacritty"), home);
+        assert_eq!(zone.id, "src");
+        assert_eq!(display, "1-src/alacritty");
+
+        assert_eq!(zones.detect(Path::new("/tmp/x"), home).0.id, "scratch");
+        assert_eq!(zones.detect(&home.join("0-core-old"), home).0.id, "scratch");
+    }
+
+    #[test]
+    fn test_custom_zones() {
+        let home = Path::new("/home/test");
+        let toml = r#"
+            [[zone]]
+            id = "notes"
+            label = "NOTE"
+            icon = "📓"
+            root = "~/notes"
+            paths = ["/srv/notes", "~/notes/private/journal"]
+        "#;
+        let zones = Zones::parse(toml, Path::new("zones.toml")).unwrap();
+
+        assert_eq!(zones.detect(Path::new("/srv/notes/a.md"), home).0.id, "notes");
+        assert_eq!(zones.fallback().id, "scratch");
+        assert_eq!(zones.get("notes").unwrap().rgb(), None);
+        assert_eq!(Zones::default().get("src").unwrap().rgb(), Some((163, 227, 107)));
+
+        let twice = format!("{}{}", toml, toml);
+        assert!(Zones::parse(&twice, Path::new("zones.toml")).is_err());
+    }
+
+    #[test]
+    fn test_roots() {
+        let home = std::env::temp_dir().join(format!("faelight-zone-roots-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&home);
+        fs::create_dir_all(home.join("0-core/rust-tools")).unwrap();
+        fs::create_dir_all(home.join("2-projects")).unwrap();
+
+        // rust-tools is inside 0-core, and 1-src doesn't exist
+        assert_eq!(Zones::default().roots(&home), vec![home.join("0-core"), home.join("2-projects")]);
+
+        fs::remove_dir_all(&home).unwrap();
+    }
+}
@@ -0,0 +1,18 @@
+//! Error types for faelight-zone
+
+use std::path::PathBuf;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum ZoneError {
+    #[error("Cannot read {path}: {source}")]
+    Read { path: PathBuf, source: std::io::Error },
+
+    #[error("Invalid {path}: {source}")]
+    Parse { path: PathBuf, source: toml::de::Error },
+
+    #[error("Invalid zone definitions: {0}")]
+    Invalid(String),
+}
+
+pub type Result<T> = std::result::Result<T, ZoneError>;
@@ -1,7 +1,15 @@
 mod model;
 mod detect;
+mod config;
+mod error;
+mod policy;
+pub mod lock;
 
 pub use model::Zone;
+pub use config::{Zones, CONFIG_PATH};
+pub use error::{Result, ZoneError};
+pub use policy::{can, resolve_path, Decision, Op, Rule};
+pub use lock::{is_core_locked, lock_state, LockInfo, LockState};
 
 use std::path::Path;
 
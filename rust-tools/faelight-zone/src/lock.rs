@@ -0,0 +1,174 @@
+//! Core lock state. The immutable attribute core-protect sets on ~/0-core
+//! is the only source of truth; lock.toml just describes who set it and why.
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use serde::{Deserialize, Serialize};
+
+/// Written by `core-protect lock`, relative to $HOME
+pub const LOCK_INFO_PATH: &str = ".local/state/0-core/lock.toml";
+
+/// How long `lock_state()` reuses its last answer
+const CACHE_TTL: Duration = Duration::from_secs(2);
+
+/// Who locked the Core, when and why
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LockInfo {
+    pub by: String,
+    /// Seconds since the Unix epoch
+    pub at: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+impl LockInfo {
+    pub fn age(&self) -> Duration {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+        now.saturating_sub(Duration::from_secs(self.at))
+    }
+}
+
+/// "by christian 2h ago (release freeze)"
+impl fmt::Display for LockInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let secs = self.age().as_secs();
+        let ago = match secs {
+            0..=59 => "just now".to_string(),
+            60..=3599 => format!("{}m ago", secs / 60),
+            3600..=86399 => format!("{}h ago", secs / 3600),
+            _ => format!("{}d ago", secs / 86400),
+        };
+
+        write!(f, "by {} {}", self.by, ago)?;
+        if let Some(reason) = &self.reason {
+            write!(f, " ({})", reason)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LockState {
+    pub locked: bool,
+    /// Only known when the lock was taken with core-protect
+    pub info: Option<LockInfo>,
+}
+
+impl LockState {
+    /// Read the state of `home`'s Core, bypassing the cache
+    pub fn read(home: &Path) -> Self {
+        let locked = has_immutable_attr(&home.join("0-core"));
+        let info = if locked { read_info(home) } else { None };
+        Self { locked, info }
+    }
+
+    /// Describe a lock that was just taken
+    pub fn record(home: &Path, reason: Option<&str>) -> io::Result<()> {
+        let info = LockInfo {
+            by: std::env::var("SUDO_USER")
+                .or_else(|_| std::env::var("USER"))
+                .unwrap_or_else(|_| "unknown".to_string()),
+            at: SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs(),
+            reason: reason.filter(|r| !r.trim().is_empty()).map(str::to_string),
+        };
+
+        let path = home.join(LOCK_INFO_PATH);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let content = toml::to_string(&info).map_err(io::Error::other)?;
+        fs::write(path, content)?;
+        forget();
+        Ok(())
+    }
+
+    /// Drop the description once the lock is gone
+    pub fn clear(home: &Path) -> io::Result<()> {
+        forget();
+        match fs::remove_file(home.join(LOCK_INFO_PATH)) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        }
+    }
+}
+
+static CACHE: Mutex<Option<(Instant, PathBuf, LockState)>> = Mutex::new(None);
+
+/// State of the Core under $HOME, cached for a couple of seconds so bars
+/// and file listings don't run lsattr on every redraw
+pub fn lock_state() -> LockState {
+    let home = std::env::var("HOME").map(PathBuf::from).unwrap_or_else(|_| PathBuf::from("/"));
+    let mut cache = CACHE.lock().unwrap_or_else(|e| e.into_inner());
+
+    if let Some((at, cached_home, state)) = cache.as_ref() {
+        if at.elapsed() < CACHE_TTL && *cached_home == home {
+            return state.clone();
+        }
+    }
+
+    let state = LockState::read(&home);
+    *cache = Some((Instant::now(), home, state.clone()));
+    state
+}
+
+pub fn is_core_locked() -> bool {
+    lock_state().locked
+}
+
+/// Make the next `lock_state()` read the filesystem again
+fn forget() {
+    *CACHE.lock().unwrap_or_else(|e| e.into_inner()) = None;
+}
+
+fn has_immutable_attr(path: &Path) -> bool {
+    Command::new("lsattr")
+        .arg("-d")
+        .arg(path)
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .and_then(|o| {
+            // "----i----------------- /home/user/0-core": only look at the flags
+            String::from_utf8_lossy(&o.stdout)
+                .split_whitespace()
+                .next()
+                .map(|attrs| attrs.contains('i'))
+        })
+        .unwrap_or(false)
+}
+
+fn read_info(home: &Path) -> Option<LockInfo> {
+    let content = fs::read_to_string(home.join(LOCK_INFO_PATH)).ok()?;
+    toml::from_str(&content).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lock_info() {
+        let home = std::env::temp_dir().join(format!("faelight-zone-lock-{}", std::process::id()));
+        fs::create_dir_all(home.join("0-core")).unwrap();
+
+        LockState::record(&home, Some("release freeze")).unwrap();
+        let info = read_info(&home).unwrap();
+        assert_eq!(info.reason.as_deref(), Some("release freeze"));
+        assert!(info.age() < Duration::from_secs(60));
+        assert!(info.to_string().ends_with("just now (release freeze)"));
+
+        // Without the attribute the description means nothing
+        assert_eq!(LockState::read(&home), LockState { locked: false, info: None });
+
+        LockState::clear(&home).unwrap();
+        assert!(read_info(&home).is_none());
+        LockState::clear(&home).unwrap();
+
+        fs::remove_dir_all(&home).unwrap();
+    }
+}
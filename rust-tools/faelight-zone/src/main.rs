@@ -1,15 +1,51 @@
 use std::env;
-use std::path::PathBuf;
-use faelight_zone::current_zone;
+use std::path::{Path, PathBuf};
+use std::process;
+use faelight_zone::{Decision, Op, Zones};
 
 fn main() {
-    let cwd = env::current_dir().unwrap_or_else(|_| PathBuf::from("/"));
+    let args: Vec<String> = env::args().skip(1).collect();
     let home = env::var("HOME")
         .map(PathBuf::from)
         .unwrap_or_else(|_| PathBuf::from("/"));
 
-    let (zone, path) = current_zone(&cwd, &home);
+    let zones = Zones::load(&home).unwrap_or_else(|e| {
+        eprintln!("faelight-zone: using the built-in zones. {}", e.to_string().trim_end());
+        Zones::default()
+    });
 
-    // Output format: "🔒 0-core" (icon + path, no label)
-    println!("{} {}", zone.icon(), path);
+    match args.as_slice() {
+        [] => {
+            let cwd = env::current_dir().unwrap_or_else(|_| PathBuf::from("/"));
+            let (zone, path) = zones.detect(&cwd, &home);
+
+            // Output format: "🔒 0-core" (icon + path, no label)
+            println!("{} {}", zone.icon(), path);
+        }
+        [cmd, op, path] if cmd == "check" => {
+            let op = op.parse::<Op>().unwrap_or_else(|e: String| usage(&e));
+            process::exit(check(&zones, op, Path::new(path), &home));
+        }
+        _ => usage("expected no arguments or 'check <op> <path>'"),
+    }
+}
+
+/// Print the decision. Exit status: 0 allow, 1 deny, 2 confirm.
+fn check(zones: &Zones, op: Op, path: &Path, home: &Path) -> i32 {
+    let path = faelight_zone::resolve_path(path);
+    let decision = zones.can(op, &path, home, faelight_zone::is_core_locked());
+    println!("{}", decision);
+
+    match decision {
+        Decision::Allow => 0,
+        Decision::Deny(_) => 1,
+        Decision::Confirm(_) => 2,
+    }
+}
+
+fn usage(error: &str) -> ! {
+    eprintln!("faelight-zone: {}", error);
+    eprintln!("Usage: faelight-zone                    Show the zone of the current directory");
+    eprintln!("       faelight-zone check <op> <path>  Ask the zone policy (read, write, delete, execute, link, move)");
+    process::exit(64);
 }
@@ -1,37 +1,77 @@
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum Zone {
-    Core,
-    Workspace,
-    Src,
-    Project,
-    Archive,
-    Scratch,
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use serde::Deserialize;
+use crate::policy::{Op, Rule};
+
+/// A named part of the filesystem, as defined in zones.toml
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Zone {
+    /// Stable identifier, e.g. "core"
+    pub id: String,
+    /// Short label for prompts and panels, e.g. "CORE"
+    pub label: String,
+    pub icon: String,
+    /// Main directory of the zone (`~` is $HOME)
+    #[serde(default)]
+    pub root: Option<String>,
+    /// Further directories that belong to the zone
+    #[serde(default)]
+    pub paths: Vec<String>,
+    /// Critical zones display in UPPERCASE and are guarded by the Core lock
+    #[serde(default)]
+    pub critical: bool,
+    /// Display color as "#rrggbb"
+    #[serde(default)]
+    pub color: Option<String>,
+    /// Rules for operations, e.g. `{ delete = "confirm" }`
+    #[serde(default)]
+    pub policy: BTreeMap<Op, Rule>,
 }
 
 impl Zone {
-    pub fn short_label(&self) -> &'static str {
-        match self {
-            Zone::Core      => "CORE",
-            Zone::Workspace => "WORK",
-            Zone::Src       => "SRC",
-            Zone::Project   => "PROJ",
-            Zone::Archive   => "ARCH",
-            Zone::Scratch   => "SCR",
-        }
+    pub fn short_label(&self) -> &str {
+        &self.label
     }
 
-    pub fn icon(&self) -> &'static str {
-        match self {
-            Zone::Core      => "🔒",
-            Zone::Workspace => "🦀",
-            Zone::Src       => "🛠",
-            Zone::Project   => "💼",
-            Zone::Archive   => "💎",
-            Zone::Scratch   => "🧪",
-        }
+    pub fn icon(&self) -> &str {
+        &self.icon
     }
 
     pub fn is_critical(&self) -> bool {
-        matches!(self, Zone::Core | Zone::Workspace)
+        self.critical
+    }
+
+    /// The catch-all zone has no directories of its own
+    pub fn is_fallback(&self) -> bool {
+        self.root.is_none() && self.paths.is_empty()
+    }
+
+    /// Expanded root directory
+    pub fn root_path(&self, home: &Path) -> Option<PathBuf> {
+        self.root.as_deref().map(|r| expand(r, home))
+    }
+
+    /// Every expanded directory of the zone, root first
+    pub fn prefixes(&self, home: &Path) -> Vec<PathBuf> {
+        self.root.iter().chain(&self.paths).map(|p| expand(p, home)).collect()
+    }
+
+    /// Color as RGB, if set and valid
+    pub fn rgb(&self) -> Option<(u8, u8, u8)> {
+        let hex = self.color.as_deref()?.strip_prefix('#')?;
+        if hex.len() != 6 {
+            return None;
+        }
+        let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+        Some((channel(0)?, channel(2)?, channel(4)?))
+    }
+}
+
+fn expand(path: &str, home: &Path) -> PathBuf {
+    match path.strip_prefix('~') {
+        Some("") => home.to_path_buf(),
+        Some(rest) if rest.starts_with('/') => home.join(&rest[1..]),
+        _ => PathBuf::from(path),
     }
 }
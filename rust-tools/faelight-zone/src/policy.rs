@@ -0,0 +1,168 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use serde::Deserialize;
+use crate::config::Zones;
+use crate::model::Zone;
+
+/// Something a tool wants to do to a path
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Op {
+    Read,
+    Write,
+    Delete,
+    Execute,
+    /// Create a symlink at the path
+    Link,
+    /// Move or rename the path away (check the destination with `Write`)
+    Move,
+}
+
+impl Op {
+    pub const ALL: [Op; 6] = [Op::Read, Op::Write, Op::Delete, Op::Execute, Op::Link, Op::Move];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Op::Read => "read",
+            Op::Write => "write",
+            Op::Delete => "delete",
+            Op::Execute => "execute",
+            Op::Link => "link",
+            Op::Move => "move",
+        }
+    }
+
+    /// Changes the filesystem, so a locked Core refuses it
+    pub fn modifies(&self) -> bool {
+        matches!(self, Op::Write | Op::Delete | Op::Link | Op::Move)
+    }
+}
+
+impl FromStr for Op {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Op::ALL
+            .into_iter()
+            .find(|op| op.name() == s)
+            .ok_or_else(|| format!("unknown operation '{}' (read, write, delete, execute, link, move)", s))
+    }
+}
+
+/// Per-zone setting for an operation in zones.toml
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Rule {
+    Allow,
+    Confirm,
+    Deny,
+}
+
+/// Answer of the policy engine
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Decision {
+    Allow,
+    /// Allowed once the user agrees
+    Confirm(String),
+    Deny(String),
+}
+
+impl Decision {
+    pub fn is_allowed(&self) -> bool {
+        matches!(self, Decision::Allow)
+    }
+
+    pub fn is_denied(&self) -> bool {
+        matches!(self, Decision::Deny(_))
+    }
+}
+
+impl fmt::Display for Decision {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Decision::Allow => write!(f, "allow"),
+            Decision::Confirm(reason) => write!(f, "confirm: {}", reason),
+            Decision::Deny(reason) => write!(f, "deny: {}", reason),
+        }
+    }
+}
+
+impl Zone {
+    /// Rule for `op` from zones.toml. Without one, deleting or moving in a
+    /// critical zone needs confirmation and everything else is allowed.
+    pub fn rule(&self, op: Op) -> Rule {
+        match self.policy.get(&op) {
+            Some(rule) => *rule,
+            None if self.critical && matches!(op, Op::Delete | Op::Move) => Rule::Confirm,
+            None => Rule::Allow,
+        }
+    }
+}
+
+impl Zones {
+    /// Decide whether `op` may touch `path`. A locked Core denies every
+    /// modifying operation in critical zones, whatever zones.toml says.
+    pub fn can(&self, op: Op, path: &Path, home: &Path, locked: bool) -> Decision {
+        let (zone, _) = self.detect(path, home);
+        let place = format!("the {} {} zone", zone.icon(), zone.short_label());
+
+        if locked && zone.is_critical() && op.modifies() {
+            return Decision::Deny(format!("{} is locked, run 'core-protect unlock' to {}", place, op.name()));
+        }
+
+        match zone.rule(op) {
+            Rule::Allow => Decision::Allow,
+            Rule::Confirm => Decision::Confirm(format!("{} in {}", op.name(), place)),
+            Rule::Deny => Decision::Deny(format!("{} is not allowed in {}", op.name(), place)),
+        }
+    }
+}
+
+/// Decide whether `op` may touch `path`, using the zones of this process and
+/// the current lock state
+pub fn can(op: Op, path: &Path) -> Decision {
+    let home = std::env::var("HOME").map(PathBuf::from).unwrap_or_else(|_| PathBuf::from("/"));
+    Zones::global().can(op, &resolve_path(path), &home, crate::lock::is_core_locked())
+}
+
+/// Absolute path with symlinks resolved. Paths that don't exist yet are
+/// resolved through their parent.
+pub fn resolve_path(path: &Path) -> PathBuf {
+    let path = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+    if let Ok(real) = path.canonicalize() {
+        return real;
+    }
+
+    match (path.parent().map(Path::canonicalize), path.file_name()) {
+        (Some(Ok(parent)), Some(name)) => parent.join(name),
+        _ => path,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decisions() {
+        let home = Path::new("/home/test");
+        let zones = Zones::default();
+        let core = home.join("0-core/stow/shell-zsh/.zshrc");
+
+        assert_eq!(zones.can(Op::Read, &core, home, true), Decision::Allow);
+        assert!(zones.can(Op::Write, &core, home, true).is_denied());
+        assert!(zones.can(Op::Link, &home.join("0-core/rust-tools/x"), home, true).is_denied());
+        assert_eq!(zones.can(Op::Execute, &core, home, true), Decision::Allow);
+
+        assert_eq!(zones.can(Op::Write, &core, home, false), Decision::Allow);
+        assert!(matches!(zones.can(Op::Delete, &core, home, false), Decision::Confirm(_)));
+
+        // Locks only guard critical zones
+        assert_eq!(zones.can(Op::Delete, Path::new("/tmp/x"), home, true), Decision::Allow);
+        assert!(matches!(zones.can(Op::Write, &home.join("1-src/x"), home, true), Decision::Confirm(_)));
+
+        assert_eq!("move".parse::<Op>(), Ok(Op::Move));
+        assert!("chmod".parse::<Op>().is_err());
+    }
+}
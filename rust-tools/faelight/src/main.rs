@@ -270,11 +270,12 @@ fn cmd_core(action: CoreAction, dry_run: bool) -> i32 {
             exec_script("unlock-core", &[])
         }
         CoreAction::Status => {
-            // Check if locked
-            let home = std::env::var("HOME").unwrap_or_default();
-            let lock_file = format!("{}/.0-core-locked", home);
-            if std::path::Path::new(&lock_file).exists() {
+            let state = faelight_zone::lock_state();
+            if state.locked {
                 println!("🔒 Core is {}", "LOCKED".red());
+                if let Some(info) = state.info {
+                    println!("   Locked {}", info);
+                }
             } else {
                 println!("🔓 Core is {}", "UNLOCKED".green());
             }
@@ -417,7 +418,7 @@ fn cmd_status(json: bool) -> i32 {
         .trim()
         .to_string();
     
-    let locked = std::path::Path::new(&format!("{}/.0-core-locked", home)).exists();
+    let locked = faelight_zone::is_core_locked();
     
     let profile = std::fs::read_to_string(format!("{}/.local/state/faelight/current-profile", home))
         .unwrap_or_else(|_| "default".to_string())
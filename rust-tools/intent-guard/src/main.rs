@@ -5,223 +5,21 @@ use std::env;
 use std::io::{self, Write};
 use std::process;
 
-const VERSION: &str = "1.0.0";
-
-// ============================================================================
-// TYPES & STRUCTURES
-// ============================================================================
-
-#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
-enum RiskLevel {
-    Low,
-    Medium,
-    High,
-    Critical,
-}
-
-impl RiskLevel {
-    fn color(&self) -> &str {
-        match self {
-            RiskLevel::Low => "\x1b[0;34m",      // Blue
-            RiskLevel::Medium => "\x1b[0;33m",   // Yellow
-            RiskLevel::High => "\x1b[0;31m",     // Red
-            RiskLevel::Critical => "\x1b[1;31m", // Bold Red
-        }
-    }
-
-    fn label(&self) -> &str {
-        match self {
-            RiskLevel::Low => "LOW",
-            RiskLevel::Medium => "MEDIUM",
-            RiskLevel::High => "HIGH",
-            RiskLevel::Critical => "CRITICAL",
-        }
-    }
-}
-
-struct Pattern {
-    name: &'static str,
-    description: &'static str,
-    check: fn(&str) -> bool,
-    risk: RiskLevel,
-}
-
-// ============================================================================
-// PATTERN CHECKERS
-// ============================================================================
-
-fn check_rm_rf_root(cmd: &str) -> bool {
-    if !cmd.trim_start().starts_with("rm ") && !cmd.trim_start().starts_with("sudo rm ") {
-        return false;
-    }
-    let has_recursive =
-        cmd.contains("-rf") || cmd.contains("-fr") || (cmd.contains("-r") && cmd.contains("-f"));
-    if !has_recursive {
-        return false;
-    }
-    cmd.contains(" /") || cmd.contains("/*") || cmd.contains(" ~") || cmd.contains("~/")
-}
-
-fn check_rm_rf_core(cmd: &str) -> bool {
-    if !cmd.trim_start().starts_with("rm ") && !cmd.trim_start().starts_with("sudo rm ") {
-        return false;
-    }
-    cmd.contains("~/0-core") || cmd.contains("/home/") && cmd.contains("/0-core")
-}
+use intent_guard::{check, Pattern, RiskLevel, PATTERNS};
 
-fn check_chmod_777(cmd: &str) -> bool {
-    cmd.trim_start().starts_with("chmod ") && cmd.contains("777")
-}
-
-fn check_dd(cmd: &str) -> bool {
-    cmd.trim_start().starts_with("dd ") && (cmd.contains("of=/dev/") || cmd.contains("if=/dev/"))
-}
-
-fn check_pacman_remove(cmd: &str) -> bool {
-    (cmd.trim_start().starts_with("pacman ") || cmd.trim_start().starts_with("sudo pacman "))
-        && (cmd.contains("-R") || cmd.contains("--remove"))
-}
-
-fn check_mv_core(cmd: &str) -> bool {
-    if !(cmd.trim_start().starts_with("mv ") || cmd.trim_start().starts_with("sudo mv ")) {
-        return false;
-    }
-    
-    let parts: Vec<&str> = cmd.split_whitespace().collect();
-    if parts.len() < 3 {
-        return false;
-    }
-    
-    let source_idx = if parts[0] == "sudo" { 2 } else { 1 };
-    if source_idx >= parts.len() {
-        return false;
-    }
-    
-    let source = parts[source_idx];
-    source.contains("0-core")
-}
-
-fn check_mkfs(cmd: &str) -> bool {
-    cmd.trim_start().starts_with("mkfs.") || cmd.trim_start().starts_with("sudo mkfs.")
-}
-
-fn check_systemctl_disable(cmd: &str) -> bool {
-    cmd.trim_start().starts_with("systemctl ")
-        && (cmd.contains("disable ") || cmd.contains("stop "))
-}
-
-fn check_piped_execution(cmd: &str) -> bool {
-    let starts_with_curl = cmd.trim_start().starts_with("curl ");
-    let starts_with_wget = cmd.trim_start().starts_with("wget ");
-    if !starts_with_curl && !starts_with_wget {
-        return false;
-    }
-    cmd.contains("| sh") || cmd.contains("| bash") || cmd.contains("|sh") || cmd.contains("|bash")
-}
-
-fn check_shell_overwrite(cmd: &str) -> bool {
-    if cmd.trim_start().starts_with("echo ")
-        || cmd.trim_start().starts_with("cat ")
-        || cmd.trim_start().starts_with("printf ")
-    {
-        return false;
-    }
-    (cmd.contains("> ~/.zshrc") || cmd.contains("> ~/.bashrc") || cmd.contains("> ~/0-core"))
-        && !cmd.contains(">>")
-}
-
-fn check_find_delete(cmd: &str) -> bool {
-    cmd.trim_start().starts_with("find ") && (cmd.contains("-delete") || cmd.contains("--delete"))
-}
-
-// ============================================================================
-// PATTERN DATABASE
-// ============================================================================
-
-const PATTERNS: &[Pattern] = &[
-    // CRITICAL - Data loss patterns
-    Pattern {
-        name: "rm_rf_dangerous",
-        description: "Recursive delete on critical paths",
-        check: check_rm_rf_root,
-        risk: RiskLevel::Critical,
-    },
-    Pattern {
-        name: "rm_rf_core",
-        description: "Delete 0-Core system",
-        check: check_rm_rf_core,
-        risk: RiskLevel::Critical,
-    },
-    Pattern {
-        name: "mkfs",
-        description: "Format filesystem (DESTRUCTIVE)",
-        check: check_mkfs,
-        risk: RiskLevel::Critical,
-    },
-    Pattern {
-        name: "mv_core",
-        description: "Move 0-Core system",
-        check: check_mv_core,
-        risk: RiskLevel::Critical,
-    },
-    Pattern {
-        name: "shell_overwrite",
-        description: "Overwrite shell configuration",
-        check: check_shell_overwrite,
-        risk: RiskLevel::Critical,
-    },
-    // HIGH - Dangerous system operations
-    Pattern {
-        name: "chmod_777",
-        description: "World-writable permissions",
-        check: check_chmod_777,
-        risk: RiskLevel::High,
-    },
-    Pattern {
-        name: "dd_device",
-        description: "Direct disk write",
-        check: check_dd,
-        risk: RiskLevel::High,
-    },
-    Pattern {
-        name: "piped_execution",
-        description: "Download and execute script",
-        check: check_piped_execution,
-        risk: RiskLevel::High,
-    },
-    Pattern {
-        name: "find_delete",
-        description: "Find with delete flag",
-        check: check_find_delete,
-        risk: RiskLevel::High,
-    },
-    // MEDIUM - Package operations
-    Pattern {
-        name: "pacman_remove",
-        description: "Remove system packages",
-        check: check_pacman_remove,
-        risk: RiskLevel::Medium,
-    },
-    Pattern {
-        name: "systemctl_disable",
-        description: "Disable/stop system service",
-        check: check_systemctl_disable,
-        risk: RiskLevel::Medium,
-    },
-];
+const VERSION: &str = "1.0.0";
 
 // ============================================================================
 // CORE LOGIC
 // ============================================================================
 
 fn check_command(cmd: &str) {
-    let mut matches: Vec<&Pattern> = PATTERNS.iter().filter(|p| (p.check)(cmd)).collect();
+    let matches = check(cmd);
     if matches.is_empty() {
         process::exit(0);
     }
 
-    matches.sort_by(|a, b| b.risk.partial_cmp(&a.risk).unwrap());
-    let highest_risk = &matches[0];
+    let highest_risk = matches[0];
 
     show_warning(cmd, highest_risk);
 
@@ -234,7 +32,7 @@ fn check_command(cmd: &str) {
 }
 
 fn test_command(cmd: &str) {
-    let matches: Vec<&Pattern> = PATTERNS.iter().filter(|p| (p.check)(cmd)).collect();
+    let matches = check(cmd);
     
     let nc = "\x1b[0m";
     